@@ -0,0 +1,107 @@
+//! Compares building the HTML of a large page in a `String` to streaming it
+//! into a writer
+//!
+//! Run with `cargo bench -p sstat -- --nocapture` to see the peak memory used
+//! by each along with its throughput.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sstat::{transpile, transpile_to_writer, Config};
+use test::Bencher;
+
+/// Counts the bytes currently allocated, and the most allocated at once
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A generated reference with many sections, paragraphs and tables
+fn large_page() -> String {
+    let mut page = String::from("[title Reference]\n(doc\n");
+
+    for i in 0..300 {
+        page.push_str(&format!(
+            "(sec (title Item {i})\n\
+             (p The (b item) number {i} is described here, with (mono code) & <markup>.)\n\
+             (table (trow (tcell Name) (tcell item-{i})) (trow (tcell Value) (tcell {i}))))\n"
+        ));
+    }
+    page.push(')');
+
+    page
+}
+
+/// Run a benchmark, printing the peak memory used by a single iteration on
+/// top of what was allocated before it
+fn measure(b: &mut Bencher, name: &str, mut render: impl FnMut() -> usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let len = render();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    eprintln!("{name}: peak memory {} KiB", peak / 1024);
+
+    b.bytes = len as u64;
+    b.iter(render);
+}
+
+#[bench]
+fn string_building(b: &mut Bencher) {
+    let page = large_page();
+    let config = Config::default();
+
+    measure(b, "string_building", || {
+        let output = transpile("page.sstat".to_owned(), page.clone(), &config).unwrap();
+        output.content.len()
+    });
+}
+
+#[bench]
+fn streaming(b: &mut Bencher) {
+    let page = large_page();
+    let config = Config::default();
+
+    measure(b, "streaming", || {
+        let mut len = 0;
+        let writer = BufWriter::new(Counted(&mut len));
+        transpile_to_writer("page.sstat".to_owned(), page.clone(), &config, writer).unwrap();
+        len
+    });
+}
+
+/// Discards everything written to it, counting the bytes
+struct Counted<'a>(&'a mut usize);
+
+impl std::io::Write for Counted<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        *self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
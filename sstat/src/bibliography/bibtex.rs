@@ -0,0 +1,455 @@
+//! BibTeX bibliographies
+
+use std::collections::HashMap;
+
+use super::{BibliographyError, Entry, Name};
+
+/// Macros that are always defined
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Accent commands, along with the combining character they add to a letter
+const ACCENTS: &[(&str, char)] = &[
+    ("`", '\u{300}'),
+    ("'", '\u{301}'),
+    ("^", '\u{302}'),
+    ("~", '\u{303}'),
+    ("=", '\u{304}'),
+    (".", '\u{307}'),
+    ("\"", '\u{308}'),
+    ("c", '\u{327}'),
+    ("v", '\u{30c}'),
+];
+
+/// Commands that are replaced by their own name
+const LOGOS: &[&str] = &["TeX", "LaTeX", "BibTeX"];
+
+/// Parse a BibTeX file
+///
+/// Only the fields needed to format an entry are kept, everything else is
+/// ignored
+pub(super) fn parse(source: &str) -> Result<Vec<Entry>, BibliographyError> {
+    let mut parser = BibtexParser {
+        source,
+        pos: 0,
+        macros: MONTHS
+            .iter()
+            .map(|m| (m.to_string(), m.to_string()))
+            .collect(),
+    };
+
+    let mut entries: Vec<Entry> = vec![];
+
+    // Anything outside of an entry is a comment
+    while let Some(at) = source[parser.pos..].find('@') {
+        parser.pos += at + 1;
+
+        let (kind, _) = parser.identifier()?;
+        let close = parser.open()?;
+
+        match kind.to_lowercase().as_str() {
+            "comment" | "preamble" => parser.skip_to(close)?,
+            "string" => {
+                let (name, value) = parser.field()?;
+                parser.macros.insert(name, value);
+                parser.skip_whitespace();
+                parser.expect(close)?;
+            }
+            _ => {
+                let start = parser.pos;
+                let entry = parser.entry(close)?;
+
+                if entries.iter().any(|e| e.key == entry.key) {
+                    return Err(BibliographyError {
+                        start,
+                        end: start + entry.key.len(),
+                        reason: format!("duplicate entry '{}'", entry.key),
+                    });
+                }
+
+                entries.push(entry);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+struct BibtexParser<'b> {
+    source: &'b str,
+    /// The byte offset of the next character
+    pos: usize,
+    /// All `@string` definitions so far, by their lowercase name
+    macros: HashMap<String, String>,
+}
+
+impl BibtexParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, start: usize, end: usize, reason: impl Into<String>) -> BibliographyError {
+        BibliographyError {
+            start,
+            end,
+            reason: reason.into(),
+        }
+    }
+
+    /// An error for the next character, or the end of the file
+    fn unexpected(&self, expected: &str) -> BibliographyError {
+        match self.peek() {
+            Some(c) => self.error(
+                self.pos,
+                self.pos + c.len_utf8(),
+                format!("unexpected '{c}', expected {expected}"),
+            ),
+            None => self.error(
+                self.pos,
+                self.pos,
+                format!("unexpected end-of-file, expected {expected}"),
+            ),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), BibliographyError> {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            _ => Err(self.unexpected(&format!("'{c}'"))),
+        }
+    }
+
+    /// Take the name of an entry type, field, or macro, along with its start
+    fn identifier(&mut self) -> Result<(String, usize), BibliographyError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        let rest = &self.source[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || "_-:.+/".contains(c)))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.unexpected("a name"));
+        }
+
+        self.pos += len;
+
+        Ok((rest[..len].to_owned(), start))
+    }
+
+    /// Take the delimiter that opens an entry, returning the one that closes it
+    fn open(&mut self) -> Result<char, BibliographyError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                Ok('}')
+            }
+            Some('(') => {
+                self.pos += 1;
+                Ok(')')
+            }
+            _ => Err(self.unexpected("'{' or '('")),
+        }
+    }
+
+    /// Skip past the unbalanced delimiter that closes an entry
+    fn skip_to(&mut self, close: char) -> Result<(), BibliographyError> {
+        let start = self.pos;
+        let mut depth = 0usize;
+
+        for (idx, c) in self.source[start..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                c if c == close && depth == 0 => {
+                    self.pos = start + idx + 1;
+                    return Ok(());
+                }
+                _ => (),
+            }
+        }
+
+        self.pos = self.source.len();
+        Err(self.unexpected(&format!("'{close}'")))
+    }
+
+    /// Parse the key and fields of an entry
+    fn entry(&mut self, close: char) -> Result<Entry, BibliographyError> {
+        self.skip_whitespace();
+
+        let rest = &self.source[self.pos..];
+        let len = rest
+            .find(|c: char| c == ',' || c == close || c.is_whitespace())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.unexpected("a citation key"));
+        }
+        self.pos += len;
+
+        let mut entry = Entry {
+            key: rest[..len].to_owned(),
+            ..Default::default()
+        };
+        let mut fields = HashMap::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+
+                    if self.peek() == Some(close) {
+                        continue;
+                    }
+
+                    let (name, value) = self.field()?;
+                    fields.insert(name, value);
+                }
+                _ => return Err(self.unexpected(&format!("',' or '{close}'"))),
+            }
+        }
+
+        let field = |names: &[&str]| names.iter().find_map(|n| fields.get(*n));
+
+        entry.authors = field(&["author", "editor"])
+            .map(|a| split_names(a).iter().map(|n| parse_name(n)).collect())
+            .unwrap_or_default();
+        entry.title = field(&["title"]).map(|t| clean(t));
+        entry.container = field(&["journal", "booktitle"]).map(|c| clean(c));
+        entry.publisher =
+            field(&["publisher", "institution", "school", "organization"]).map(|p| clean(p));
+        entry.year = field(&["year"])
+            .map(|y| clean(y))
+            .or_else(|| field(&["date"]).map(|d| clean(d.split('-').next().unwrap())));
+        entry.url = field(&["url"])
+            .map(|u| u.trim().to_owned())
+            .or_else(|| field(&["doi"]).map(|d| format!("https://doi.org/{}", d.trim())));
+
+        Ok(entry)
+    }
+
+    /// Parse a `name = value` field, returning the lowercase name and the
+    /// value with all macros expanded
+    ///
+    /// Braces are kept in the value, as they decide how names are split
+    fn field(&mut self) -> Result<(String, String), BibliographyError> {
+        let (name, _) = self.identifier()?;
+
+        self.skip_whitespace();
+        self.expect('=')?;
+
+        let mut value = String::new();
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some('{') => {
+                    self.pos += 1;
+                    value.push_str(self.delimited('}')?);
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    value.push_str(self.delimited('"')?);
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    let rest = &self.source[self.pos..];
+                    let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap();
+                    value.push_str(&rest[..len]);
+                    self.pos += len;
+                }
+                Some(c) if c.is_alphabetic() => {
+                    let (name, start) = self.identifier()?;
+
+                    match self.macros.get(&name.to_lowercase()) {
+                        Some(expansion) => value.push_str(expansion),
+                        None => {
+                            return Err(self.error(
+                                start,
+                                self.pos,
+                                format!("undefined string '{name}'"),
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(self.unexpected("a value")),
+            }
+
+            self.skip_whitespace();
+            if self.peek() != Some('#') {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        Ok((name.to_lowercase(), value))
+    }
+
+    /// Take text up to a closing delimiter that is not nested in braces,
+    /// skipping past the delimiter
+    fn delimited(&mut self, close: char) -> Result<&str, BibliographyError> {
+        let start = self.pos;
+        let mut depth = 0usize;
+
+        for (idx, c) in self.source[start..].char_indices() {
+            match c {
+                c if c == close && depth == 0 => {
+                    self.pos = start + idx + 1;
+                    return Ok(&self.source[start..start + idx]);
+                }
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                _ => (),
+            }
+        }
+
+        Err(self.error(
+            start - 1,
+            start,
+            format!("unclosed '{}'", &self.source[start - 1..start]),
+        ))
+    }
+}
+
+/// Split a list of names on every `and` that is not nested in braces
+fn split_names(names: &str) -> Vec<String> {
+    split_top_level(names, char::is_whitespace)
+        .split(|w| w.eq_ignore_ascii_case("and"))
+        .filter(|n| !n.is_empty())
+        .map(|n| n.join(" "))
+        .collect()
+}
+
+/// Parse a name written as either `Given Family` or `Family, Given`
+fn parse_name(name: &str) -> Name {
+    let parts = split_top_level(name, |c| c == ',');
+
+    if let [family, .., given] = parts.as_slice() {
+        let given = clean(given);
+
+        return Name {
+            family: clean(family),
+            given: (!given.is_empty()).then_some(given),
+        };
+    }
+
+    let words = split_top_level(name, char::is_whitespace);
+    match words.split_last() {
+        Some((family, [])) => Name {
+            family: clean(family),
+            given: None,
+        },
+        Some((family, given)) => Name {
+            family: clean(family),
+            given: Some(clean(&given.join(" "))),
+        },
+        None => Name::default(),
+    }
+}
+
+/// Split text on every character matching a predicate that is not nested in
+/// braces, dropping empty parts
+fn split_top_level(text: &str, pred: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            c if pred(c) && depth == 0 => {
+                parts.push(&text[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&text[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Turn a BibTeX value into plain text, removing braces, collapsing
+/// whitespace, and replacing common LaTeX commands
+fn clean(value: &str) -> String {
+    let mut cleaned = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => (),
+            '~' => cleaned.push('\u{a0}'),
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                match chars.peek() {
+                    Some('-') => {
+                        chars.next();
+                        cleaned.push('—');
+                    }
+                    _ => cleaned.push('–'),
+                }
+            }
+            '\\' => {
+                let mut command = String::new();
+                match chars.next() {
+                    Some(c) if "&%$#_{}".contains(c) => {
+                        cleaned.push(c);
+                        continue;
+                    }
+                    Some(c) if c.is_alphabetic() => {
+                        command.push(c);
+                        while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                            command.push(c);
+                        }
+                    }
+                    Some(c) => command.push(c),
+                    None => {
+                        cleaned.push('\\');
+                        continue;
+                    }
+                }
+
+                if let Some((_, combining)) = ACCENTS.iter().find(|(a, _)| *a == command) {
+                    while chars.next_if(|c| *c == '{' || c.is_whitespace()).is_some() {}
+                    if let Some(letter) = chars.next() {
+                        cleaned.push(letter);
+                    }
+                    cleaned.push(*combining);
+                } else if LOGOS.contains(&command.as_str()) {
+                    cleaned.push_str(&command);
+                }
+                // Any other command is dropped, but its arguments are kept
+            }
+            c if c.is_whitespace() => {
+                if !cleaned.ends_with(' ') {
+                    cleaned.push(' ');
+                }
+            }
+            c => cleaned.push(c),
+        }
+    }
+
+    cleaned.trim().to_owned()
+}
@@ -0,0 +1,110 @@
+//! CSL-JSON bibliographies
+
+use serde_json::Value;
+
+use super::{BibliographyError, Entry, Name};
+
+/// Parse a CSL-JSON file, which is an array of entries
+///
+/// Only the fields needed to format an entry are kept, everything else is
+/// ignored
+pub(super) fn parse(source: &str) -> Result<Vec<Entry>, BibliographyError> {
+    let value: Value = serde_json::from_str(source).map_err(|e| {
+        let start = offset(source, e.line(), e.column());
+
+        BibliographyError {
+            start,
+            end: start,
+            reason: e.to_string(),
+        }
+    })?;
+
+    let error = |reason: String| BibliographyError {
+        start: 0,
+        end: 0,
+        reason,
+    };
+
+    let Value::Array(items) = value else {
+        return Err(error("expected an array of entries".to_owned()));
+    };
+
+    let mut entries: Vec<Entry> = vec![];
+
+    for (idx, item) in items.iter().enumerate() {
+        let key = match item.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => return Err(error(format!("entry {idx} has no 'id'"))),
+        };
+
+        if entries.iter().any(|e| e.key == key) {
+            return Err(error(format!("duplicate entry '{key}'")));
+        }
+
+        let string = |name: &str| item.get(name).and_then(Value::as_str).map(str::to_owned);
+
+        let authors = ["author", "editor"]
+            .iter()
+            .find_map(|n| item.get(*n).and_then(Value::as_array))
+            .map(|names| names.iter().filter_map(parse_name).collect())
+            .unwrap_or_default();
+
+        entries.push(Entry {
+            key,
+            authors,
+            title: string("title"),
+            container: string("container-title"),
+            publisher: string("publisher"),
+            year: item.get("issued").and_then(parse_year),
+            url: string("URL").or_else(|| string("DOI").map(|d| format!("https://doi.org/{d}"))),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Parse a name, which is either split into its parts or given literally
+fn parse_name(name: &Value) -> Option<Name> {
+    let part = |p: &str| name.get(p).and_then(Value::as_str).map(str::to_owned);
+
+    match (part("family"), part("literal")) {
+        (Some(family), _) => Some(Name {
+            family,
+            given: part("given"),
+        }),
+        (None, Some(literal)) => Some(Name {
+            family: literal,
+            given: None,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Find the year of a date, given either as `{"date-parts": [[2000, 1, 1]]}`
+/// or as `{"raw": "2000-01-01"}`
+fn parse_year(date: &Value) -> Option<String> {
+    if let Some(year) = date.pointer("/date-parts/0/0") {
+        return match year {
+            Value::String(year) => Some(year.clone()),
+            year => Some(year.to_string()),
+        };
+    }
+
+    ["raw", "literal"]
+        .iter()
+        .find_map(|n| date.get(*n).and_then(Value::as_str))
+        .and_then(|d| d.split('-').next())
+        .map(str::to_owned)
+}
+
+/// The byte offset of a 1-based line and column
+fn offset(source: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+
+    (line_start + column.saturating_sub(1)).min(source.len())
+}
@@ -0,0 +1,200 @@
+//! Bibliographies and citations
+
+mod bibtex;
+mod csl;
+
+use crate::CitationStyle;
+
+/// A single entry of a bibliography
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// The key used to cite this entry
+    pub(crate) key: String,
+    pub(crate) authors: Vec<Name>,
+    pub(crate) title: Option<String>,
+    /// The journal, book, or proceedings this entry is part of
+    pub(crate) container: Option<String>,
+    pub(crate) publisher: Option<String>,
+    pub(crate) year: Option<String>,
+    pub(crate) url: Option<String>,
+}
+
+/// The name of a person or organization
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Name {
+    pub(crate) family: String,
+    pub(crate) given: Option<String>,
+}
+
+/// A cited entry, as listed in a bibliography
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Reference {
+    pub(crate) entry: Entry,
+    /// The label shown in front of the entry, if the citation style uses one
+    pub(crate) label: Option<String>,
+    /// The entry as formatted by the citation style
+    pub(crate) segments: Vec<Segment>,
+}
+
+/// A piece of a formatted bibliography entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Segment {
+    Text(String),
+    /// The title of a work, usually emphasized
+    Title(String),
+    Link(String),
+}
+
+/// An error in a bibliography file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BibliographyError {
+    /// The byte offset of the start of the error within the file
+    pub(crate) start: usize,
+    /// The byte offset of the end of the error within the file
+    pub(crate) end: usize,
+    pub(crate) reason: String,
+}
+
+/// Parse a bibliography file, which is in CSL-JSON format if its name ends in
+/// `.json`, and in BibTeX format otherwise
+pub(crate) fn parse_bibliography(
+    path: &str,
+    source: &str,
+) -> Result<Vec<Entry>, BibliographyError> {
+    match path.ends_with(".json") {
+        true => csl::parse(source),
+        false => bibtex::parse(source),
+    }
+}
+
+impl CitationStyle {
+    /// The label used to cite an entry, where `number` is the position of
+    /// the entry in the order of first citation, starting at 1
+    pub(crate) fn citation_label(&self, entry: &Entry, number: usize) -> String {
+        match self {
+            Self::Numeric => format!("[{number}]"),
+            Self::AuthorYear => {
+                let authors = match entry.authors.as_slice() {
+                    [] => entry.title.clone().unwrap_or_else(|| entry.key.clone()),
+                    [author] => author.family.clone(),
+                    [first, second] => format!("{} and {}", first.family, second.family),
+                    [first, ..] => format!("{} et al.", first.family),
+                };
+
+                format!("({authors}, {})", year(entry))
+            }
+        }
+    }
+
+    /// Put cited entries in the order they are listed in a bibliography
+    ///
+    /// `references` must be in the order of first citation
+    pub(crate) fn sort(&self, references: &mut [Reference]) {
+        if *self == Self::AuthorYear {
+            references.sort_by_cached_key(|r| {
+                let first_author = r.entry.authors.first().map(|a| a.family.clone());
+                (first_author, r.entry.year.clone(), r.entry.title.clone())
+            });
+        }
+    }
+
+    /// The label shown in front of an entry in a bibliography, if any
+    pub(crate) fn reference_label(&self, entry: &Entry, number: usize) -> Option<String> {
+        match self {
+            Self::Numeric => Some(self.citation_label(entry, number)),
+            Self::AuthorYear => None,
+        }
+    }
+
+    /// Format an entry as listed in a bibliography
+    pub(crate) fn format(&self, entry: &Entry) -> Vec<Segment> {
+        let mut segments = vec![];
+        let mut text = String::new();
+
+        match self {
+            Self::Numeric => {
+                let authors: Vec<_> = entry.authors.iter().map(initials_first).collect();
+                if !authors.is_empty() {
+                    text.push_str(&join_names(&authors));
+                    text.push_str(". ");
+                }
+            }
+            Self::AuthorYear => {
+                let authors: Vec<_> = entry.authors.iter().map(family_first).collect();
+                if !authors.is_empty() {
+                    text.push_str(&join_names(&authors));
+                    text.push(' ');
+                }
+                text.push_str(&format!("({}). ", year(entry)));
+            }
+        }
+
+        if let Some(title) = &entry.title {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+            segments.push(Segment::Title(title.clone()));
+            text.push_str(". ");
+        }
+
+        let mut details: Vec<&str> = [&entry.container, &entry.publisher]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if *self == Self::Numeric {
+            details.extend(entry.year.as_deref());
+        }
+        if !details.is_empty() {
+            text.push_str(&details.join(", "));
+            text.push_str(". ");
+        }
+
+        match &entry.url {
+            Some(url) => {
+                segments.push(Segment::Text(text));
+                segments.push(Segment::Link(url.clone()));
+            }
+            None => segments.push(Segment::Text(text.trim_end().to_owned())),
+        }
+
+        segments.retain(|s| s != &Segment::Text(String::new()));
+        segments
+    }
+}
+
+fn year(entry: &Entry) -> &str {
+    entry.year.as_deref().unwrap_or("n.d.")
+}
+
+/// Format a name as eg. `D. E. Knuth`
+fn initials_first(name: &Name) -> String {
+    match &name.given {
+        Some(given) => format!("{} {}", initials(given), name.family),
+        None => name.family.clone(),
+    }
+}
+
+/// Format a name as eg. `Knuth, D. E.`
+fn family_first(name: &Name) -> String {
+    match &name.given {
+        Some(given) => format!("{}, {}", name.family, initials(given)),
+        None => name.family.clone(),
+    }
+}
+
+fn initials(given: &str) -> String {
+    given
+        .split_whitespace()
+        .filter_map(|n| n.chars().next())
+        .map(|c| format!("{c}."))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Join names as eg. `A, B and C`
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
@@ -0,0 +1,398 @@
+//! Site-wide configuration
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use codespan_reporting::files::SimpleFile;
+
+use crate::parse::{Attribute, Parser};
+use crate::render::THEME_PROPERTIES;
+use crate::{did_you_mean, Error, ParseErrorType};
+
+/// Configuration shared by every page of a site
+///
+/// Configuration files use the same syntax as page attributes, eg.
+///
+/// ```lisp
+/// [let product S-stat]
+/// [let version 0.1.0]
+/// [set profile internal]
+/// [typography smart]
+/// [lang en]
+/// [citations author-year]
+/// [class_prefix docs-]
+/// [theme accent #0a7d5a]
+/// [dark_theme accent #3fc58d]
+/// [stylesheet /style.css]
+/// [document fragment]
+/// [layouts site/layouts]
+/// [nav /index.html Home]
+/// [nav /docs/index.html Documentation]
+/// [base_url https://example.com/]
+/// [heading_anchors]
+/// [debug]
+/// [source_map]
+/// [safe]
+/// [format html]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Variables available to every page
+    pub variables: HashMap<String, String>,
+    /// Flags that conditional content is evaluated against
+    pub flags: HashMap<String, String>,
+    /// How text is typeset, unless overridden by a page
+    pub typography: Typography,
+    /// The language content is written in, unless overridden by a page
+    pub language: Option<String>,
+    /// How citations and bibliographies are formatted, unless overridden by a
+    /// page
+    pub citation_style: CitationStyle,
+    /// Which classes rendered elements are given
+    pub classes: Classes,
+    /// Theme properties of the stylesheet overridden in both the light and
+    /// the dark theme, as names and CSS values
+    pub theme: Vec<(String, String)>,
+    /// Theme properties of the stylesheet overridden in the dark theme only
+    pub dark_theme: Vec<(String, String)>,
+    /// Stylesheets linked from every page, before those of the page itself
+    pub stylesheets: Vec<String>,
+    /// Whether pages are rendered as complete documents
+    pub document: Document,
+    /// The directory layouts are read from, `layouts` in the working
+    /// directory if not set
+    pub layouts: Option<PathBuf>,
+    /// The site navigation, as labels and the URLs they link to
+    pub nav: Vec<(String, String)>,
+    /// The URL of the root of the site, which URLs in the metadata of pages
+    /// are relative to
+    pub base_url: Option<String>,
+    /// Whether titles end in a link to themselves, unless overridden by a
+    /// page
+    pub heading_anchors: bool,
+    /// Whether every rendered element is annotated with the position in the
+    /// source it was generated from
+    pub debug: bool,
+    /// Whether a source map from the rendered HTML back to the source is
+    /// generated along with it
+    pub source_map: bool,
+    /// Whether pages are treated as untrusted, removing anything from them
+    /// that could run scripts or load unexpected resources
+    pub safe: bool,
+    /// The format pages are rendered to
+    pub format: Format,
+}
+
+/// How text is typeset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Typography {
+    /// Text is used exactly as written
+    #[default]
+    Plain,
+    /// Quotes, dashes and ellipses are replaced by their typographic
+    /// equivalents, and non-breaking spaces are inserted where a line should
+    /// not be broken
+    Smart,
+}
+
+impl Typography {
+    /// The names of all modes, as used in attributes
+    pub(crate) const NAMES: &'static [&'static str] = &["plain", "smart"];
+
+    /// Find a mode by its name
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "smart" => Some(Self::Smart),
+            _ => None,
+        }
+    }
+}
+
+/// How citations and bibliographies are formatted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CitationStyle {
+    /// Entries are cited by number, eg. `[1]`, in the order they are first
+    /// cited
+    #[default]
+    Numeric,
+    /// Entries are cited by author and year, eg. `(Knuth, 1984)`, and listed
+    /// alphabetically
+    AuthorYear,
+}
+
+impl CitationStyle {
+    /// The names of all styles, as used in attributes
+    pub(crate) const NAMES: &'static [&'static str] = &["numeric", "author-year"];
+
+    /// Find a style by its name
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "numeric" => Some(Self::Numeric),
+            "author-year" => Some(Self::AuthorYear),
+            _ => None,
+        }
+    }
+}
+
+/// Whether pages are rendered as complete documents
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Document {
+    /// The `<main>` element is wrapped in a complete HTML document, with a
+    /// `<head>` describing the page
+    #[default]
+    Full,
+    /// Only the `<main>` element is rendered, for embedding pages in other
+    /// documents
+    Fragment,
+}
+
+impl Document {
+    /// The names of all modes, as used in attributes
+    pub(crate) const NAMES: &'static [&'static str] = &["full", "fragment"];
+
+    /// Find a mode by its name
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(Self::Full),
+            "fragment" => Some(Self::Fragment),
+            _ => None,
+        }
+    }
+}
+
+/// The format pages are rendered to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// HTML, either as a complete document or as a fragment
+    #[default]
+    Html,
+    /// CommonMark with the GitHub Flavored Markdown extensions, falling back
+    /// to inline HTML for nodes it cannot represent
+    Markdown,
+    /// A standalone LaTeX document, for print
+    Latex,
+}
+
+impl Format {
+    /// The names of all formats, as used in attributes and on the command
+    /// line
+    pub const NAMES: &'static [&'static str] = &["html", "markdown", "latex"];
+
+    /// Find a format by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "html" => Some(Self::Html),
+            "markdown" => Some(Self::Markdown),
+            "latex" => Some(Self::Latex),
+            _ => None,
+        }
+    }
+}
+
+/// Which classes rendered elements are given, besides those given by
+/// `[class ...]` attributes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Classes {
+    /// Every element is given a class made of a prefix and the kind of
+    /// element, eg. `__sstat-paragraph` for the prefix `__sstat-`
+    Prefixed(String),
+    /// No classes are generated
+    None,
+}
+
+impl Classes {
+    /// The prefix of every generated class, unless configured otherwise
+    pub const DEFAULT_PREFIX: &'static str = "__sstat-";
+
+    /// The value of a `[class_prefix ...]` attribute, where `none` turns off
+    /// generated classes
+    fn from_prefix(prefix: &str) -> Self {
+        match prefix {
+            "none" => Self::None,
+            prefix => Self::Prefixed(prefix.to_owned()),
+        }
+    }
+
+    /// The prefix of generated ids, eg. `__sstat-` in `__sstat-figure-1`,
+    /// which is empty if no classes are generated
+    pub(crate) fn id_prefix(&self) -> &str {
+        match self {
+            Self::Prefixed(prefix) => prefix,
+            Self::None => "",
+        }
+    }
+}
+
+impl Default for Classes {
+    fn default() -> Self {
+        Self::Prefixed(Self::DEFAULT_PREFIX.to_owned())
+    }
+}
+
+impl Config {
+    /// Parse a configuration file
+    pub fn parse(filename: String, source: String) -> Result<Self, Error> {
+        let file = SimpleFile::new(filename, source);
+        let parser = Parser::new(file);
+
+        let mut config = Self::default();
+
+        for attribute in parser.parse_attributes()? {
+            match attribute.attribute_name.name {
+                "let" => {
+                    let (name, value) = parser.parse_binding(&attribute)?;
+                    config
+                        .variables
+                        .insert(name.name.to_owned(), value.text.to_owned());
+                }
+                "set" => {
+                    let (name, value) = parser.parse_binding(&attribute)?;
+                    config
+                        .flags
+                        .insert(name.name.to_owned(), value.text.to_owned());
+                }
+                "typography" => config.typography = parser.parse_typography(&attribute)?,
+                "citations" => config.citation_style = parser.parse_citation_style(&attribute)?,
+                "theme" => config
+                    .theme
+                    .push(parse_theme_property(&parser, &attribute)?),
+                "dark_theme" => config
+                    .dark_theme
+                    .push(parse_theme_property(&parser, &attribute)?),
+                "class_prefix" => {
+                    config.classes = Classes::from_prefix(attribute.attribute_value.text.trim());
+                }
+                "lang" => {
+                    config.language = Some(attribute.attribute_value.text.trim().to_owned());
+                }
+                "stylesheet" => config
+                    .stylesheets
+                    .push(attribute.attribute_value.text.trim().to_owned()),
+                "document" => config.document = parse_document(&parser, &attribute)?,
+                "layouts" => {
+                    let value = attribute.attribute_value;
+                    config.layouts = Some(parser.resolve_path(value.text.trim(), value.span)?);
+                }
+                "nav" => config.nav.push(parse_nav_entry(&parser, &attribute)?),
+                "heading_anchors" => {
+                    config.heading_anchors = parser.parse_flag_attribute(&attribute)?;
+                }
+                "debug" => config.debug = parser.parse_flag_attribute(&attribute)?,
+                "source_map" => config.source_map = parser.parse_flag_attribute(&attribute)?,
+                "safe" => config.safe = parser.parse_flag_attribute(&attribute)?,
+                "format" => config.format = parse_format(&parser, &attribute)?,
+                "base_url" => {
+                    config.base_url = Some(attribute.attribute_value.text.trim().to_owned());
+                }
+                name => {
+                    return Err(parser
+                        .make_error(
+                            attribute.attribute_name.span,
+                            ParseErrorType::UnknownConfigAttribute {
+                                name: name.to_owned(),
+                            },
+                        )
+                        .into())
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parse the value of a `[document ...]` attribute
+fn parse_document(parser: &Parser, attribute: &Attribute) -> Result<Document, Error> {
+    let value = attribute.attribute_value.text.trim();
+
+    Document::from_name(value).ok_or_else(|| {
+        let mut error = parser.make_error(
+            attribute.attribute_value.span,
+            ParseErrorType::InvalidAttributeValue {
+                name: "document".to_owned(),
+                value: value.to_owned(),
+            },
+        );
+        if let Some(suggestion) = did_you_mean(value, Document::NAMES.iter().copied()) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        error.into()
+    })
+}
+
+/// Parse the value of a `[format ...]` attribute
+fn parse_format(parser: &Parser, attribute: &Attribute) -> Result<Format, Error> {
+    let value = attribute.attribute_value.text.trim();
+
+    Format::from_name(value).ok_or_else(|| {
+        let mut error = parser.make_error(
+            attribute.attribute_value.span,
+            ParseErrorType::InvalidAttributeValue {
+                name: "format".to_owned(),
+                value: value.to_owned(),
+            },
+        );
+        if let Some(suggestion) = did_you_mean(value, Format::NAMES.iter().copied()) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        error.into()
+    })
+}
+
+/// Parse the value of a `[nav url label]` attribute
+fn parse_nav_entry(parser: &Parser, attribute: &Attribute) -> Result<(String, String), Error> {
+    let value = attribute.attribute_value.text.trim();
+
+    match value.split_once(char::is_whitespace) {
+        Some((url, label)) => Ok((label.trim().to_owned(), url.to_owned())),
+        None => Err(parser
+            .make_error(
+                attribute.attribute_value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: "nav".to_owned(),
+                    value: value.to_owned(),
+                },
+            )
+            .add_context("expected a URL followed by a label, like '/index.html Home'".to_owned())
+            .into()),
+    }
+}
+
+/// Parse the value of a `[theme name value]` or `[dark_theme name value]`
+/// attribute
+fn parse_theme_property(parser: &Parser, attribute: &Attribute) -> Result<(String, String), Error> {
+    let (name, value) = parser.parse_binding(attribute)?;
+
+    if !THEME_PROPERTIES.contains(&name.name) {
+        let mut error = parser.make_error(
+            name.span,
+            ParseErrorType::UnknownThemeProperty {
+                name: name.name.to_owned(),
+            },
+        );
+        if let Some(suggestion) = did_you_mean(name.name, THEME_PROPERTIES.iter().copied()) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        return Err(error.into());
+    }
+
+    // Anything that could end the declaration would let the value escape it
+    if value.text.is_empty() || value.text.contains([';', '{', '}', '<', '>']) {
+        return Err(parser
+            .make_error(
+                value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: name.name.to_owned(),
+                    value: value.text.to_owned(),
+                },
+            )
+            .add_context("expected a CSS value, like '#0a7d5a' or 'Georgia, serif'".to_owned())
+            .into());
+    }
+
+    Ok((name.name.to_owned(), value.text.to_owned()))
+}
@@ -10,6 +10,9 @@ use crate::parse::Span;
 pub enum Error {
     /// Wrapper around [`std::io::Error`]
     Io(std::io::Error),
+    /// Wrapper around [`std::fmt::Error`], when the sink the output is
+    /// written to fails
+    Fmt(std::fmt::Error),
     /// Wrapper around [`ParseError`]
     ParseError(ParseError),
 }
@@ -18,6 +21,7 @@ impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Io(e) => write!(f, "{}", e),
+            Self::Fmt(e) => write!(f, "{}", e),
             Self::ParseError(e) => write!(f, "{}", e),
         }
     }
@@ -30,6 +34,13 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<std::fmt::Error> for Error {
+    #[inline(always)]
+    fn from(value: std::fmt::Error) -> Self {
+        Self::Fmt(value)
+    }
+}
+
 impl From<ParseError> for Error {
     #[inline(always)]
     fn from(value: ParseError) -> Self {
@@ -37,9 +48,21 @@ impl From<ParseError> for Error {
     }
 }
 
+/// How severe a [`ParseError`] is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Transpilation cannot continue
+    Error,
+    /// Transpilation can continue, but the output is probably not what was
+    /// intended
+    Warning,
+}
+
 /// Any error related to parsing
 #[derive(Clone, Debug)]
 pub struct ParseError {
+    /// How severe the error is
+    pub severity: Severity,
     /// The source of the error
     pub source: Box<SimpleFile<String, String>>,
     /// The location of the error
@@ -48,19 +71,41 @@ pub struct ParseError {
     pub ty: ParseErrorType,
     /// Any additional context about the error
     pub context: Vec<String>,
+    /// Any other locations relevant to the error
+    pub related: Vec<Related>,
+}
+
+/// A secondary location relevant to an error, possibly in a different file
+#[derive(Clone, Debug)]
+pub struct Related {
+    /// The source containing the location
+    pub source: Box<SimpleFile<String, String>>,
+    /// The location itself
+    pub span: Span,
+    /// What the location means for the error
+    pub message: String,
 }
 
 impl ParseError {
     /// Create a new error
     pub fn new(source: SimpleFile<String, String>, span: Span, ty: ParseErrorType) -> Self {
         Self {
+            severity: Severity::Error,
             source: Box::new(source),
             span,
             ty,
             context: vec![],
+            related: vec![],
         }
     }
 
+    /// Turn the error into a warning
+    pub fn into_warning(mut self) -> Self {
+        self.severity = Severity::Warning;
+
+        self
+    }
+
     /// Add context to the error
     pub fn add_context(mut self, ctx: String) -> Self {
         self.context.push(ctx);
@@ -68,23 +113,76 @@ impl ParseError {
         self
     }
 
+    /// Add a related location to the error
+    pub fn add_related(
+        mut self,
+        source: SimpleFile<String, String>,
+        span: Span,
+        message: String,
+    ) -> Self {
+        self.related.push(Related {
+            source: Box::new(source),
+            span,
+            message,
+        });
+
+        self
+    }
+
+    /// Related locations in the same file as the error itself
+    fn local_related(&self) -> impl Iterator<Item = &Related> {
+        self.related
+            .iter()
+            .filter(|r| r.source.name() == self.source.name())
+    }
+
+    /// Related locations in a different file than the error itself
+    fn foreign_related(&self) -> impl Iterator<Item = &Related> {
+        self.related
+            .iter()
+            .filter(|r| r.source.name() != self.source.name())
+    }
+
     fn as_diagnostic(&self) -> Diagnostic<()> {
-        Diagnostic::error()
+        let mut labels = vec![Label::primary((), self.span).with_message(self.ty.to_string())];
+        labels.extend(
+            self.local_related()
+                .map(|r| Label::secondary((), r.span).with_message(r.message.clone())),
+        );
+
+        let severity = match self.severity {
+            Severity::Error => codespan_reporting::diagnostic::Severity::Error,
+            Severity::Warning => codespan_reporting::diagnostic::Severity::Warning,
+        };
+
+        Diagnostic::new(severity)
             .with_message(self.ty.message())
-            .with_labels(vec![
-                Label::primary((), self.span).with_message(self.ty.to_string())
-            ])
+            .with_labels(labels)
             .with_notes(self.context.clone())
     }
 
-    fn emit(&self, writer: &mut dyn codespan_reporting::term::termcolor::WriteColor) -> () {
+    fn emit(&self, writer: &mut dyn codespan_reporting::term::termcolor::WriteColor) {
         codespan_reporting::term::emit(
             writer,
             &Default::default(),
             self.source.as_ref(),
             &self.as_diagnostic(),
         )
-        .unwrap()
+        .unwrap();
+
+        for related in self.foreign_related() {
+            let diagnostic = Diagnostic::note()
+                .with_message(related.message.clone())
+                .with_labels(vec![Label::primary((), related.span)]);
+
+            codespan_reporting::term::emit(
+                writer,
+                &Default::default(),
+                related.source.as_ref(),
+                &diagnostic,
+            )
+            .unwrap();
+        }
     }
 }
 
@@ -115,6 +213,125 @@ pub enum ParseErrorType {
     /// Expected an identifier
     #[allow(missing_docs)]
     ExpectedIdentifier { found: String },
+    /// Unknown node name
+    #[allow(missing_docs)]
+    UnknownNode { name: String },
+    /// Use of a variable that was never defined
+    #[allow(missing_docs)]
+    UndefinedVariable { name: String },
+    /// Unknown attribute in a configuration file
+    #[allow(missing_docs)]
+    UnknownConfigAttribute { name: String },
+    /// Definition of a node whose name is already taken
+    #[allow(missing_docs)]
+    RedefinedNode { name: String },
+    /// A component was used without one of its parameters
+    #[allow(missing_docs)]
+    MissingParameter { name: String },
+    /// A component was given an attribute that is not one of its parameters
+    #[allow(missing_docs)]
+    UnknownParameter { name: String },
+    /// A `slot` node outside of a component definition
+    StraySlot,
+    /// A node was given an attribute it does not accept
+    #[allow(missing_docs)]
+    UnexpectedAttribute { node: String, name: String },
+    /// An imported file could not be read
+    #[allow(missing_docs)]
+    ImportFailed { path: String, reason: String },
+    /// Expected a component definition
+    #[allow(missing_docs)]
+    ExpectedDefnode { found: String },
+    /// An `else` node that does not directly follow an `if` node
+    StrayElse,
+    /// A block node inside a node that may only contain inline content
+    #[allow(missing_docs)]
+    BlockInInline { block: String, inline: String },
+    /// An attribute was given a value it does not accept
+    #[allow(missing_docs)]
+    InvalidAttributeValue { name: String, value: String },
+    /// A formula in a `math` or `mathblock` node could not be parsed
+    #[allow(missing_docs)]
+    InvalidFormula { reason: String },
+    /// A node was used without an attribute it requires
+    #[allow(missing_docs)]
+    MissingAttribute { node: String, name: String },
+    /// A bibliography file could not be parsed
+    #[allow(missing_docs)]
+    InvalidBibliography { reason: String },
+    /// A citation of a key that is not in any bibliography
+    #[allow(missing_docs)]
+    UnknownCitation { key: String },
+    /// A reference without content to a node that is not numbered
+    #[allow(missing_docs)]
+    UnknownReference { id: String },
+    /// A reference to a footnote that is not defined
+    #[allow(missing_docs)]
+    UnknownFootnote { id: String },
+    /// A footnote defined with an id that is already taken by another one
+    #[allow(missing_docs)]
+    DuplicateFootnote { id: String },
+    /// A CSV file imported by a `table` could not be parsed
+    #[allow(missing_docs)]
+    InvalidCsv { reason: String },
+    /// A table row with a different number of columns than the first row
+    #[allow(missing_docs)]
+    InconsistentRowWidth { expected: usize, found: usize },
+    /// A table cell that overlaps a cell spanning multiple rows
+    OverlappingCells,
+    /// A table cell spanning more rows than are left in its part of the table
+    #[allow(missing_docs)]
+    RowspanOverflow { rowspan: usize, remaining: usize },
+    /// A local file referenced by a node does not exist
+    #[allow(missing_docs)]
+    MissingFile { path: String },
+    /// A node inside a node that does not accept it as a child
+    #[allow(missing_docs)]
+    UnexpectedChild { parent: String, child: String },
+    /// A theme property in a configuration file that does not exist
+    #[allow(missing_docs)]
+    UnknownThemeProperty { name: String },
+    /// A layout that does not exist
+    #[allow(missing_docs)]
+    UnknownLayout { name: String },
+    /// A layout that extends itself, directly or through other layouts
+    #[allow(missing_docs)]
+    RecursiveLayout { name: String },
+    /// A placeholder in a layout that does not exist
+    #[allow(missing_docs)]
+    UnknownPlaceholder { name: String },
+    /// An `extends` placeholder that is not at the start of its layout
+    MisplacedExtends,
+    /// A URL that should be absolute, but is relative to the site
+    #[allow(missing_docs)]
+    RelativeUrl { url: String },
+    /// A tag in an `html` node that is not allowed in safe mode
+    #[allow(missing_docs)]
+    UnsafeTag { tag: String },
+    /// An attribute in an `html` node that is not allowed in safe mode
+    #[allow(missing_docs)]
+    UnsafeAttribute { name: String },
+    /// A URL whose scheme is not allowed in safe mode
+    #[allow(missing_docs)]
+    UnsafeUrl { url: String },
+    /// A CSS declaration that is not allowed in safe mode
+    #[allow(missing_docs)]
+    UnsafeStyle { declaration: String },
+    /// A path leaving the directory of the page, which is not allowed in
+    /// safe mode
+    #[allow(missing_docs)]
+    UnsafePath { path: String },
+    /// A node that the output format cannot represent, which is rendered as
+    /// HTML instead, either because of its kind or because of an attribute
+    #[allow(missing_docs)]
+    HtmlFallback {
+        name: String,
+        attribute: Option<String>,
+        format: String,
+    },
+    /// A node that the output format cannot represent, which is left out
+    #[allow(missing_docs)]
+    Unrepresentable { name: String, format: String },
 }
 
 impl ParseErrorType {
@@ -127,6 +344,65 @@ impl ParseErrorType {
                 found: _,
             } => String::from("unexpected token"),
             Self::ExpectedIdentifier { found: _ } => String::from("expected identifier"),
+            Self::UnknownNode { name: _ } => String::from("unknown node"),
+            Self::UndefinedVariable { name: _ } => String::from("undefined variable"),
+            Self::UnknownConfigAttribute { name: _ } => {
+                String::from("unknown configuration attribute")
+            }
+            Self::RedefinedNode { name: _ } => String::from("node redefined"),
+            Self::MissingParameter { name: _ } => String::from("missing parameter"),
+            Self::UnknownParameter { name: _ } => String::from("unknown parameter"),
+            Self::StraySlot => String::from("slot outside of component definition"),
+            Self::UnexpectedAttribute { node: _, name: _ } => String::from("unexpected attribute"),
+            Self::ImportFailed { path: _, reason: _ } => String::from("failed to import file"),
+            Self::ExpectedDefnode { found: _ } => String::from("expected component definition"),
+            Self::StrayElse => String::from("'else' without 'if'"),
+            Self::BlockInInline {
+                block: _,
+                inline: _,
+            } => String::from("block node in inline context"),
+            Self::InvalidAttributeValue { name: _, value: _ } => {
+                String::from("invalid attribute value")
+            }
+            Self::InvalidFormula { reason: _ } => String::from("invalid formula"),
+            Self::MissingAttribute { node: _, name: _ } => String::from("missing attribute"),
+            Self::InvalidBibliography { reason: _ } => String::from("invalid bibliography"),
+            Self::UnknownCitation { key: _ } => String::from("unknown citation"),
+            Self::UnknownReference { id: _ } => String::from("unknown reference"),
+            Self::UnknownFootnote { id: _ } => String::from("unknown footnote"),
+            Self::DuplicateFootnote { id: _ } => String::from("duplicate footnote"),
+            Self::InvalidCsv { reason: _ } => String::from("invalid CSV"),
+            Self::InconsistentRowWidth {
+                expected: _,
+                found: _,
+            } => String::from("inconsistent row width"),
+            Self::OverlappingCells => String::from("overlapping cells"),
+            Self::RowspanOverflow {
+                rowspan: _,
+                remaining: _,
+            } => String::from("rowspan too large"),
+            Self::MissingFile { path: _ } => String::from("missing file"),
+            Self::UnexpectedChild {
+                parent: _,
+                child: _,
+            } => String::from("unexpected child node"),
+            Self::UnknownThemeProperty { name: _ } => String::from("unknown theme property"),
+            Self::UnknownLayout { name: _ } => String::from("unknown layout"),
+            Self::RecursiveLayout { name: _ } => String::from("recursive layout"),
+            Self::UnknownPlaceholder { name: _ } => String::from("unknown placeholder"),
+            Self::MisplacedExtends => String::from("misplaced 'extends'"),
+            Self::RelativeUrl { url: _ } => String::from("relative URL"),
+            Self::UnsafeTag { tag: _ } => String::from("unsafe tag"),
+            Self::UnsafeAttribute { name: _ } => String::from("unsafe attribute"),
+            Self::UnsafeUrl { url: _ } => String::from("unsafe URL"),
+            Self::UnsafeStyle { declaration: _ } => String::from("unsafe style"),
+            Self::UnsafePath { path: _ } => String::from("unsafe path"),
+            Self::HtmlFallback {
+                name: _,
+                attribute: _,
+                format: _,
+            } => String::from("rendered as HTML"),
+            Self::Unrepresentable { name: _, format: _ } => String::from("unrepresentable node"),
         }
     }
 }
@@ -144,6 +420,167 @@ impl Display for ParseErrorType {
             Self::ExpectedIdentifier { found } => {
                 write!(f, "expected IDENTIFIER, found '{found}'")
             }
+            Self::UnknownNode { name } => write!(f, "no node named '{name}' exists"),
+            Self::UndefinedVariable { name } => write!(f, "variable '{name}' is not defined"),
+            Self::UnknownConfigAttribute { name } => {
+                write!(f, "'{name}' is not a configuration attribute")
+            }
+            Self::RedefinedNode { name } => write!(f, "a node named '{name}' already exists"),
+            Self::MissingParameter { name } => write!(f, "missing parameter '{name}'"),
+            Self::UnknownParameter { name } => {
+                write!(f, "component has no parameter named '{name}'")
+            }
+            Self::StraySlot => write!(f, "'slot' can only be used inside 'defnode'"),
+            Self::UnexpectedAttribute { node, name } => {
+                write!(f, "'{node}' does not accept an attribute named '{name}'")
+            }
+            Self::ImportFailed { path, reason } => write!(f, "cannot import '{path}': {reason}"),
+            Self::ExpectedDefnode { found } => write!(f, "expected 'defnode', found '{found}'"),
+            Self::StrayElse => write!(f, "'else' must directly follow an 'if' node"),
+            Self::BlockInInline { block, inline } => {
+                write!(
+                    f,
+                    "'{block}' is a block node, but '{inline}' may only contain inline content"
+                )
+            }
+            Self::InvalidAttributeValue { name, value } => {
+                write!(f, "'{value}' is not a valid value for '{name}'")
+            }
+            Self::InvalidFormula { reason } => write!(f, "{reason}"),
+            Self::MissingAttribute { node, name } => {
+                write!(f, "'{node}' requires an attribute named '{name}'")
+            }
+            Self::InvalidBibliography { reason } => write!(f, "{reason}"),
+            Self::UnknownCitation { key } => {
+                write!(f, "no bibliography entry with key '{key}' exists")
+            }
+            Self::UnknownReference { id } => {
+                write!(f, "no numbered node with id '{id}' exists")
+            }
+            Self::UnknownFootnote { id } => write!(f, "no footnote with id '{id}' exists"),
+            Self::DuplicateFootnote { id } => {
+                write!(f, "a footnote with id '{id}' is already defined")
+            }
+            Self::InvalidCsv { reason } => write!(f, "{reason}"),
+            Self::InconsistentRowWidth { expected, found } => {
+                write!(f, "row has {found} columns, but the table has {expected}")
+            }
+            Self::OverlappingCells => {
+                write!(f, "cell overlaps a cell spanning multiple rows")
+            }
+            Self::RowspanOverflow { rowspan, remaining } => write!(
+                f,
+                "cell spans {rowspan} rows, but only {remaining} are left in its part of the table"
+            ),
+            Self::MissingFile { path } => write!(f, "'{path}' does not exist"),
+            Self::UnexpectedChild { parent, child } => {
+                write!(f, "'{child}' cannot be inside '{parent}'")
+            }
+            Self::UnknownThemeProperty { name } => {
+                write!(f, "no theme property named '{name}' exists")
+            }
+            Self::UnknownLayout { name } => write!(f, "no layout named '{name}' exists"),
+            Self::RecursiveLayout { name } => {
+                write!(f, "layout '{name}' extends itself")
+            }
+            Self::UnknownPlaceholder { name } => {
+                write!(f, "no placeholder named '{name}' exists")
+            }
+            Self::MisplacedExtends => {
+                write!(f, "'extends' must come before everything else in a layout")
+            }
+            Self::RelativeUrl { url } => write!(f, "'{url}' is not an absolute URL"),
+            Self::UnsafeTag { tag } => {
+                write!(f, "'<{tag}>' is not allowed in safe mode and was removed")
+            }
+            Self::UnsafeAttribute { name } => {
+                write!(
+                    f,
+                    "attribute '{name}' is not allowed in safe mode and was removed"
+                )
+            }
+            Self::UnsafeUrl { url } => write!(
+                f,
+                "URL '{url}' is not allowed in safe mode and was removed, as only relative, \
+                 http, https and mailto URLs are"
+            ),
+            Self::UnsafeStyle { declaration } => {
+                write!(
+                    f,
+                    "CSS '{declaration}' is not allowed in safe mode and was removed"
+                )
+            }
+            Self::UnsafePath { path } => write!(
+                f,
+                "'{path}' is outside the directory of the page, which is not allowed in safe mode"
+            ),
+            Self::HtmlFallback {
+                name,
+                attribute: None,
+                format,
+            } => write!(
+                f,
+                "this '{name}' node cannot be represented in {format}, so it is rendered as HTML"
+            ),
+            Self::HtmlFallback {
+                name,
+                attribute: Some(attribute),
+                format,
+            } => write!(
+                f,
+                "'[{attribute}]' attributes cannot be represented in {format}, so this \
+                 '{name}' node is rendered as HTML"
+            ),
+            Self::Unrepresentable { name, format } => write!(
+                f,
+                "this '{name}' node cannot be represented in {format}, so it was left out"
+            ),
         }
     }
 }
+
+/// Find the candidate closest to `name`, if any is close enough to be a
+/// plausible typo
+pub(crate) fn did_you_mean<'c>(
+    name: &str,
+    candidates: impl Iterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let max_distance = usize::max(1, name.chars().count() / 3);
+
+    candidates
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+/// The edit distance between two strings, counting the transposition of two
+/// adjacent characters as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
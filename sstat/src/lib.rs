@@ -9,27 +9,135 @@
 //!
 
 #![warn(missing_docs)]
-#![feature(assert_matches)]
-#![feature(let_chains)]
-#![feature(type_alias_impl_trait)]
+#![allow(clippy::result_large_err)]
+
+use std::{fmt, io};
 
 use codespan_reporting::files::SimpleFile;
 
+mod bibliography;
+mod config;
 mod error;
 pub mod parse;
+mod pass;
+mod render;
+mod slug;
 
 #[cfg(test)]
 mod test;
 
+pub use config::*;
 pub use error::*;
 use parse::Parser;
+use render::IoWriter;
+pub use slug::*;
+
+/// The result of transpiling a single page
+#[derive(Debug)]
+pub struct Output {
+    /// The rendered page, in the [`Format`] of the [`Config`]
+    pub content: String,
+    /// A JSON source map from byte ranges of the HTML to the source code they
+    /// were generated from, if the [`Config`] asks for one and the page is
+    /// rendered to HTML
+    pub source_map: Option<String>,
+    /// Any warnings produced while transpiling
+    pub warnings: Vec<ParseError>,
+}
+
+/// The result of transpiling a single page into a writer
+#[derive(Debug)]
+pub struct StreamOutput {
+    /// A JSON source map from byte ranges of the HTML to the source code they
+    /// were generated from, if the [`Config`] asks for one and the page is
+    /// rendered to HTML
+    pub source_map: Option<String>,
+    /// Any warnings produced while transpiling
+    pub warnings: Vec<ParseError>,
+}
+
+/// The default stylesheet, styling every class the renderer generates using
+/// the class prefix and theme of a [`Config`]
+pub fn stylesheet(config: &Config) -> String {
+    render::stylesheet(config)
+}
 
-/// Transpile the given source from S-Stat to HTML
-pub fn transpile(filename: String, source: String) -> Result<(), Error> {
+/// Transpile the given source from S-Stat to the [`Format`] of the [`Config`]
+pub fn transpile(filename: String, source: String, config: &Config) -> Result<Output, Error> {
+    let mut content = String::new();
+    let output = transpile_into(filename, source, config, &mut content)?;
+
+    Ok(Output {
+        content,
+        source_map: output.source_map,
+        warnings: output.warnings,
+    })
+}
+
+/// Transpile the given source from S-Stat to the [`Format`] of the [`Config`],
+/// writing the output to a [`fmt::Write`] as it is rendered instead of
+/// building it in memory first
+pub fn transpile_into(
+    filename: String,
+    source: String,
+    config: &Config,
+    output: &mut impl fmt::Write,
+) -> Result<StreamOutput, Error> {
     let file = SimpleFile::new(filename, source);
-    let parser = Parser::new(file);
+    // Untrusted pages may only read files next to them, which must be known
+    // before any file is read
+    let parser = Parser::new(file).safe_mode(config.safe);
+
+    let mut page = parser.parse()?;
+    parser.expand_components(&mut page)?;
+    parser.evaluate_conditionals(&mut page, config)?;
+    parser.resolve_variables(&mut page, config)?;
+    parser.resolve_citations(&mut page, config)?;
+    parser.check_flags(&mut page)?;
+    parser.layout_tables(&mut page)?;
+    parser.check_media(&mut page)?;
+    parser.check_lists(&mut page)?;
+    parser.check_semantics(&mut page)?;
+    parser.sanitize(&mut page, config);
+    parser.wrap_paragraphs(&mut page);
+    parser.normalize_whitespace(&mut page);
+    parser.process_escapes(&mut page);
+    parser.apply_typography(&mut page, config)?;
+    parser.number_nodes(&mut page, config)?;
+    parser.assign_heading_ids(&mut page, config);
+    parser.resolve_footnotes(&mut page)?;
+    parser.collect_metadata(&mut page, config)?;
+    parser.load_layout(&mut page, config)?;
+
+    let source_map = render::render(&page, &parser, config, output)?;
+
+    Ok(StreamOutput {
+        source_map,
+        warnings: parser.take_warnings(),
+    })
+}
 
-    parser.parse()?;
+/// Transpile the given source from S-Stat to the [`Format`] of the [`Config`],
+/// writing the output to an [`io::Write`] as it is rendered
+///
+/// The output is written in many small pieces, so the writer should be
+/// buffered, eg. using a [`BufWriter`](io::BufWriter).
+pub fn transpile_to_writer(
+    filename: String,
+    source: String,
+    config: &Config,
+    writer: impl io::Write,
+) -> Result<StreamOutput, Error> {
+    let mut writer = IoWriter::new(writer);
 
-    Ok(())
+    match transpile_into(filename, source, config, &mut writer) {
+        Err(Error::Fmt(_)) => Err(writer
+            .error
+            .expect("writers only fail with an I/O error")
+            .into()),
+        result => {
+            writer.flush()?;
+            result
+        }
+    }
 }
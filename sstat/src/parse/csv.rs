@@ -0,0 +1,141 @@
+//! Tables in CSV format
+
+/// A single line of a CSV file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CsvRow {
+    /// The byte offset of the start of the row within the file
+    pub(crate) start: usize,
+    /// The byte offset of the end of the row within the file, excluding the
+    /// line break
+    pub(crate) end: usize,
+    pub(crate) fields: Vec<CsvField>,
+}
+
+/// A single field of a CSV row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CsvField {
+    /// The byte offset of the start of the field within the file
+    pub(crate) start: usize,
+    /// The byte offset of the end of the field within the file
+    pub(crate) end: usize,
+    /// The content of the field, with any quotes removed
+    pub(crate) text: String,
+}
+
+/// An error in a CSV file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CsvError {
+    /// The byte offset of the start of the error within the file
+    pub(crate) start: usize,
+    /// The byte offset of the end of the error within the file
+    pub(crate) end: usize,
+    pub(crate) reason: String,
+}
+
+/// Parse a CSV file, as described in RFC 4180
+///
+/// Fields are separated by commas, and may be quoted using double quotes, in
+/// which case they can contain commas, line breaks, and escaped quotes (`""`).
+/// Empty lines are skipped.
+pub(crate) fn parse_csv(source: &str) -> Result<Vec<CsvRow>, CsvError> {
+    let mut rows = vec![];
+    let mut pos = 0;
+
+    while pos < source.len() {
+        let line_end = source[pos..].find('\n').map_or(source.len(), |i| pos + i);
+        if source[pos..line_end].trim().is_empty() {
+            pos = line_end + 1;
+            continue;
+        }
+
+        let (row, end) = parse_row(source, pos)?;
+        rows.push(row);
+        pos = end + 1;
+    }
+
+    Ok(rows)
+}
+
+/// Parse the row starting at `start`, returning it along with the offset of
+/// the line break that ends it
+fn parse_row(source: &str, start: usize) -> Result<(CsvRow, usize), CsvError> {
+    let mut fields = vec![];
+    let mut pos = start;
+
+    loop {
+        let (field, end) = match source[pos..].starts_with('"') {
+            true => parse_quoted(source, pos)?,
+            false => {
+                let end = source[pos..]
+                    .find([',', '\n'])
+                    .map_or(source.len(), |i| pos + i);
+                let text = source[pos..end].trim_end_matches('\r');
+
+                let field = CsvField {
+                    start: pos,
+                    end: pos + text.len(),
+                    text: text.to_owned(),
+                };
+
+                (field, end)
+            }
+        };
+        fields.push(field);
+
+        match source[end..].chars().next() {
+            Some(',') => pos = end + 1,
+            _ => {
+                let row = CsvRow {
+                    start,
+                    end: fields.last().unwrap().end,
+                    fields,
+                };
+
+                return Ok((row, end));
+            }
+        }
+    }
+}
+
+/// Parse a quoted field starting at `start`, returning it along with the
+/// offset of the character after it
+fn parse_quoted(source: &str, start: usize) -> Result<(CsvField, usize), CsvError> {
+    let mut text = String::new();
+    let mut chars = source[start + 1..].char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        if c != '"' {
+            text.push(c);
+            continue;
+        }
+
+        // An escaped quote
+        if chars.next_if(|(_, c)| *c == '"').is_some() {
+            text.push('"');
+            continue;
+        }
+
+        let end = start + 1 + idx + 1;
+        let rest = source[end..].trim_start_matches([' ', '\t', '\r']);
+        let next = end + (source[end..].len() - rest.len());
+
+        return match rest.chars().next() {
+            None | Some(',') | Some('\n') => Ok((CsvField { start, end, text }, next)),
+            Some(c) => Err(CsvError {
+                start: next,
+                end: next + c.len_utf8(),
+                reason: format!("unexpected '{c}' after quoted field"),
+            }),
+        };
+    }
+
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+
+    Err(CsvError {
+        start,
+        end: line_end,
+        reason: "unclosed '\"'".to_owned(),
+    })
+}
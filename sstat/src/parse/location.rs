@@ -1,7 +1,10 @@
 //! AST types for location info
 
+use std::fmt::{self, Display};
 use std::path::PathBuf;
 
+use super::Parser;
+
 /// All information about the location of a specific item
 #[derive(Clone, Debug)]
 pub struct Location {
@@ -17,6 +20,12 @@ pub struct Location {
     pub span: Span,
 }
 
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file_path.display(), self.start)
+    }
+}
+
 /// Information about where a given AST node is located in the source file
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct LineCol {
@@ -26,6 +35,12 @@ pub struct LineCol {
     pub column: usize,
 }
 
+impl Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 /// A specific region of source code
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Span {
@@ -46,4 +61,51 @@ impl Span {
     pub fn new(start: usize, end: usize) -> Self {
         Self { start, end }
     }
+
+    /// Whether another span lies entirely within this one
+    pub fn contains(&self, other: Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+impl Parser {
+    /// Find the file, lines and columns of a span
+    ///
+    /// Lines and columns start at 1, and columns are counted in characters.
+    pub(crate) fn location(&self, span: Span) -> Location {
+        self.with_file(span, |file, span| {
+            let source = file.source();
+            let start = line_col(source, span.start);
+            let end = line_col(source, span.end);
+
+            Location {
+                file_path: PathBuf::from(file.name()),
+                lines: source
+                    .lines()
+                    .skip(start.line - 1)
+                    .take(end.line - start.line + 1)
+                    .map(str::to_owned)
+                    .collect(),
+                start,
+                end,
+                span,
+            }
+        })
+    }
+}
+
+/// The line and column of an offset into some source code
+fn line_col(source: &str, offset: usize) -> LineCol {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    LineCol {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
 }
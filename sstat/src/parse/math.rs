@@ -0,0 +1,621 @@
+//! Mathematical formulas, written in a subset of LaTeX notation
+
+/// Greek letters, by their command name
+const GREEK_LETTERS: &[(&str, char)] = &[
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ϵ'),
+    ("varepsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("vartheta", 'ϑ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'ϕ'),
+    ("varphi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Lambda", 'Λ'),
+    ("Xi", 'Ξ'),
+    ("Pi", 'Π'),
+    ("Sigma", 'Σ'),
+    ("Upsilon", 'Υ'),
+    ("Phi", 'Φ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+];
+
+/// Operators and relations, by their command name
+const SYMBOLS: &[(&str, char)] = &[
+    ("cdot", '⋅'),
+    ("times", '×'),
+    ("div", '÷'),
+    ("pm", '±'),
+    ("mp", '∓'),
+    ("ast", '∗'),
+    ("circ", '∘'),
+    ("leq", '≤'),
+    ("le", '≤'),
+    ("geq", '≥'),
+    ("ge", '≥'),
+    ("neq", '≠'),
+    ("ne", '≠'),
+    ("approx", '≈'),
+    ("equiv", '≡'),
+    ("sim", '∼'),
+    ("simeq", '≃'),
+    ("propto", '∝'),
+    ("ll", '≪'),
+    ("gg", '≫'),
+    ("in", '∈'),
+    ("notin", '∉'),
+    ("ni", '∋'),
+    ("subset", '⊂'),
+    ("subseteq", '⊆'),
+    ("supset", '⊃'),
+    ("supseteq", '⊇'),
+    ("cup", '∪'),
+    ("cap", '∩'),
+    ("setminus", '∖'),
+    ("emptyset", '∅'),
+    ("forall", '∀'),
+    ("exists", '∃'),
+    ("neg", '¬'),
+    ("land", '∧'),
+    ("wedge", '∧'),
+    ("lor", '∨'),
+    ("vee", '∨'),
+    ("to", '→'),
+    ("rightarrow", '→'),
+    ("leftarrow", '←'),
+    ("leftrightarrow", '↔'),
+    ("Rightarrow", '⇒'),
+    ("Leftarrow", '⇐'),
+    ("Leftrightarrow", '⇔'),
+    ("iff", '⇔'),
+    ("implies", '⇒'),
+    ("mapsto", '↦'),
+    ("infty", '∞'),
+    ("partial", '∂'),
+    ("nabla", '∇'),
+    ("ldots", '…'),
+    ("cdots", '⋯'),
+    ("vdots", '⋮'),
+    ("ddots", '⋱'),
+    ("langle", '⟨'),
+    ("rangle", '⟩'),
+    ("lfloor", '⌊'),
+    ("rfloor", '⌋'),
+    ("lceil", '⌈'),
+    ("rceil", '⌉'),
+    ("mid", '∣'),
+    ("parallel", '∥'),
+    ("perp", '⊥'),
+    ("angle", '∠'),
+    ("prime", '′'),
+];
+
+/// Large operators, by their command name, along with whether their scripts
+/// are placed above and below them in display mode
+const LARGE_OPERATORS: &[(&str, char, bool)] = &[
+    ("sum", '∑', true),
+    ("prod", '∏', true),
+    ("coprod", '∐', true),
+    ("bigcup", '⋃', true),
+    ("bigcap", '⋂', true),
+    ("int", '∫', false),
+    ("iint", '∬', false),
+    ("iiint", '∭', false),
+    ("oint", '∮', false),
+];
+
+/// Named functions, along with whether their scripts are placed below them in
+/// display mode
+const FUNCTIONS: &[(&str, bool)] = &[
+    ("sin", false),
+    ("cos", false),
+    ("tan", false),
+    ("cot", false),
+    ("sec", false),
+    ("csc", false),
+    ("arcsin", false),
+    ("arccos", false),
+    ("arctan", false),
+    ("sinh", false),
+    ("cosh", false),
+    ("tanh", false),
+    ("exp", false),
+    ("log", false),
+    ("ln", false),
+    ("lg", false),
+    ("deg", false),
+    ("dim", false),
+    ("ker", false),
+    ("gcd", true),
+    ("det", true),
+    ("lim", true),
+    ("max", true),
+    ("min", true),
+    ("sup", true),
+    ("inf", true),
+    ("arg", false),
+];
+
+/// Accents, by their command name
+const ACCENTS: &[(&str, char)] = &[
+    ("hat", '^'),
+    ("bar", '¯'),
+    ("overline", '¯'),
+    ("vec", '→'),
+    ("dot", '˙'),
+    ("ddot", '¨'),
+    ("tilde", '˜'),
+];
+
+/// Spaces, by their command name, along with their width
+const SPACES: &[(&str, &str)] = &[
+    (",", "0.1667em"),
+    (":", "0.2222em"),
+    (";", "0.2778em"),
+    ("!", "-0.1667em"),
+    ("quad", "1em"),
+    ("qquad", "2em"),
+];
+
+/// Commands that take arguments, or otherwise need special handling
+const SPECIAL_COMMANDS: &[&str] = &[
+    "frac",
+    "sqrt",
+    "text",
+    "mathrm",
+    "operatorname",
+    "left",
+    "right",
+];
+
+/// A parsed mathematical formula
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum MathExpr {
+    /// A sequence of expressions
+    Row(Vec<MathExpr>),
+    /// A single-letter variable
+    Identifier { name: char, upright: bool },
+    /// A named function, like `sin`
+    Function { name: String, limits: bool },
+    /// A number
+    Number(String),
+    /// An operator, relation, or other symbol
+    Operator(char),
+    /// A large operator, like a sum or an integral
+    LargeOperator { op: char, limits: bool },
+    /// Plain text within a formula
+    Text(String),
+    /// Horizontal space
+    Space(&'static str),
+    /// A fraction
+    Fraction {
+        numerator: Box<MathExpr>,
+        denominator: Box<MathExpr>,
+    },
+    /// A square root, or any other root if it has an index
+    Root {
+        radicand: Box<MathExpr>,
+        index: Option<Box<MathExpr>>,
+    },
+    /// An expression with a subscript and/or a superscript
+    Scripts {
+        base: Box<MathExpr>,
+        sub: Option<Box<MathExpr>>,
+        sup: Option<Box<MathExpr>>,
+    },
+    /// An expression with an accent above it
+    Accent { base: Box<MathExpr>, accent: char },
+    /// An expression between delimiters that grow with it
+    Fenced {
+        open: Option<char>,
+        inner: Box<MathExpr>,
+        close: Option<char>,
+    },
+}
+
+impl MathExpr {
+    /// Whether the scripts of this expression are placed above and below it
+    /// when displayed as a block
+    pub(crate) fn has_limits(&self) -> bool {
+        matches!(
+            self,
+            Self::LargeOperator { limits: true, .. } | Self::Function { limits: true, .. }
+        )
+    }
+}
+
+/// An error in a formula
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FormulaError {
+    /// The byte offset of the start of the error within the formula
+    pub(crate) start: usize,
+    /// The byte offset of the end of the error within the formula
+    pub(crate) end: usize,
+    pub(crate) reason: String,
+    /// The name of a command that was likely meant instead of an unknown one
+    pub(crate) suggestion: Option<&'static str>,
+}
+
+/// Parse a formula
+pub(crate) fn parse_formula(source: &str) -> Result<MathExpr, FormulaError> {
+    let mut parser = FormulaParser { source, pos: 0 };
+
+    let formula = parser.parse_row(false)?;
+
+    match parser.peek() {
+        None => Ok(formula),
+        Some('}') => Err(parser.error_at(parser.pos, 1, "unmatched '}'")),
+        Some(_) => Err(parser.error_at(parser.pos, "\\right".len(), "'\\right' without '\\left'")),
+    }
+}
+
+/// The names of all supported commands
+fn command_names() -> impl Iterator<Item = &'static str> {
+    GREEK_LETTERS
+        .iter()
+        .map(|(n, _)| *n)
+        .chain(SYMBOLS.iter().map(|(n, _)| *n))
+        .chain(LARGE_OPERATORS.iter().map(|(n, _, _)| *n))
+        .chain(FUNCTIONS.iter().map(|(n, _)| *n))
+        .chain(ACCENTS.iter().map(|(n, _)| *n))
+        .chain(SPACES.iter().map(|(n, _)| *n))
+        .chain(SPECIAL_COMMANDS.iter().copied())
+}
+
+struct FormulaParser<'f> {
+    source: &'f str,
+    /// The byte offset of the next character
+    pos: usize,
+}
+
+impl FormulaParser<'_> {
+    fn rest(&self) -> &str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Whether the next command is `\right`
+    fn at_right(&self) -> bool {
+        let rest = self.rest();
+
+        rest.starts_with("\\right")
+            && !rest["\\right".len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+    }
+
+    fn error_at(&self, start: usize, len: usize, reason: &str) -> FormulaError {
+        FormulaError {
+            start,
+            end: start + len,
+            reason: reason.to_owned(),
+            suggestion: None,
+        }
+    }
+
+    /// Parse expressions until the end of the current group
+    ///
+    /// ```ebnf
+    /// row = { term };
+    /// term = atom, [ "_", argument ], [ "^", argument ];
+    /// ```
+    fn parse_row(&mut self, in_brackets: bool) -> Result<MathExpr, FormulaError> {
+        let mut items = vec![];
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None | Some('}') => break,
+                Some(']') if in_brackets => break,
+                Some('\\') if self.at_right() => break,
+                Some('_' | '^') => {
+                    // Scripts without a base are attached to an empty one
+                    let base = items.pop().unwrap_or(MathExpr::Row(vec![]));
+                    items.push(self.parse_scripts(base)?);
+                }
+                Some(_) => items.push(self.parse_atom(false)?),
+            }
+        }
+
+        Ok(match items.len() {
+            1 => items.pop().unwrap(),
+            _ => MathExpr::Row(items),
+        })
+    }
+
+    fn parse_scripts(&mut self, base: MathExpr) -> Result<MathExpr, FormulaError> {
+        let (base, mut sub, mut sup) = match base {
+            MathExpr::Scripts { base, sub, sup } => (base, sub, sup),
+            base => (Box::new(base), None, None),
+        };
+
+        loop {
+            self.skip_whitespace();
+
+            let start = self.pos;
+            let (script, name) = match self.peek() {
+                Some('_') => (&mut sub, "subscript"),
+                Some('^') => (&mut sup, "superscript"),
+                _ => break,
+            };
+            self.bump();
+
+            if script.is_some() {
+                return Err(self.error_at(start, 1, &format!("double {name}")));
+            }
+            *script = Some(Box::new(self.parse_argument()?));
+        }
+
+        Ok(MathExpr::Scripts { base, sub, sup })
+    }
+
+    /// Parse the argument of a command or script, which is either a group or
+    /// a single atom
+    ///
+    /// ```ebnf
+    /// argument = group | atom;
+    /// ```
+    fn parse_argument(&mut self) -> Result<MathExpr, FormulaError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            None | Some('}' | '_' | '^') => Err(self.error_at(self.pos, 1, "expected an argument")),
+            Some('\\') if self.at_right() => {
+                Err(self.error_at(self.pos, 1, "expected an argument"))
+            }
+            Some(_) => self.parse_atom(true),
+        }
+    }
+
+    /// Parse a single atom
+    ///
+    /// If `single` is set, numbers consist of only a single digit, as is the
+    /// case for arguments
+    ///
+    /// ```ebnf
+    /// atom = group | number | letter | command | ?SYMBOL?;
+    /// ```
+    fn parse_atom(&mut self, single: bool) -> Result<MathExpr, FormulaError> {
+        let start = self.pos;
+        let c = self.bump().unwrap();
+
+        match c {
+            '{' => self.parse_group(start),
+            '\\' => self.parse_command(start),
+            '0'..='9' | '.' if !single => {
+                let rest = self.rest();
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(rest.len());
+                self.pos += len;
+
+                Ok(MathExpr::Number(self.source[start..self.pos].to_owned()))
+            }
+            '0'..='9' => Ok(MathExpr::Number(c.to_string())),
+            c if c.is_alphabetic() => Ok(MathExpr::Identifier {
+                name: c,
+                upright: false,
+            }),
+            '$' | '&' | '#' => {
+                Err(self.error_at(start, 1, &format!("'{c}' is not supported in formulas")))
+            }
+            '-' => Ok(MathExpr::Operator('−')),
+            '*' => Ok(MathExpr::Operator('∗')),
+            '\'' => Ok(MathExpr::Operator('′')),
+            c => Ok(MathExpr::Operator(c)),
+        }
+    }
+
+    /// Parse the rest of a group, after its opening brace
+    ///
+    /// ```ebnf
+    /// group = "{", row, "}";
+    /// ```
+    fn parse_group(&mut self, start: usize) -> Result<MathExpr, FormulaError> {
+        let inner = self.parse_row(false)?;
+
+        match self.bump() {
+            Some('}') => Ok(inner),
+            _ => Err(self.error_at(start, 1, "unclosed '{'")),
+        }
+    }
+
+    /// Parse the rest of a command, after its backslash
+    ///
+    /// ```ebnf
+    /// command = "\", ( ?LETTERS? | ?CHARACTER? ), { argument };
+    /// ```
+    fn parse_command(&mut self, start: usize) -> Result<MathExpr, FormulaError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+
+        let name = match len {
+            0 => match self.bump() {
+                Some(c) => &self.source[self.pos - c.len_utf8()..self.pos],
+                None => return Err(self.error_at(start, 1, "expected a command name")),
+            },
+            _ => {
+                self.pos += len;
+                &self.source[start + 1..self.pos]
+            }
+        };
+        let command_len = self.pos - start;
+
+        if let Some((_, c)) = GREEK_LETTERS.iter().find(|(n, _)| *n == name) {
+            return Ok(MathExpr::Identifier {
+                name: *c,
+                upright: false,
+            });
+        }
+        if let Some((_, c)) = SYMBOLS.iter().find(|(n, _)| *n == name) {
+            return Ok(MathExpr::Operator(*c));
+        }
+        if let Some((_, op, limits)) = LARGE_OPERATORS.iter().find(|(n, _, _)| *n == name) {
+            return Ok(MathExpr::LargeOperator {
+                op: *op,
+                limits: *limits,
+            });
+        }
+        if let Some((_, limits)) = FUNCTIONS.iter().find(|(n, _)| *n == name) {
+            return Ok(MathExpr::Function {
+                name: name.to_owned(),
+                limits: *limits,
+            });
+        }
+        if let Some((_, accent)) = ACCENTS.iter().find(|(n, _)| *n == name) {
+            let base = Box::new(self.parse_argument()?);
+            return Ok(MathExpr::Accent {
+                base,
+                accent: *accent,
+            });
+        }
+        if let Some((_, width)) = SPACES.iter().find(|(n, _)| *n == name) {
+            return Ok(MathExpr::Space(width));
+        }
+
+        match name {
+            "frac" => {
+                let numerator = Box::new(self.parse_argument()?);
+                let denominator = Box::new(self.parse_argument()?);
+
+                Ok(MathExpr::Fraction {
+                    numerator,
+                    denominator,
+                })
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+
+                let index = if self.peek() == Some('[') {
+                    let bracket = self.pos;
+                    self.bump();
+
+                    let index = self.parse_row(true)?;
+                    if self.bump() != Some(']') {
+                        return Err(self.error_at(bracket, 1, "unclosed '['"));
+                    }
+
+                    Some(Box::new(index))
+                } else {
+                    None
+                };
+                let radicand = Box::new(self.parse_argument()?);
+
+                Ok(MathExpr::Root { radicand, index })
+            }
+            "text" | "operatorname" => {
+                self.skip_whitespace();
+
+                let open = self.pos;
+                if self.bump() != Some('{') {
+                    return Err(self.error_at(open, 1, "expected '{'"));
+                }
+
+                let rest = self.rest();
+                let Some(len) = rest.find('}') else {
+                    return Err(self.error_at(open, 1, "unclosed '{'"));
+                };
+                let text = rest[..len].to_owned();
+                self.pos += len + 1;
+
+                Ok(match name {
+                    "text" => MathExpr::Text(text),
+                    _ => MathExpr::Function {
+                        name: text.trim().to_owned(),
+                        limits: false,
+                    },
+                })
+            }
+            "mathrm" => Ok(upright(self.parse_argument()?)),
+            "left" => {
+                let open = self.parse_delimiter(start)?;
+                let inner = Box::new(self.parse_row(false)?);
+
+                if !self.at_right() {
+                    return Err(self.error_at(start, command_len, "'\\left' without '\\right'"));
+                }
+                let right = self.pos;
+                self.pos += "\\right".len();
+                let close = self.parse_delimiter(right)?;
+
+                Ok(MathExpr::Fenced { open, inner, close })
+            }
+            "right" => Err(self.error_at(start, command_len, "'\\right' without '\\left'")),
+            "(" | ")" | "[" | "]" | "{" | "}" | "%" | "_" | "^" | "&" | "#" | "$" => {
+                Ok(MathExpr::Operator(name.chars().next().unwrap()))
+            }
+            "|" => Ok(MathExpr::Operator('‖')),
+            "\\" => Err(self.error_at(start, command_len, "line breaks are not supported")),
+            _ => Err(FormulaError {
+                suggestion: crate::did_you_mean(name, command_names()),
+                ..self.error_at(start, command_len, &format!("unknown command '\\{name}'"))
+            }),
+        }
+    }
+
+    /// Parse the delimiter after `\left` or `\right`, where `.` means no
+    /// delimiter at all
+    fn parse_delimiter(&mut self, command: usize) -> Result<Option<char>, FormulaError> {
+        self.skip_whitespace();
+
+        let start = self.pos;
+        match self.bump() {
+            Some('.') => Ok(None),
+            Some('\\') => match self.parse_command(start)? {
+                MathExpr::Operator(c) => Ok(Some(c)),
+                _ => Err(self.error_at(start, self.pos - start, "expected a delimiter")),
+            },
+            Some(c) if !c.is_alphanumeric() && c != '{' && c != '}' => Ok(Some(c)),
+            _ => Err(self.error_at(command, start - command, "expected a delimiter")),
+        }
+    }
+}
+
+/// Make all identifiers in an expression upright
+fn upright(expr: MathExpr) -> MathExpr {
+    match expr {
+        MathExpr::Identifier { name, .. } => MathExpr::Identifier {
+            name,
+            upright: true,
+        },
+        MathExpr::Row(items) => MathExpr::Row(items.into_iter().map(upright).collect()),
+        expr => expr,
+    }
+}
@@ -1,19 +1,25 @@
 //! AST type definitions
 
-use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 use codespan_reporting::files::SimpleFile;
+use typed_arena::Arena;
 use unicode_xid::UnicodeXID;
 
+mod csv;
 mod location;
+mod math;
 mod node;
 mod token;
 
+pub(crate) use csv::*;
 pub use location::*;
+pub(crate) use math::*;
 pub(crate) use node::*;
 pub(crate) use token::*;
 
-use crate::{ParseError, ParseErrorType};
+use crate::{did_you_mean, ParseError, ParseErrorType};
 
 type ParseResult<'i, T> = Result<(&'i str, (T, Span)), ErrorKind<ParseError>>;
 
@@ -23,20 +29,232 @@ pub(crate) enum ErrorKind<E> {
     Failure(E),
 }
 
+impl<E> ErrorKind<E> {
+    /// The underlying error, regardless of whether it is recoverable
+    pub(crate) fn into_inner(self) -> E {
+        match self {
+            Self::Error(e) => e,
+            Self::Failure(e) => e,
+        }
+    }
+}
+
+/// A copy of a component definition, made for a single use of the component
+///
+/// The spans of the copy are those of the definition moved to `offset`, after
+/// everything that was parsed so far, so that diagnostics can tell the copies
+/// of a definition apart.
+struct Expansion {
+    offset: usize,
+    definition: Span,
+    /// The span of the use of the component
+    call: Span,
+}
+
+impl Expansion {
+    /// Whether a position lies within the spans of the copy
+    fn contains(&self, position: usize) -> bool {
+        (self.offset..=self.offset + self.definition.end - self.definition.start)
+            .contains(&position)
+    }
+
+    /// The span of the definition that a span in the copy was copied from
+    fn original(&self, span: Span) -> Span {
+        Span::new(
+            span.start - self.offset + self.definition.start,
+            span.end - self.offset + self.definition.start,
+        )
+    }
+}
+
 /// Wrapper around all the information needed for parsing
-#[derive(Clone)]
 pub(crate) struct Parser {
     file: SimpleFile<String, String>,
+    /// All imported files, along with the offset at which their spans start
+    ///
+    /// Spans in imported files never overlap with spans in the page itself or
+    /// in other imported files
+    imports: RefCell<Vec<(usize, SimpleFile<String, String>)>>,
+    /// The names of all user-defined components, along with the span of the
+    /// name in their definition
+    components: RefCell<HashMap<String, Span>>,
+    /// Every copy of a component definition made for a use of it, in the order
+    /// they were made
+    expansions: RefCell<Vec<Expansion>>,
+    /// Storage for any text that is not part of the page source
+    arena: Arena<u8>,
+    /// All warnings produced so far
+    warnings: RefCell<Vec<ParseError>>,
+    /// Whether the page is untrusted, so that it may only read files in its
+    /// own directory
+    safe: bool,
 }
 
 impl Parser {
     /// Make a new [`Parser`]
     pub(crate) fn new(file: SimpleFile<String, String>) -> Self {
-        Self { file }
+        Self {
+            file,
+            imports: RefCell::new(vec![]),
+            components: RefCell::new(HashMap::new()),
+            expansions: RefCell::new(vec![]),
+            arena: Arena::new(),
+            warnings: RefCell::new(vec![]),
+            safe: false,
+        }
+    }
+
+    /// Only allow the page to read files in its own directory, or below it
+    pub(crate) fn safe_mode(mut self, safe: bool) -> Self {
+        self.safe = safe;
+        self
+    }
+
+    /// Whether the page is untrusted
+    pub(crate) fn is_safe(&self) -> bool {
+        self.safe
+    }
+
+    /// Store a string for as long as the parser lives
+    pub(crate) fn alloc_str(&self, s: &str) -> &str {
+        self.arena.alloc_str(s)
+    }
+
+    /// The offset after everything that was parsed or expanded so far, at
+    /// which new spans can start without overlapping any other span
+    pub(crate) fn next_offset(&self) -> usize {
+        let files_end = match self.imports.borrow().last() {
+            Some((offset, file)) => offset + file.source().len(),
+            None => self.file.source().len(),
+        };
+        let expansions_end = self.expansions.borrow().last().map_or(0, |expansion| {
+            expansion.offset + expansion.definition.end - expansion.definition.start
+        });
+
+        files_end.max(expansions_end) + 1
+    }
+
+    /// Move a span in a copy of a component definition back to the
+    /// definition, or `None` if it starts and ends in different copies
+    fn original_span(&self, span: Span) -> Option<Span> {
+        let expansions = self.expansions.borrow();
+        let find = |position| expansions.iter().position(|e| e.contains(position));
+
+        match (find(span.start), find(span.end)) {
+            (None, None) => Some(span),
+            (Some(start), Some(end)) if start == end => Some(expansions[start].original(span)),
+            _ => None,
+        }
+    }
+
+    /// Apply a function to the file containing a span, along with the span
+    /// relative to the start of that file
+    ///
+    /// Spans starting and ending in different copies of component
+    /// definitions are reduced to their start.
+    fn with_file<T>(
+        &self,
+        span: Span,
+        f: impl FnOnce(&SimpleFile<String, String>, Span) -> T,
+    ) -> T {
+        let span = self.original_span(span).unwrap_or_else(|| {
+            let start = self
+                .original_span(Span::new(span.start, span.start))
+                .unwrap();
+            Span::new(start.start, start.start)
+        });
+        let imports = self.imports.borrow();
+
+        match imports
+            .iter()
+            .rev()
+            .find(|(offset, _)| span.start >= *offset)
+        {
+            Some((offset, file)) => f(file, Span::new(span.start - offset, span.end - offset)),
+            None => f(&self.file, span),
+        }
     }
 
-    fn make_error(&self, span: Span, ty: ParseErrorType) -> ParseError {
-        ParseError::new(self.file.clone(), span, ty)
+    /// Find the file containing a span, along with the span relative to the
+    /// start of that file
+    fn locate(&self, span: Span) -> (SimpleFile<String, String>, Span) {
+        self.with_file(span, |file, span| (file.clone(), span))
+    }
+
+    /// Check whether the source code in a span satisfies a predicate
+    ///
+    /// Spans that do not lie within a single file never do
+    pub(crate) fn check_source(&self, span: Span, pred: impl FnOnce(&str) -> bool) -> bool {
+        let Some(span) = self.original_span(span) else {
+            return false;
+        };
+        if span.start > span.end {
+            return false;
+        }
+
+        self.with_file(span, |file, span| {
+            file.source().get(span.start..span.end).is_some_and(pred)
+        })
+    }
+
+    pub(crate) fn make_error(&self, span: Span, ty: ParseErrorType) -> ParseError {
+        let (file, located) = self.locate(span);
+        let mut error = ParseError::new(file, located, ty);
+
+        // Nodes in expanded components point into their definition, so also
+        // point at the use of the component they were copied for, and at the
+        // use of any component that one is in
+        let mut span = span;
+        loop {
+            let call = self
+                .expansions
+                .borrow()
+                .iter()
+                .find(|e| e.contains(span.start))
+                .map(|e| e.call);
+            let Some(call) = call else {
+                break;
+            };
+
+            error = self.add_related(error, call, "in this use of the component");
+            span = call;
+        }
+
+        error
+    }
+
+    /// Make room for a copy of a component definition for a use of it, so
+    /// that errors in the copy also point at that use
+    ///
+    /// Returns the offset the spans of the copy are moved by.
+    pub(crate) fn record_expansion(&self, definition: Span, call: Span) -> usize {
+        let offset = self.next_offset();
+        self.expansions.borrow_mut().push(Expansion {
+            offset,
+            definition,
+            call,
+        });
+
+        offset - definition.start
+    }
+
+    /// Report a problem that does not prevent transpilation
+    pub(crate) fn warn(&self, span: Span, ty: ParseErrorType) {
+        let warning = self.make_error(span, ty).into_warning();
+
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    /// Take all warnings produced so far
+    pub(crate) fn take_warnings(&self) -> Vec<ParseError> {
+        self.warnings.take()
+    }
+
+    /// Point out another location relevant to an error
+    pub(crate) fn add_related(&self, error: ParseError, span: Span, message: &str) -> ParseError {
+        let (file, span) = self.locate(span);
+
+        error.add_related(file, span, message.to_owned())
     }
 
     /// Keep applying a given combinator as long as it succeeds
@@ -63,7 +281,7 @@ impl Parser {
                         input = rest;
                     }
                     Err(ErrorKind::Error(_)) => return Ok((input, (values, span))),
-                    Err(ErrorKind::Failure(_)) => return Ok((input, (values, span))),
+                    Err(ErrorKind::Failure(e)) => return Err(ErrorKind::Failure(e)),
                 }
             }
         }
@@ -76,7 +294,20 @@ impl Parser {
     {
         move |input: &str, start: usize| match combinator(input, start) {
             Ok((rest, (o, span))) => Ok((rest, (Some(o), span))),
-            Err(_) => Ok((input, (None, Span::new(start, start)))),
+            Err(ErrorKind::Error(_)) => Ok((input, (None, Span::new(start, start)))),
+            Err(ErrorKind::Failure(e)) => Err(ErrorKind::Failure(e)),
+        }
+    }
+
+    /// Turns any recoverable error produced by a combinator into a failure,
+    /// preventing alternatives from being tried
+    fn cut<'i, O, F>(&self, combinator: F) -> impl Fn(&'i str, usize) -> ParseResult<'i, O>
+    where
+        F: Fn(&'i str, usize) -> ParseResult<'i, O>,
+    {
+        move |input: &str, start: usize| match combinator(input, start) {
+            Err(ErrorKind::Error(e)) => Err(ErrorKind::Failure(e)),
+            res => res,
         }
     }
 
@@ -84,11 +315,11 @@ impl Parser {
     fn tag<'p, 'i: 'p>(
         &'p self,
         tag: &'i str,
-    ) -> impl Fn(&'i str, usize) -> ParseResult<&'i str> + '_ {
+    ) -> impl Fn(&'i str, usize) -> ParseResult<'i, &'i str> + 'p {
         move |input: &str, start: usize| {
             let tag_len = tag.len();
             if tag_len > input.len() {
-                return Err(ErrorKind::Failure(self.make_error(
+                return Err(ErrorKind::Error(self.make_error(
                     Span::new(start, start + input.len()),
                     ParseErrorType::UnexpectedEof {
                         expected: tag.to_owned(),
@@ -96,18 +327,22 @@ impl Parser {
                 )));
             }
 
-            let (i_tag, rest) = input.split_at(tag_len);
-
-            if i_tag == tag {
+            if let Some(rest) = input.strip_prefix(tag) {
                 let span = Span::new(start, start + tag_len);
-                return Ok((rest, (i_tag, span)));
+                return Ok((rest, (&input[..tag_len], span)));
             }
 
+            // What was found instead ends on a character boundary, even when
+            // the tag does not
+            let found_len = (tag_len..=input.len())
+                .find(|&i| input.is_char_boundary(i))
+                .unwrap_or(input.len());
+
             Err(ErrorKind::Error(self.make_error(
-                Span::new(start, start + tag_len),
+                Span::new(start, start + found_len),
                 ParseErrorType::UnexpectedToken {
                     expected: tag.to_owned(),
-                    found: i_tag.to_owned(),
+                    found: input[..found_len].to_owned(),
                 },
             )))
         }
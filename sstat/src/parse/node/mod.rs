@@ -2,7 +2,10 @@
 
 mod node_inner;
 
+use std::path::{Component, Path, PathBuf};
+
 use super::*;
+use crate::pass::{Layout, Metadata};
 pub(crate) use node_inner::*;
 
 /// A generic identifier
@@ -58,7 +61,7 @@ impl Parser {
     fn take_text(&self) -> impl Fn(&str, usize) -> ParseResult<Text> + '_ {
         move |input: &str, start: usize| {
             if input.is_empty() {
-                return Err(ErrorKind::Failure(self.make_error(
+                return Err(ErrorKind::Error(self.make_error(
                     Span::new(start, start + 1),
                     ParseErrorType::UnexpectedToken {
                         expected: "TEXT".to_owned(),
@@ -67,22 +70,68 @@ impl Parser {
                 )));
             }
 
-            let mut idx = start;
-            let mut chars = input.chars();
-            let mut prev = chars.next().unwrap();
+            let mut len = input.len();
+            let mut escaped = false;
 
-            for curr in chars {
-                if (curr == '(' || curr == ')') && prev != '\\' {
+            for (idx, curr) in input.char_indices() {
+                if (curr == '(' || curr == ')') && !escaped {
+                    len = idx;
                     break;
                 }
 
-                prev = curr;
-                idx += 1;
+                escaped = curr == '\\' && !escaped;
+            }
+
+            if len == 0 {
+                return Err(ErrorKind::Error(self.make_error(
+                    Span::new(start, start + 1),
+                    ParseErrorType::UnexpectedToken {
+                        expected: "TEXT".to_owned(),
+                        found: input[..1].to_owned(),
+                    },
+                )));
+            }
+
+            let rest = unsafe { input.get_unchecked(len..) };
+            let text = unsafe { input.get_unchecked(..len) };
+            let span = Span::new(start, start + len);
+
+            let text = Text { text, span };
+
+            Ok((rest, (text, span)))
+        }
+    }
+}
+
+impl Parser {
+    /// Keep taking text until the first unbalanced, unescaped ')'
+    ///
+    /// Unlike [`Parser::take_text`], this allows nested parentheses, and may
+    /// return empty text
+    fn take_raw_text(&self) -> impl Fn(&str, usize) -> ParseResult<Text> + '_ {
+        move |input: &str, start: usize| {
+            let mut len = input.len();
+            let mut depth = 0usize;
+            let mut escaped = false;
+
+            for (idx, curr) in input.char_indices() {
+                if !escaped {
+                    match curr {
+                        '(' => depth += 1,
+                        ')' if depth == 0 => {
+                            len = idx;
+                            break;
+                        }
+                        ')' => depth -= 1,
+                        _ => (),
+                    }
+                }
+
+                escaped = curr == '\\' && !escaped;
             }
 
-            let rest = unsafe { input.get_unchecked(idx..) };
-            let text = unsafe { input.get_unchecked(..idx) };
-            let span = Span::new(start, idx);
+            let (text, rest) = input.split_at(len);
+            let span = Span::new(start, start + len);
 
             let text = Text { text, span };
 
@@ -98,13 +147,20 @@ pub(crate) struct Page<'s> {
     pub(crate) attributes: Vec<Attribute<'s>>,
     /// The document node of this page
     pub(crate) doc: DocNode<'s>,
+    /// The component definitions imported by this page
+    pub(crate) library: Vec<Node<'s>>,
+    /// Information about this page as a whole, collected after all other
+    /// passes
+    pub(crate) metadata: Metadata,
+    /// The layout this page is rendered into
+    pub(crate) layout: Layout,
 
     pub(crate) span: Span,
 }
 
 impl Parser {
     /// Parse the entire source code
-    pub fn parse(&self) -> Result<Page, ParseError> {
+    pub fn parse(&self) -> Result<Page<'_>, ParseError> {
         match self.parse_page() {
             Ok((_, (page, _))) => Ok(page),
             Err(ErrorKind::Error(e)) => Err(e),
@@ -113,7 +169,7 @@ impl Parser {
     }
 
     /// Parse a single page
-    pub(crate) fn parse_page(&self) -> ParseResult<Page> {
+    pub(crate) fn parse_page(&self) -> ParseResult<'_, Page<'_>> {
         let start = 0;
         let input = self.file.source();
 
@@ -121,7 +177,11 @@ impl Parser {
         let mut global_span = span;
 
         let (rest, (attributes, span)) = self.many(self.parse_attribute())(rest, span.end)?;
-        let attributes = attributes.into_iter().map(|(a, _)| a).collect();
+        let attributes: Vec<_> = attributes.into_iter().map(|(a, _)| a).collect();
+
+        let library = self
+            .parse_imports(&attributes)
+            .map_err(ErrorKind::Failure)?;
 
         let (rest, (doc, span)) = self.parse_doc_node()(rest, span.end)?;
 
@@ -130,11 +190,140 @@ impl Parser {
         let page = Page {
             attributes,
             doc,
+            library,
+            metadata: Metadata::default(),
+            layout: Layout::default(),
             span: global_span,
         };
 
         Ok((rest, (page, global_span)))
     }
+
+    /// Parse the component definitions in all files imported using an
+    /// `[import path]` attribute
+    ///
+    /// Paths are relative to the directory of the importing page
+    fn parse_imports(&self, attributes: &[Attribute]) -> Result<Vec<Node<'_>>, ParseError> {
+        let mut library = vec![];
+
+        for attribute in attributes {
+            if attribute.attribute_name.name != "import" {
+                continue;
+            }
+
+            let (source, offset) = self.import_file(attribute.attribute_value)?;
+            library.extend(self.parse_library(source, offset)?);
+        }
+
+        Ok(library)
+    }
+
+    /// Read a file named by an attribute value, relative to the directory of
+    /// the page, returning its source and the offset at which its spans start
+    ///
+    /// The file is placed after everything that was parsed so far, so that
+    /// diagnostics can point into it
+    pub(crate) fn import_file(&self, value: Text) -> Result<(&str, usize), ParseError> {
+        let path = value.text.trim();
+        let full_path = self.resolve_path(path, value.span)?;
+
+        let source = std::fs::read_to_string(&full_path).map_err(|e| {
+            self.make_error(
+                value.span,
+                ParseErrorType::ImportFailed {
+                    path: path.to_owned(),
+                    reason: e.to_string(),
+                },
+            )
+        })?;
+
+        Ok(self.register_file(&full_path, source))
+    }
+
+    /// Place the source of a file read by a pass after everything that was
+    /// parsed or expanded so far, returning it and the offset at which its spans start
+    pub(crate) fn register_file(&self, path: &Path, source: String) -> (&str, usize) {
+        let offset = self.next_offset();
+
+        let file = SimpleFile::new(path.display().to_string(), source.clone());
+        self.imports.borrow_mut().push((offset, file));
+
+        (self.alloc_str(&source), offset)
+    }
+
+    /// The location of a file named by a path relative to the directory of
+    /// the page
+    ///
+    /// In safe mode, the file must be in the directory of the page or below
+    /// it, even once symbolic links are followed.
+    pub(crate) fn resolve_path(&self, path: &str, span: Span) -> Result<PathBuf, ParseError> {
+        let dir = Path::new(self.file.name())
+            .parent()
+            .unwrap_or(Path::new(""));
+        let full_path = dir.join(path);
+
+        if self.is_safe() && !is_within(dir, Path::new(path)) {
+            return Err(self.make_error(
+                span,
+                ParseErrorType::UnsafePath {
+                    path: path.to_owned(),
+                },
+            ));
+        }
+
+        Ok(full_path)
+    }
+
+    /// Parse a file containing only component definitions
+    ///
+    /// ```ebnf
+    /// library = { defnode_node };
+    /// ```
+    fn parse_library<'i>(&self, input: &'i str, start: usize) -> Result<Vec<Node<'i>>, ParseError> {
+        let (rest, (nodes, span)) =
+            self.many(self.parse_node())(input, start).map_err(ErrorKind::into_inner)?;
+
+        let (rest, (_, span)) =
+            self.take_non_parseable()(rest, span.end).map_err(ErrorKind::into_inner)?;
+
+        if let Some(c) = rest.chars().next() {
+            return Err(self.make_error(
+                Span::new(span.end, span.end + c.len_utf8()),
+                ParseErrorType::ExpectedDefnode {
+                    found: c.to_string(),
+                },
+            ));
+        }
+
+        nodes
+            .into_iter()
+            .map(|(node, _)| match node {
+                Node::Defnode { .. } => Ok(node),
+                Node::Text { inner } => Err(self.make_error(
+                    inner.span,
+                    ParseErrorType::ExpectedDefnode {
+                        found: "TEXT".to_owned(),
+                    },
+                )),
+                _ => Err(self.make_error(
+                    node.span(),
+                    ParseErrorType::ExpectedDefnode {
+                        found: "node".to_owned(),
+                    },
+                )),
+            })
+            .collect()
+    }
+}
+
+/// Parse the value of a boolean attribute, which is true if it has no value,
+/// as in `[open]`, or if its value is `true`
+pub(crate) fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim() {
+        "" | "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
 }
 
 /// A list of attributes for a given node
@@ -199,6 +388,72 @@ impl Parser {
     }
 }
 
+impl Parser {
+    /// Parse a source consisting solely of attributes
+    pub(crate) fn parse_attributes(&self) -> Result<Vec<Attribute<'_>>, ParseError> {
+        let input = self.file.source();
+
+        let (rest, (attributes, span)) =
+            self.many(self.parse_attribute())(input, 0).map_err(ErrorKind::into_inner)?;
+
+        let (rest, _) = self.take_non_parseable()(rest, span.end).map_err(ErrorKind::into_inner)?;
+
+        if let Some(c) = rest.chars().next() {
+            let start = input.len() - rest.len();
+
+            return Err(self.make_error(
+                Span::new(start, start + c.len_utf8()),
+                ParseErrorType::UnexpectedToken {
+                    expected: "[".to_owned(),
+                    found: c.to_string(),
+                },
+            ));
+        }
+
+        Ok(attributes.into_iter().map(|(a, _)| a).collect())
+    }
+
+    /// Split the value of a `[let name value]` attribute into the name and
+    /// the value of the variable it defines
+    ///
+    /// ```ebnf
+    /// let_attribute = "[", "let", identifier, ?TOKENS?, "]";
+    /// ```
+    pub(crate) fn parse_binding<'s>(
+        &self,
+        attribute: &Attribute<'s>,
+    ) -> Result<(Identifier<'s>, Text<'s>), ParseError> {
+        let Text { text, span } = attribute.attribute_value;
+
+        let (rest, (name, name_span)) =
+            self.take_identifier()(text, span.start).map_err(ErrorKind::into_inner)?;
+
+        if let Some(c) = rest.chars().next().filter(|c| !c.is_whitespace()) {
+            return Err(self.make_error(
+                Span::new(name_span.end, name_span.end + c.len_utf8()),
+                ParseErrorType::UnexpectedToken {
+                    expected: "whitespace".to_owned(),
+                    found: c.to_string(),
+                },
+            ));
+        }
+
+        let value = rest.trim();
+        let value_start = span.start + (text.len() - rest.trim_start().len());
+
+        let name = Identifier {
+            name,
+            span: name_span,
+        };
+        let value = Text {
+            text: value,
+            span: Span::new(value_start, value_start + value.len()),
+        };
+
+        Ok((name, value))
+    }
+}
+
 /// A document node, contains all the markup of a single page
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DocNode<'s> {
@@ -267,3 +522,31 @@ impl Parser {
         }
     }
 }
+
+/// Whether a relative path stays within a directory, both as written and once
+/// any symbolic links along it are followed
+fn is_within(dir: &Path, path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => (),
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+
+    // Files that do not exist cannot be read, but may still be checked for
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    match (dir.canonicalize(), dir.join(path).canonicalize()) {
+        (Ok(dir), Ok(path)) => path.starts_with(dir),
+        _ => true,
+    }
+}
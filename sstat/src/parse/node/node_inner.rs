@@ -1,6 +1,8 @@
 //! AST node definitions
 
 use super::*;
+use crate::bibliography::Reference;
+use crate::pass::{Align, Footnote, ListofEntry};
 
 /// All possible types of nodes and their respective contents
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -23,13 +25,581 @@ pub(crate) enum Node<'s> {
         title: Title,
         attributes: Vec<Attribute<'s>>,
         inner: Vec<Node<'s>>,
+        /// The id generated from the text of the title, once it has been
+        /// assigned, unless it has an `[id ...]`
+        slug: Option<String>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Header {
+        lparen: LParen,
+        header: Header,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The id generated from the text of the header, once it has been
+        /// assigned, unless it has an `[id ...]`
+        slug: Option<String>,
         rparen: RParen,
 
         span: Span,
     },
     P {
         lparen: LParen,
-        p: P,
+        p: P,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    B {
+        lparen: LParen,
+        b: B,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    I {
+        lparen: LParen,
+        i: I,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Mono {
+        lparen: LParen,
+        mono: Mono,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    U {
+        lparen: LParen,
+        u: U,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    S {
+        lparen: LParen,
+        s: S,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Mark {
+        lparen: LParen,
+        mark: Mark,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Sub {
+        lparen: LParen,
+        sub: Sub,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Sup {
+        lparen: LParen,
+        sup: Sup,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Kbd {
+        lparen: LParen,
+        kbd: Kbd,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Abbr {
+        lparen: LParen,
+        abbr: Abbr,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Q {
+        lparen: LParen,
+        q: Q,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Time {
+        lparen: LParen,
+        time: Time,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Small {
+        lparen: LParen,
+        small: Small,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Del {
+        lparen: LParen,
+        del: Del,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Ins {
+        lparen: LParen,
+        ins: Ins,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Span {
+        lparen: LParen,
+        span_: SpanKeyword,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Important {
+        lparen: LParen,
+        important: Important,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Note {
+        lparen: LParen,
+        note: Note,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Tip {
+        lparen: LParen,
+        tip: Tip,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Caution {
+        lparen: LParen,
+        caution: Caution,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Warning {
+        lparen: LParen,
+        warning: Warning,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Details {
+        lparen: LParen,
+        details: Details,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Div {
+        lparen: LParen,
+        div: Div,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    List {
+        lparen: LParen,
+        list: List,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Ordlist {
+        lparen: LParen,
+        ordlist: Ordlist,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Li {
+        lparen: LParen,
+        li: Li,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Dlist {
+        lparen: LParen,
+        dlist: Dlist,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Dterm {
+        lparen: LParen,
+        dterm: Dterm,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Ddesc {
+        lparen: LParen,
+        ddesc: Ddesc,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Image {
+        lparen: LParen,
+        image: Image,
+        attributes: Vec<Attribute<'s>>,
+        /// The figure number of this image, once it has been numbered
+        number: Option<usize>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Video {
+        lparen: LParen,
+        video: Video,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The MIME type of the file given by `[src ...]`, once it has been
+        /// inferred
+        mime: Option<&'static str>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Audio {
+        lparen: LParen,
+        audio: Audio,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The MIME type of the file given by `[src ...]`, once it has been
+        /// inferred
+        mime: Option<&'static str>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Source {
+        lparen: LParen,
+        source: Source,
+        attributes: Vec<Attribute<'s>>,
+        /// The MIME type of the file, once it has been inferred
+        mime: Option<&'static str>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Table {
+        lparen: LParen,
+        table: Table,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The table number of this table, if it has a caption, once it has
+        /// been numbered
+        number: Option<usize>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Thead {
+        lparen: LParen,
+        thead: Thead,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Tfoot {
+        lparen: LParen,
+        tfoot: Tfoot,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Trow {
+        lparen: LParen,
+        trow: Trow,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Tcell {
+        lparen: LParen,
+        tcell: Tcell,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The alignment of the column this cell starts in, once its table has
+        /// been laid out
+        align: Option<Align>,
+        /// Whether the cell was imported from a CSV file, so that its text is
+        /// kept as is instead of being unescaped and typeset
+        literal: bool,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Codeblock {
+        lparen: LParen,
+        codeblock: Codeblock,
+        attributes: Vec<Attribute<'s>>,
+        text: Text<'s>,
+        /// The listing number of this codeblock, if it has a caption, once it
+        /// has been numbered
+        number: Option<usize>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Html {
+        lparen: LParen,
+        html: Html,
+        attributes: Vec<Attribute<'s>>,
+        text: Text<'s>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Math {
+        lparen: LParen,
+        math: Math,
+        attributes: Vec<Attribute<'s>>,
+        text: Text<'s>,
+        formula: MathExpr,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Mathblock {
+        lparen: LParen,
+        mathblock: Mathblock,
+        attributes: Vec<Attribute<'s>>,
+        text: Text<'s>,
+        formula: MathExpr,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Var {
+        lparen: LParen,
+        var: Var,
+        attributes: Vec<Attribute<'s>>,
+        name: Identifier<'s>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Ref {
+        lparen: LParen,
+        ref_: Ref,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The label shown if this reference has no children, once it has been
+        /// resolved
+        label: Option<String>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Listof {
+        lparen: LParen,
+        listof: Listof,
+        attributes: Vec<Attribute<'s>>,
+        /// The nodes listed, once all nodes have been numbered
+        entries: Vec<ListofEntry>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Cite {
+        lparen: LParen,
+        cite: Cite,
+        attributes: Vec<Attribute<'s>>,
+        /// The label this citation is shown as, once it has been resolved
+        label: Option<String>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Bibliography {
+        lparen: LParen,
+        bibliography: Bibliography,
+        attributes: Vec<Attribute<'s>>,
+        /// The entries listed, once all citations have been resolved
+        references: Vec<Reference>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Fnote {
+        lparen: LParen,
+        fnote: Fnote,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        /// The number of this footnote, once all footnotes have been numbered
+        number: Option<usize>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Fnoteref {
+        lparen: LParen,
+        fnoteref: Fnoteref,
+        attributes: Vec<Attribute<'s>>,
+        /// The number of the footnote referenced, once all footnotes have
+        /// been numbered
+        number: Option<usize>,
+        /// The content of the footnote if this is its first reference, once
+        /// all footnotes have been numbered
+        note: Option<Vec<Node<'s>>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Fnotedef {
+        lparen: LParen,
+        fnotedef: Fnotedef,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Fnotes {
+        lparen: LParen,
+        fnotes: Fnotes,
+        attributes: Vec<Attribute<'s>>,
+        /// The footnotes listed, once all footnotes have been numbered
+        footnotes: Vec<Footnote<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    Defnode {
+        lparen: LParen,
+        defnode: Defnode,
+        name: Identifier<'s>,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Slot {
+        lparen: LParen,
+        slot: Slot,
+        attributes: Vec<Attribute<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    If {
+        lparen: LParen,
+        if_: If,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+    Else {
+        lparen: LParen,
+        else_: Else,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+
+        span: Span,
+    },
+
+    /// A use of a user-defined component
+    Component {
+        lparen: LParen,
+        name: Identifier<'s>,
         attributes: Vec<Attribute<'s>>,
         inner: Vec<Node<'s>>,
         rparen: RParen,
@@ -38,6 +608,1378 @@ pub(crate) enum Node<'s> {
     },
 }
 
+/// The names of all built-in nodes that consist of only attributes and child
+/// nodes
+const CONTAINER_NODE_NAMES: &[&str] = &[
+    "sec",
+    "title",
+    "header",
+    "p",
+    "important",
+    "note",
+    "tip",
+    "caution",
+    "warning",
+    "details",
+    "div",
+    "list",
+    "ordlist",
+    "li",
+    "dlist",
+    "dterm",
+    "ddesc",
+    "video",
+    "audio",
+    "table",
+    "thead",
+    "tfoot",
+    "trow",
+    "tcell",
+    "b",
+    "i",
+    "mono",
+    "u",
+    "s",
+    "mark",
+    "sub",
+    "sup",
+    "kbd",
+    "abbr",
+    "q",
+    "time",
+    "small",
+    "del",
+    "ins",
+    "span",
+    "ref",
+    "fnote",
+    "fnotedef",
+    "if",
+    "else",
+];
+
+/// The names of all other built-in nodes that can appear inside a document
+const SPECIAL_NODE_NAMES: &[&str] = &[
+    "image",
+    "source",
+    "codeblock",
+    "html",
+    "math",
+    "mathblock",
+    "var",
+    "listof",
+    "cite",
+    "bibliography",
+    "fnoteref",
+    "fnotes",
+    "defnode",
+    "slot",
+];
+
+/// The names of all built-in nodes that can appear inside a document
+pub(crate) fn builtin_node_names() -> impl Iterator<Item = &'static str> {
+    CONTAINER_NODE_NAMES
+        .iter()
+        .chain(SPECIAL_NODE_NAMES)
+        .copied()
+}
+
+impl<'s> Node<'s> {
+    /// Make a node that consists of only attributes and child nodes
+    ///
+    /// `name` must be one of [`CONTAINER_NODE_NAMES`]
+    #[allow(clippy::too_many_arguments)]
+    fn container(
+        name: &str,
+        lparen: LParen,
+        keyword: Span,
+        attributes: Vec<Attribute<'s>>,
+        inner: Vec<Node<'s>>,
+        rparen: RParen,
+        span: Span,
+    ) -> Self {
+        match name {
+            "sec" => Self::Sec {
+                lparen,
+                sec: Sec { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "title" => Self::Title {
+                lparen,
+                title: Title { span: keyword },
+                attributes,
+                inner,
+                slug: None,
+                rparen,
+                span,
+            },
+            "header" => Self::Header {
+                lparen,
+                header: Header { span: keyword },
+                attributes,
+                inner,
+                slug: None,
+                rparen,
+                span,
+            },
+            "p" => Self::P {
+                lparen,
+                p: P { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "kbd" => Self::Kbd {
+                lparen,
+                kbd: Kbd { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "abbr" => Self::Abbr {
+                lparen,
+                abbr: Abbr { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "q" => Self::Q {
+                lparen,
+                q: Q { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "time" => Self::Time {
+                lparen,
+                time: Time { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "small" => Self::Small {
+                lparen,
+                small: Small { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "del" => Self::Del {
+                lparen,
+                del: Del { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "ins" => Self::Ins {
+                lparen,
+                ins: Ins { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "span" => Self::Span {
+                lparen,
+                span_: SpanKeyword { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "important" => Self::Important {
+                lparen,
+                important: Important { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "note" => Self::Note {
+                lparen,
+                note: Note { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "tip" => Self::Tip {
+                lparen,
+                tip: Tip { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "caution" => Self::Caution {
+                lparen,
+                caution: Caution { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "warning" => Self::Warning {
+                lparen,
+                warning: Warning { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "details" => Self::Details {
+                lparen,
+                details: Details { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "div" => Self::Div {
+                lparen,
+                div: Div { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "list" => Self::List {
+                lparen,
+                list: List { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "ordlist" => Self::Ordlist {
+                lparen,
+                ordlist: Ordlist { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "li" => Self::Li {
+                lparen,
+                li: Li { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "dlist" => Self::Dlist {
+                lparen,
+                dlist: Dlist { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "dterm" => Self::Dterm {
+                lparen,
+                dterm: Dterm { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "ddesc" => Self::Ddesc {
+                lparen,
+                ddesc: Ddesc { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "video" => Self::Video {
+                lparen,
+                video: Video { span: keyword },
+                attributes,
+                inner,
+                mime: None,
+                rparen,
+                span,
+            },
+            "audio" => Self::Audio {
+                lparen,
+                audio: Audio { span: keyword },
+                attributes,
+                inner,
+                mime: None,
+                rparen,
+                span,
+            },
+            "table" => Self::Table {
+                lparen,
+                table: Table { span: keyword },
+                attributes,
+                inner,
+                number: None,
+                rparen,
+                span,
+            },
+            "thead" => Self::Thead {
+                lparen,
+                thead: Thead { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "tfoot" => Self::Tfoot {
+                lparen,
+                tfoot: Tfoot { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "trow" => Self::Trow {
+                lparen,
+                trow: Trow { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "tcell" => Self::Tcell {
+                lparen,
+                tcell: Tcell { span: keyword },
+                attributes,
+                inner,
+                align: None,
+                literal: false,
+                rparen,
+                span,
+            },
+            "b" => Self::B {
+                lparen,
+                b: B { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "i" => Self::I {
+                lparen,
+                i: I { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "mono" => Self::Mono {
+                lparen,
+                mono: Mono { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "u" => Self::U {
+                lparen,
+                u: U { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "s" => Self::S {
+                lparen,
+                s: S { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "mark" => Self::Mark {
+                lparen,
+                mark: Mark { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "sub" => Self::Sub {
+                lparen,
+                sub: Sub { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "sup" => Self::Sup {
+                lparen,
+                sup: Sup { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "ref" => Self::Ref {
+                lparen,
+                ref_: Ref { span: keyword },
+                attributes,
+                inner,
+                label: None,
+                rparen,
+                span,
+            },
+            "fnote" => Self::Fnote {
+                lparen,
+                fnote: Fnote { span: keyword },
+                attributes,
+                inner,
+                number: None,
+                rparen,
+                span,
+            },
+            "fnotedef" => Self::Fnotedef {
+                lparen,
+                fnotedef: Fnotedef { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "if" => Self::If {
+                lparen,
+                if_: If { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            "else" => Self::Else {
+                lparen,
+                else_: Else { span: keyword },
+                attributes,
+                inner,
+                rparen,
+                span,
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    /// The name of this node as written in the source
+    pub(crate) fn name(&self) -> &'s str {
+        match self {
+            Self::Text { .. } => "TEXT",
+            Self::Sec { .. } => "sec",
+            Self::Title { .. } => "title",
+            Self::Header { .. } => "header",
+            Self::P { .. } => "p",
+            Self::B { .. } => "b",
+            Self::I { .. } => "i",
+            Self::Mono { .. } => "mono",
+            Self::U { .. } => "u",
+            Self::S { .. } => "s",
+            Self::Mark { .. } => "mark",
+            Self::Sub { .. } => "sub",
+            Self::Sup { .. } => "sup",
+            Self::Kbd { .. } => "kbd",
+            Self::Abbr { .. } => "abbr",
+            Self::Q { .. } => "q",
+            Self::Time { .. } => "time",
+            Self::Small { .. } => "small",
+            Self::Del { .. } => "del",
+            Self::Ins { .. } => "ins",
+            Self::Span { .. } => "span",
+            Self::Important { .. } => "important",
+            Self::Note { .. } => "note",
+            Self::Tip { .. } => "tip",
+            Self::Caution { .. } => "caution",
+            Self::Warning { .. } => "warning",
+            Self::Details { .. } => "details",
+            Self::Div { .. } => "div",
+            Self::List { .. } => "list",
+            Self::Ordlist { .. } => "ordlist",
+            Self::Li { .. } => "li",
+            Self::Dlist { .. } => "dlist",
+            Self::Dterm { .. } => "dterm",
+            Self::Ddesc { .. } => "ddesc",
+            Self::Image { .. } => "image",
+            Self::Video { .. } => "video",
+            Self::Audio { .. } => "audio",
+            Self::Source { .. } => "source",
+            Self::Table { .. } => "table",
+            Self::Thead { .. } => "thead",
+            Self::Tfoot { .. } => "tfoot",
+            Self::Trow { .. } => "trow",
+            Self::Tcell { .. } => "tcell",
+            Self::Codeblock { .. } => "codeblock",
+            Self::Html { .. } => "html",
+            Self::Math { .. } => "math",
+            Self::Mathblock { .. } => "mathblock",
+            Self::Var { .. } => "var",
+            Self::Ref { .. } => "ref",
+            Self::Listof { .. } => "listof",
+            Self::Cite { .. } => "cite",
+            Self::Bibliography { .. } => "bibliography",
+            Self::Fnote { .. } => "fnote",
+            Self::Fnoteref { .. } => "fnoteref",
+            Self::Fnotedef { .. } => "fnotedef",
+            Self::Fnotes { .. } => "fnotes",
+            Self::Defnode { .. } => "defnode",
+            Self::Slot { .. } => "slot",
+            Self::If { .. } => "if",
+            Self::Else { .. } => "else",
+            Self::Component { name, .. } => name.name,
+        }
+    }
+
+    /// Whether this node is block content, as opposed to inline content
+    pub(crate) fn is_block(&self) -> bool {
+        matches!(
+            self,
+            Self::Sec { .. }
+                | Self::Title { .. }
+                | Self::Header { .. }
+                | Self::P { .. }
+                | Self::Important { .. }
+                | Self::Note { .. }
+                | Self::Tip { .. }
+                | Self::Caution { .. }
+                | Self::Warning { .. }
+                | Self::Details { .. }
+                | Self::Div { .. }
+                | Self::List { .. }
+                | Self::Ordlist { .. }
+                | Self::Li { .. }
+                | Self::Dlist { .. }
+                | Self::Dterm { .. }
+                | Self::Ddesc { .. }
+                | Self::Image { .. }
+                | Self::Video { .. }
+                | Self::Audio { .. }
+                | Self::Source { .. }
+                | Self::Table { .. }
+                | Self::Thead { .. }
+                | Self::Tfoot { .. }
+                | Self::Trow { .. }
+                | Self::Tcell { .. }
+                | Self::Codeblock { .. }
+                | Self::Mathblock { .. }
+                | Self::Listof { .. }
+                | Self::Bibliography { .. }
+                | Self::Fnotedef { .. }
+                | Self::Fnotes { .. }
+        )
+    }
+
+    /// Whether the children of this node are in block context, meaning any
+    /// inline content is wrapped in paragraphs
+    pub(crate) fn has_block_content(&self) -> bool {
+        matches!(
+            self,
+            Self::Sec { .. }
+                | Self::Important { .. }
+                | Self::Note { .. }
+                | Self::Tip { .. }
+                | Self::Caution { .. }
+                | Self::Warning { .. }
+                | Self::Details { .. }
+                | Self::Div { .. }
+        )
+    }
+
+    /// Whether the children of this node may only be inline content
+    pub(crate) fn has_inline_content(&self) -> bool {
+        matches!(
+            self,
+            Self::Title { .. }
+                | Self::Header { .. }
+                | Self::P { .. }
+                | Self::B { .. }
+                | Self::I { .. }
+                | Self::Mono { .. }
+                | Self::U { .. }
+                | Self::S { .. }
+                | Self::Mark { .. }
+                | Self::Sub { .. }
+                | Self::Sup { .. }
+                | Self::Kbd { .. }
+                | Self::Abbr { .. }
+                | Self::Q { .. }
+                | Self::Time { .. }
+                | Self::Small { .. }
+                | Self::Del { .. }
+                | Self::Ins { .. }
+                | Self::Span { .. }
+                | Self::Dterm { .. }
+                | Self::Ref { .. }
+                | Self::Fnote { .. }
+                | Self::Fnotedef { .. }
+        )
+    }
+
+    /// The region of source code spanned by this node
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Self::Text { inner } => inner.span,
+            Self::Sec { span, .. }
+            | Self::Title { span, .. }
+            | Self::Header { span, .. }
+            | Self::P { span, .. }
+            | Self::B { span, .. }
+            | Self::I { span, .. }
+            | Self::Mono { span, .. }
+            | Self::U { span, .. }
+            | Self::S { span, .. }
+            | Self::Mark { span, .. }
+            | Self::Sub { span, .. }
+            | Self::Sup { span, .. }
+            | Self::Kbd { span, .. }
+            | Self::Abbr { span, .. }
+            | Self::Q { span, .. }
+            | Self::Time { span, .. }
+            | Self::Small { span, .. }
+            | Self::Del { span, .. }
+            | Self::Ins { span, .. }
+            | Self::Span { span, .. }
+            | Self::Important { span, .. }
+            | Self::Note { span, .. }
+            | Self::Tip { span, .. }
+            | Self::Caution { span, .. }
+            | Self::Warning { span, .. }
+            | Self::Details { span, .. }
+            | Self::Div { span, .. }
+            | Self::List { span, .. }
+            | Self::Ordlist { span, .. }
+            | Self::Li { span, .. }
+            | Self::Dlist { span, .. }
+            | Self::Dterm { span, .. }
+            | Self::Ddesc { span, .. }
+            | Self::Image { span, .. }
+            | Self::Video { span, .. }
+            | Self::Audio { span, .. }
+            | Self::Source { span, .. }
+            | Self::Table { span, .. }
+            | Self::Thead { span, .. }
+            | Self::Tfoot { span, .. }
+            | Self::Trow { span, .. }
+            | Self::Tcell { span, .. }
+            | Self::Codeblock { span, .. }
+            | Self::Html { span, .. }
+            | Self::Math { span, .. }
+            | Self::Mathblock { span, .. }
+            | Self::Var { span, .. }
+            | Self::Ref { span, .. }
+            | Self::Listof { span, .. }
+            | Self::Cite { span, .. }
+            | Self::Bibliography { span, .. }
+            | Self::Fnote { span, .. }
+            | Self::Fnoteref { span, .. }
+            | Self::Fnotedef { span, .. }
+            | Self::Fnotes { span, .. }
+            | Self::Defnode { span, .. }
+            | Self::Slot { span, .. }
+            | Self::If { span, .. }
+            | Self::Else { span, .. }
+            | Self::Component { span, .. } => *span,
+        }
+    }
+
+    /// All spans of this node itself, leaving out those of its attributes and
+    /// children
+    pub(crate) fn spans_mut(&mut self) -> Vec<&mut Span> {
+        match self {
+            Self::Text { inner } => vec![&mut inner.span],
+            Self::Sec {
+                lparen,
+                sec,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut sec.span, &mut rparen.span, span],
+            Self::Title {
+                lparen,
+                title,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut title.span, &mut rparen.span, span],
+            Self::Header {
+                lparen,
+                header,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut header.span, &mut rparen.span, span],
+            Self::P {
+                lparen,
+                p,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut p.span, &mut rparen.span, span],
+            Self::B {
+                lparen,
+                b,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut b.span, &mut rparen.span, span],
+            Self::I {
+                lparen,
+                i,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut i.span, &mut rparen.span, span],
+            Self::Mono {
+                lparen,
+                mono,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut mono.span, &mut rparen.span, span],
+            Self::U {
+                lparen,
+                u,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut u.span, &mut rparen.span, span],
+            Self::S {
+                lparen,
+                s,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut s.span, &mut rparen.span, span],
+            Self::Mark {
+                lparen,
+                mark,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut mark.span, &mut rparen.span, span],
+            Self::Sub {
+                lparen,
+                sub,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut sub.span, &mut rparen.span, span],
+            Self::Sup {
+                lparen,
+                sup,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut sup.span, &mut rparen.span, span],
+            Self::Kbd {
+                lparen,
+                kbd,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut kbd.span, &mut rparen.span, span],
+            Self::Abbr {
+                lparen,
+                abbr,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut abbr.span, &mut rparen.span, span],
+            Self::Q {
+                lparen,
+                q,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut q.span, &mut rparen.span, span],
+            Self::Time {
+                lparen,
+                time,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut time.span, &mut rparen.span, span],
+            Self::Small {
+                lparen,
+                small,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut small.span, &mut rparen.span, span],
+            Self::Del {
+                lparen,
+                del,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut del.span, &mut rparen.span, span],
+            Self::Ins {
+                lparen,
+                ins,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut ins.span, &mut rparen.span, span],
+            Self::Span {
+                lparen,
+                span_,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut span_.span, &mut rparen.span, span],
+            Self::Important {
+                lparen,
+                important,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut important.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Note {
+                lparen,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut rparen.span, span],
+            Self::Tip {
+                lparen,
+                tip,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut tip.span, &mut rparen.span, span],
+            Self::Caution {
+                lparen,
+                caution,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut caution.span, &mut rparen.span, span],
+            Self::Warning {
+                lparen,
+                warning,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut warning.span, &mut rparen.span, span],
+            Self::Details {
+                lparen,
+                details,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut details.span, &mut rparen.span, span],
+            Self::Div {
+                lparen,
+                div,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut div.span, &mut rparen.span, span],
+            Self::List {
+                lparen,
+                list,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut list.span, &mut rparen.span, span],
+            Self::Ordlist {
+                lparen,
+                ordlist,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut ordlist.span, &mut rparen.span, span],
+            Self::Li {
+                lparen,
+                li,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut li.span, &mut rparen.span, span],
+            Self::Dlist {
+                lparen,
+                dlist,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut dlist.span, &mut rparen.span, span],
+            Self::Dterm {
+                lparen,
+                dterm,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut dterm.span, &mut rparen.span, span],
+            Self::Ddesc {
+                lparen,
+                ddesc,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut ddesc.span, &mut rparen.span, span],
+            Self::Image {
+                lparen,
+                image,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut image.span, &mut rparen.span, span],
+            Self::Video {
+                lparen,
+                video,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut video.span, &mut rparen.span, span],
+            Self::Audio {
+                lparen,
+                audio,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut audio.span, &mut rparen.span, span],
+            Self::Source {
+                lparen,
+                source,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut source.span, &mut rparen.span, span],
+            Self::Table {
+                lparen,
+                table,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut table.span, &mut rparen.span, span],
+            Self::Thead {
+                lparen,
+                thead,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut thead.span, &mut rparen.span, span],
+            Self::Tfoot {
+                lparen,
+                tfoot,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut tfoot.span, &mut rparen.span, span],
+            Self::Trow {
+                lparen,
+                trow,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut trow.span, &mut rparen.span, span],
+            Self::Tcell {
+                lparen,
+                tcell,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut tcell.span, &mut rparen.span, span],
+            Self::Codeblock {
+                lparen,
+                codeblock,
+                text,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut codeblock.span,
+                &mut text.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Html {
+                lparen,
+                html,
+                text,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut html.span,
+                &mut text.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Math {
+                lparen,
+                math,
+                text,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut math.span,
+                &mut text.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Mathblock {
+                lparen,
+                mathblock,
+                text,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut mathblock.span,
+                &mut text.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Var {
+                lparen,
+                var,
+                name,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut var.span,
+                &mut name.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Ref {
+                lparen,
+                ref_,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut ref_.span, &mut rparen.span, span],
+            Self::Listof {
+                lparen,
+                listof,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut listof.span, &mut rparen.span, span],
+            Self::Cite {
+                lparen,
+                cite,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut cite.span, &mut rparen.span, span],
+            Self::Bibliography {
+                lparen,
+                bibliography,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut bibliography.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Fnote {
+                lparen,
+                fnote,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut fnote.span, &mut rparen.span, span],
+            Self::Fnoteref {
+                lparen,
+                fnoteref,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut fnoteref.span, &mut rparen.span, span],
+            Self::Fnotedef {
+                lparen,
+                fnotedef,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut fnotedef.span, &mut rparen.span, span],
+            Self::Fnotes {
+                lparen,
+                fnotes,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut fnotes.span, &mut rparen.span, span],
+            Self::Defnode {
+                lparen,
+                defnode,
+                name,
+                rparen,
+                span,
+                ..
+            } => vec![
+                &mut lparen.span,
+                &mut defnode.span,
+                &mut name.span,
+                &mut rparen.span,
+                span,
+            ],
+            Self::Slot {
+                lparen,
+                slot,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut slot.span, &mut rparen.span, span],
+            Self::If {
+                lparen,
+                if_,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut if_.span, &mut rparen.span, span],
+            Self::Else {
+                lparen,
+                else_,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut else_.span, &mut rparen.span, span],
+            Self::Component {
+                lparen,
+                name,
+                rparen,
+                span,
+                ..
+            } => vec![&mut lparen.span, &mut name.span, &mut rparen.span, span],
+        }
+    }
+
+    /// The attributes of this node
+    pub(crate) fn attributes(&self) -> &[Attribute<'s>] {
+        match self {
+            Self::Text { .. } => &[],
+            Self::Sec { attributes, .. }
+            | Self::Title { attributes, .. }
+            | Self::Header { attributes, .. }
+            | Self::P { attributes, .. }
+            | Self::B { attributes, .. }
+            | Self::I { attributes, .. }
+            | Self::Mono { attributes, .. }
+            | Self::U { attributes, .. }
+            | Self::S { attributes, .. }
+            | Self::Mark { attributes, .. }
+            | Self::Sub { attributes, .. }
+            | Self::Sup { attributes, .. }
+            | Self::Kbd { attributes, .. }
+            | Self::Abbr { attributes, .. }
+            | Self::Q { attributes, .. }
+            | Self::Time { attributes, .. }
+            | Self::Small { attributes, .. }
+            | Self::Del { attributes, .. }
+            | Self::Ins { attributes, .. }
+            | Self::Span { attributes, .. }
+            | Self::Important { attributes, .. }
+            | Self::Note { attributes, .. }
+            | Self::Tip { attributes, .. }
+            | Self::Caution { attributes, .. }
+            | Self::Warning { attributes, .. }
+            | Self::Details { attributes, .. }
+            | Self::Div { attributes, .. }
+            | Self::List { attributes, .. }
+            | Self::Ordlist { attributes, .. }
+            | Self::Li { attributes, .. }
+            | Self::Dlist { attributes, .. }
+            | Self::Dterm { attributes, .. }
+            | Self::Ddesc { attributes, .. }
+            | Self::Image { attributes, .. }
+            | Self::Video { attributes, .. }
+            | Self::Audio { attributes, .. }
+            | Self::Source { attributes, .. }
+            | Self::Table { attributes, .. }
+            | Self::Thead { attributes, .. }
+            | Self::Tfoot { attributes, .. }
+            | Self::Trow { attributes, .. }
+            | Self::Tcell { attributes, .. }
+            | Self::Codeblock { attributes, .. }
+            | Self::Html { attributes, .. }
+            | Self::Math { attributes, .. }
+            | Self::Mathblock { attributes, .. }
+            | Self::Var { attributes, .. }
+            | Self::Ref { attributes, .. }
+            | Self::Listof { attributes, .. }
+            | Self::Cite { attributes, .. }
+            | Self::Bibliography { attributes, .. }
+            | Self::Fnote { attributes, .. }
+            | Self::Fnoteref { attributes, .. }
+            | Self::Fnotedef { attributes, .. }
+            | Self::Fnotes { attributes, .. }
+            | Self::Defnode { attributes, .. }
+            | Self::Slot { attributes, .. }
+            | Self::If { attributes, .. }
+            | Self::Else { attributes, .. }
+            | Self::Component { attributes, .. } => attributes,
+        }
+    }
+
+    /// The trimmed value of the last attribute of this node with a given name
+    pub(crate) fn attribute(&self, name: &str) -> Option<&'s str> {
+        self.attributes()
+            .iter()
+            .rfind(|a| a.attribute_name.name == name)
+            .map(|a| a.attribute_value.text.trim())
+    }
+
+    /// The attributes of this node, mutably
+    pub(crate) fn attributes_mut(&mut self) -> Option<&mut Vec<Attribute<'s>>> {
+        match self {
+            Self::Text { .. } => None,
+            Self::Sec { attributes, .. }
+            | Self::Title { attributes, .. }
+            | Self::Header { attributes, .. }
+            | Self::P { attributes, .. }
+            | Self::B { attributes, .. }
+            | Self::I { attributes, .. }
+            | Self::Mono { attributes, .. }
+            | Self::U { attributes, .. }
+            | Self::S { attributes, .. }
+            | Self::Mark { attributes, .. }
+            | Self::Sub { attributes, .. }
+            | Self::Sup { attributes, .. }
+            | Self::Kbd { attributes, .. }
+            | Self::Abbr { attributes, .. }
+            | Self::Q { attributes, .. }
+            | Self::Time { attributes, .. }
+            | Self::Small { attributes, .. }
+            | Self::Del { attributes, .. }
+            | Self::Ins { attributes, .. }
+            | Self::Span { attributes, .. }
+            | Self::Important { attributes, .. }
+            | Self::Note { attributes, .. }
+            | Self::Tip { attributes, .. }
+            | Self::Caution { attributes, .. }
+            | Self::Warning { attributes, .. }
+            | Self::Details { attributes, .. }
+            | Self::Div { attributes, .. }
+            | Self::List { attributes, .. }
+            | Self::Ordlist { attributes, .. }
+            | Self::Li { attributes, .. }
+            | Self::Dlist { attributes, .. }
+            | Self::Dterm { attributes, .. }
+            | Self::Ddesc { attributes, .. }
+            | Self::Image { attributes, .. }
+            | Self::Video { attributes, .. }
+            | Self::Audio { attributes, .. }
+            | Self::Source { attributes, .. }
+            | Self::Table { attributes, .. }
+            | Self::Thead { attributes, .. }
+            | Self::Tfoot { attributes, .. }
+            | Self::Trow { attributes, .. }
+            | Self::Tcell { attributes, .. }
+            | Self::Codeblock { attributes, .. }
+            | Self::Html { attributes, .. }
+            | Self::Math { attributes, .. }
+            | Self::Mathblock { attributes, .. }
+            | Self::Var { attributes, .. }
+            | Self::Ref { attributes, .. }
+            | Self::Listof { attributes, .. }
+            | Self::Cite { attributes, .. }
+            | Self::Bibliography { attributes, .. }
+            | Self::Fnote { attributes, .. }
+            | Self::Fnoteref { attributes, .. }
+            | Self::Fnotedef { attributes, .. }
+            | Self::Fnotes { attributes, .. }
+            | Self::Defnode { attributes, .. }
+            | Self::Slot { attributes, .. }
+            | Self::If { attributes, .. }
+            | Self::Else { attributes, .. }
+            | Self::Component { attributes, .. } => Some(attributes),
+        }
+    }
+
+    /// The child nodes of this node, if it can have any
+    pub(crate) fn children_mut(&mut self) -> Option<&mut Vec<Node<'s>>> {
+        match self {
+            Self::Text { .. }
+            | Self::Image { .. }
+            | Self::Source { .. }
+            | Self::Codeblock { .. }
+            | Self::Html { .. }
+            | Self::Math { .. }
+            | Self::Mathblock { .. }
+            | Self::Var { .. }
+            | Self::Listof { .. }
+            | Self::Cite { .. }
+            | Self::Bibliography { .. }
+            | Self::Fnoteref { .. }
+            | Self::Fnotes { .. }
+            | Self::Slot { .. } => None,
+            Self::Sec { inner, .. }
+            | Self::Title { inner, .. }
+            | Self::Header { inner, .. }
+            | Self::P { inner, .. }
+            | Self::Important { inner, .. }
+            | Self::Note { inner, .. }
+            | Self::Tip { inner, .. }
+            | Self::Caution { inner, .. }
+            | Self::Warning { inner, .. }
+            | Self::Details { inner, .. }
+            | Self::Div { inner, .. }
+            | Self::List { inner, .. }
+            | Self::Ordlist { inner, .. }
+            | Self::Li { inner, .. }
+            | Self::Dlist { inner, .. }
+            | Self::Dterm { inner, .. }
+            | Self::Ddesc { inner, .. }
+            | Self::Video { inner, .. }
+            | Self::Audio { inner, .. }
+            | Self::Table { inner, .. }
+            | Self::Thead { inner, .. }
+            | Self::Tfoot { inner, .. }
+            | Self::Trow { inner, .. }
+            | Self::Tcell { inner, .. }
+            | Self::B { inner, .. }
+            | Self::I { inner, .. }
+            | Self::Mono { inner, .. }
+            | Self::U { inner, .. }
+            | Self::S { inner, .. }
+            | Self::Mark { inner, .. }
+            | Self::Sub { inner, .. }
+            | Self::Sup { inner, .. }
+            | Self::Kbd { inner, .. }
+            | Self::Abbr { inner, .. }
+            | Self::Q { inner, .. }
+            | Self::Time { inner, .. }
+            | Self::Small { inner, .. }
+            | Self::Del { inner, .. }
+            | Self::Ins { inner, .. }
+            | Self::Span { inner, .. }
+            | Self::Ref { inner, .. }
+            | Self::Fnote { inner, .. }
+            | Self::Fnotedef { inner, .. }
+            | Self::Defnode { inner, .. }
+            | Self::If { inner, .. }
+            | Self::Else { inner, .. }
+            | Self::Component { inner, .. } => Some(inner),
+        }
+    }
+}
+
 impl Parser {
     /// Parse a nestable node
     ///
@@ -52,7 +1994,7 @@ impl Parser {
             // "("
             let (rest, (maybe_lparen_tag, span)) = self.optional(self.tag("("))(rest, span.end)?;
 
-            if let Some(_) = maybe_lparen_tag {
+            if maybe_lparen_tag.is_some() {
                 let lparen = LParen { span };
 
                 self.parse_regular_node_start(lparen)(rest, span.end)
@@ -78,11 +2020,12 @@ impl Parser {
         lparen: LParen,
     ) -> impl Fn(&'i str, usize) -> ParseResult<Node<'i>> + '_ {
         move |input: &str, start: usize| {
+            let mut global_span = lparen.span;
+
             let (rest, (_, span)) = self.take_non_parseable()(input, start)?;
-            let mut global_span = span;
 
             // <name>
-            let (rest, (node_name, name_span)) = self.take_identifier()(rest, span.end)?;
+            let (rest, (node_name, name_span)) = self.cut(self.take_identifier())(rest, span.end)?;
             let span = name_span;
 
             let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
@@ -93,13 +2036,472 @@ impl Parser {
 
             let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
 
-            let (rest, (node, span)) = match node_name {
-                _ => unimplemented!(),
-            };
+            match node_name {
+                "codeblock" => {
+                    let (rest, ((text, rparen), span)) = self.parse_raw_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let codeblock = Codeblock { span: name_span };
+                    let node = Node::Codeblock {
+                        lparen,
+                        codeblock,
+                        attributes,
+                        text,
+                        number: None,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "html" => {
+                    let (rest, ((text, rparen), span)) = self.parse_raw_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let html = Html { span: name_span };
+                    let node = Node::Html {
+                        lparen,
+                        html,
+                        attributes,
+                        text,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "math" => {
+                    let (rest, ((text, rparen), span)) = self.parse_raw_body()(rest, span.end)?;
+                    let formula = self.parse_formula(text).map_err(ErrorKind::Failure)?;
+                    global_span.end = span.end;
+
+                    let math = Math { span: name_span };
+                    let node = Node::Math {
+                        lparen,
+                        math,
+                        attributes,
+                        text,
+                        formula,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "mathblock" => {
+                    let (rest, ((text, rparen), span)) = self.parse_raw_body()(rest, span.end)?;
+                    let formula = self.parse_formula(text).map_err(ErrorKind::Failure)?;
+                    global_span.end = span.end;
+
+                    let mathblock = Mathblock { span: name_span };
+                    let node = Node::Mathblock {
+                        lparen,
+                        mathblock,
+                        attributes,
+                        text,
+                        formula,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "var" => {
+                    let (rest, ((name, rparen), span)) = self.parse_var_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let var = Var { span: name_span };
+                    let node = Node::Var {
+                        lparen,
+                        var,
+                        attributes,
+                        name,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "image" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let image = Image { span: name_span };
+                    let node = Node::Image {
+                        lparen,
+                        image,
+                        attributes,
+                        number: None,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "source" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let source = Source { span: name_span };
+                    let node = Node::Source {
+                        lparen,
+                        source,
+                        attributes,
+                        mime: None,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "listof" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let listof = Listof { span: name_span };
+                    let node = Node::Listof {
+                        lparen,
+                        listof,
+                        attributes,
+                        entries: vec![],
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "cite" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let cite = Cite { span: name_span };
+                    let node = Node::Cite {
+                        lparen,
+                        cite,
+                        attributes,
+                        label: None,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "bibliography" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let bibliography = Bibliography { span: name_span };
+                    let node = Node::Bibliography {
+                        lparen,
+                        bibliography,
+                        attributes,
+                        references: vec![],
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "fnoteref" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let fnoteref = Fnoteref { span: name_span };
+                    let node = Node::Fnoteref {
+                        lparen,
+                        fnoteref,
+                        attributes,
+                        number: None,
+                        note: None,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "fnotes" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let fnotes = Fnotes { span: name_span };
+                    let node = Node::Fnotes {
+                        lparen,
+                        fnotes,
+                        attributes,
+                        footnotes: vec![],
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "defnode" => {
+                    let (rest, ((name, params, inner, rparen), span)) =
+                        self.parse_defnode_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    self.register_component(name).map_err(ErrorKind::Failure)?;
+
+                    let mut attributes = attributes;
+                    attributes.extend(params);
+
+                    let defnode = Defnode { span: name_span };
+                    let node = Node::Defnode {
+                        lparen,
+                        defnode,
+                        name,
+                        attributes,
+                        inner,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                "slot" => {
+                    let (rest, (rparen, span)) = self.parse_empty_body()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let slot = Slot { span: name_span };
+                    let node = Node::Slot {
+                        lparen,
+                        slot,
+                        attributes,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                _ if CONTAINER_NODE_NAMES.contains(&node_name) => {
+                    let (rest, ((inner, rparen), span)) = self.parse_children()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let node = Node::container(
+                        node_name,
+                        lparen,
+                        name_span,
+                        attributes,
+                        inner,
+                        rparen,
+                        global_span,
+                    );
+
+                    Ok((rest, (node, global_span)))
+                }
+                _ if self.components.borrow().contains_key(node_name) => {
+                    let (rest, ((inner, rparen), span)) = self.parse_children()(rest, span.end)?;
+                    global_span.end = span.end;
+
+                    let name = Identifier {
+                        name: node_name,
+                        span: name_span,
+                    };
+                    let node = Node::Component {
+                        lparen,
+                        name,
+                        attributes,
+                        inner,
+                        rparen,
+                        span: global_span,
+                    };
+
+                    Ok((rest, (node, global_span)))
+                }
+                _ => {
+                    let mut error = self.make_error(
+                        name_span,
+                        ParseErrorType::UnknownNode {
+                            name: node_name.to_owned(),
+                        },
+                    );
+
+                    let components = self.components.borrow();
+                    let candidates = builtin_node_names()
+                        .map(|n| n as &str)
+                        .chain(components.keys().map(String::as_str));
+                    if let Some(suggestion) = did_you_mean(node_name, candidates) {
+                        error = error.add_context(format!("did you mean '{suggestion}'?"));
+                    }
+
+                    Err(ErrorKind::Failure(error))
+                }
+            }
+        }
+    }
+
+    /// Parse the children and closing delimiter of a regular node
+    ///
+    /// ```ebnf
+    /// children = { node }, ")";
+    /// ```
+    pub(crate) fn parse_children<'i>(
+        &self,
+    ) -> impl Fn(&'i str, usize) -> ParseResult<(Vec<Node<'i>>, RParen)> + '_ {
+        move |input: &str, start: usize| {
+            let mut global_span = Span::new(start, start);
+
+            // { node }
+            let (rest, (nodes, span)) = self.many(self.parse_node())(input, start)?;
+            let nodes = nodes.into_iter().map(|(n, _)| n).collect();
+
+            let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
+
+            // ")"
+            let (rest, (_tag, span)) = self.cut(self.tag(")"))(rest, span.end)?;
+            let rparen = RParen { span };
+
+            global_span.end = span.end;
+
+            Ok((rest, ((nodes, rparen), global_span)))
+        }
+    }
+
+    /// Parse the unprocessed content and closing delimiter of a raw node
+    ///
+    /// ```ebnf
+    /// raw_body = ?RAW_TEXT?, ")";
+    /// ```
+    pub(crate) fn parse_raw_body<'i>(
+        &self,
+    ) -> impl Fn(&'i str, usize) -> ParseResult<(Text<'i>, RParen)> + '_ {
+        move |input: &str, start: usize| {
+            let mut global_span = Span::new(start, start);
+
+            // ?RAW_TEXT?
+            let (rest, (text, span)) = self.take_raw_text()(input, start)?;
+
+            // ")"
+            let (rest, (_tag, span)) = self.cut(self.tag(")"))(rest, span.end)?;
+            let rparen = RParen { span };
+
+            global_span.end = span.end;
+
+            Ok((rest, ((text, rparen), global_span)))
+        }
+    }
+
+    /// Parse the closing delimiter of a node that consists of only
+    /// attributes
+    ///
+    /// ```ebnf
+    /// empty_body = ")";
+    /// ```
+    pub(crate) fn parse_empty_body<'i>(
+        &self,
+    ) -> impl Fn(&'i str, usize) -> ParseResult<RParen> + '_ {
+        move |input: &str, start: usize| {
+            // ")"
+            let (rest, (_tag, span)) = self.cut(self.tag(")"))(input, start)?;
+            let rparen = RParen { span };
+
+            Ok((rest, (rparen, span)))
+        }
+    }
+
+    /// Parse the name and closing delimiter of a variable substitution node
+    ///
+    /// ```ebnf
+    /// var_node = "(", "var", { attribute }, identifier, ")";
+    /// ```
+    pub(crate) fn parse_var_body<'i>(
+        &self,
+    ) -> impl Fn(&'i str, usize) -> ParseResult<(Identifier<'i>, RParen)> + '_ {
+        move |input: &str, start: usize| {
+            let mut global_span = Span::new(start, start);
+
+            // identifier
+            let (rest, (name, span)) = self.cut(self.take_identifier())(input, start)?;
+            let name = Identifier { name, span };
+
+            let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
+
+            // ")"
+            let (rest, (_tag, span)) = self.cut(self.tag(")"))(rest, span.end)?;
+            let rparen = RParen { span };
+
+            global_span.end = span.end;
+
+            Ok((rest, ((name, rparen), global_span)))
+        }
+    }
+
+    /// Parse the name, parameters, and body of a component definition
+    ///
+    /// ```ebnf
+    /// defnode_node = "(", "defnode", identifier, { attribute }, { node }, ")";
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn parse_defnode_body<'i>(
+        &self,
+    ) -> impl Fn(
+        &'i str,
+        usize,
+    ) -> ParseResult<'i, (Identifier<'i>, Vec<Attribute<'i>>, Vec<Node<'i>>, RParen)>
+           + '_ {
+        move |input: &str, start: usize| {
+            let mut global_span = Span::new(start, start);
+
+            // identifier
+            let (rest, (name, span)) = self.cut(self.take_identifier())(input, start)?;
+            let name = Identifier { name, span };
+
+            let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
+
+            // { attribute }
+            let (rest, (attributes, span)) = self.many(self.parse_attribute())(rest, span.end)?;
+            let attributes = attributes.into_iter().map(|(a, _)| a).collect();
+
+            let (rest, (_, span)) = self.take_non_parseable()(rest, span.end)?;
+
+            // { node }, ")"
+            let (rest, ((inner, rparen), span)) = self.parse_children()(rest, span.end)?;
 
             global_span.end = span.end;
 
-            Ok((rest, (node, global_span)))
+            Ok((rest, ((name, attributes, inner, rparen), global_span)))
+        }
+    }
+
+    /// Parse the formula of a `math` or `mathblock` node
+    fn parse_formula(&self, text: Text) -> Result<MathExpr, ParseError> {
+        parse_formula(text.text).map_err(|e| {
+            let span = Span::new(text.span.start + e.start, text.span.start + e.end);
+            let error = self.make_error(span, ParseErrorType::InvalidFormula { reason: e.reason });
+
+            match e.suggestion {
+                Some(suggestion) => error.add_context(format!("did you mean '\\{suggestion}'?")),
+                None => error,
+            }
+        })
+    }
+
+    /// Make a user-defined component available to the rest of the page
+    fn register_component(&self, name: Identifier) -> Result<(), ParseError> {
+        let mut components = self.components.borrow_mut();
+
+        if let Some(previous) = components.get(name.name) {
+            let error = self.make_error(
+                name.span,
+                ParseErrorType::RedefinedNode {
+                    name: name.name.to_owned(),
+                },
+            );
+
+            return Err(self.add_related(error, *previous, "previously defined here"));
         }
+
+        if builtin_node_names().any(|n| n == name.name) {
+            return Err(self.make_error(
+                name.span,
+                ParseErrorType::RedefinedNode {
+                    name: name.name.to_owned(),
+                },
+            ));
+        }
+
+        components.insert(name.name.to_owned(), name.span);
+
+        Ok(())
     }
 }
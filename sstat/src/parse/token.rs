@@ -37,8 +37,64 @@ define_delimiters! {
 }
 
 define_keywords! {
-    "doc"   Doc   /// The top level document node
-    "sec"   Sec   /// A section
-    "title" Title /// A title
-    "p"     P     /// A paragraph
+    "doc"          Doc          /// The top level document node
+    "sec"          Sec          /// A section
+    "title"        Title        /// A title
+    "header"       Header       /// A title left out of the table of contents
+    "p"            P            /// A paragraph
+    "b"            B            /// Bold text
+    "i"            I            /// Italic text
+    "mono"         Mono         /// Monospace text
+    "u"            U            /// Underlined text
+    "s"            S            /// Strikethrough text
+    "mark"         Mark         /// Highlighted text
+    "sub"          Sub          /// Subscript text
+    "sup"          Sup          /// Superscript text
+    "kbd"          Kbd          /// Keyboard input
+    "abbr"         Abbr         /// An abbreviation
+    "q"            Q            /// An inline quotation
+    "time"         Time         /// A date or time
+    "small"        Small        /// Side comments and fine print
+    "del"          Del          /// Text removed in an edit
+    "ins"          Ins          /// Text added in an edit
+    "important"    Important    /// An important admonition
+    "note"         Note         /// A note admonition
+    "tip"          Tip          /// A tip admonition
+    "caution"      Caution      /// A caution admonition
+    "warning"      Warning      /// A warning admonition
+    "details"      Details      /// Content that can be expanded and collapsed
+    "list"         List         /// An unordered list
+    "ordlist"      Ordlist      /// An ordered list
+    "li"           Li           /// A list item
+    "dlist"        Dlist        /// A description list
+    "dterm"        Dterm        /// A term in a description list
+    "ddesc"        Ddesc        /// The description of a term in a description list
+    "div"          Div          /// A generic block
+    "span"         SpanKeyword  /// A generic inline element
+    "image"        Image        /// An image with a caption
+    "video"        Video        /// A video
+    "audio"        Audio        /// An audio clip
+    "source"       Source       /// One of the source files of a video or audio clip
+    "table"        Table        /// A table
+    "thead"        Thead        /// The header rows of a table
+    "tfoot"        Tfoot        /// The footer rows of a table
+    "trow"         Trow         /// A table row
+    "tcell"        Tcell        /// A table cell
+    "codeblock"    Codeblock    /// A block of code
+    "html"         Html         /// Raw HTML
+    "math"         Math         /// An inline formula
+    "mathblock"    Mathblock    /// A formula displayed as a block
+    "var"          Var          /// A variable substitution
+    "ref"          Ref          /// A cross-reference to another node
+    "listof"       Listof       /// A list of all numbered nodes of some kind
+    "cite"         Cite         /// A citation of a bibliography entry
+    "bibliography" Bibliography /// A list of all cited bibliography entries
+    "fnote"        Fnote        /// A footnote defined where it is referenced
+    "fnoteref"     Fnoteref     /// A reference to a footnote defined elsewhere
+    "fnotedef"     Fnotedef     /// The definition of a footnote referenced elsewhere
+    "fnotes"       Fnotes       /// A list of all footnotes that were not shown yet
+    "defnode"      Defnode      /// A component definition
+    "slot"         Slot         /// The children passed to a component
+    "if"           If           /// Conditional content
+    "else"         Else         /// Content used when a condition does not hold
 }
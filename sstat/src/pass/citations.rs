@@ -0,0 +1,180 @@
+//! Citations and bibliographies
+
+use crate::bibliography::{parse_bibliography, Entry, Reference};
+use crate::parse::*;
+use crate::{did_you_mean, CitationStyle, Config, ParseError, ParseErrorType};
+
+impl Parser {
+    /// Resolve every `(cite [key ...])` node in a page against the
+    /// bibliography files named by `[bibliography path]` page attributes, and
+    /// fill every `(bibliography)` node with the entries that were cited
+    ///
+    /// The `[citations ...]` page attribute overrides the [`Config`].
+    pub(crate) fn resolve_citations(
+        &self,
+        page: &mut Page,
+        config: &Config,
+    ) -> Result<(), ParseError> {
+        let mut style = config.citation_style;
+        let mut entries = vec![];
+
+        for attribute in &page.attributes {
+            match attribute.attribute_name.name {
+                "citations" => style = self.parse_citation_style(attribute)?,
+                "bibliography" => entries.extend(self.load_bibliography(attribute)?),
+                _ => (),
+            }
+        }
+
+        let mut cited = vec![];
+        self.cite_nodes(&mut page.doc.nodes, &entries, style, &mut cited)?;
+
+        style.sort(&mut cited);
+        for reference in &mut cited {
+            reference.segments = style.format(&reference.entry);
+        }
+
+        fill_bibliographies(&mut page.doc.nodes, &cited);
+
+        Ok(())
+    }
+
+    /// Parse the value of a `[citations ...]` attribute
+    pub(crate) fn parse_citation_style(
+        &self,
+        attribute: &Attribute,
+    ) -> Result<CitationStyle, ParseError> {
+        let value = attribute.attribute_value.text.trim();
+
+        CitationStyle::from_name(value).ok_or_else(|| {
+            let mut error = self.make_error(
+                attribute.attribute_value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: "citations".to_owned(),
+                    value: value.to_owned(),
+                },
+            );
+            if let Some(suggestion) = did_you_mean(value, CitationStyle::NAMES.iter().copied()) {
+                error = error.add_context(format!("did you mean '{suggestion}'?"));
+            }
+
+            error
+        })
+    }
+
+    /// Read and parse the file named by a `[bibliography path]` attribute
+    fn load_bibliography(&self, attribute: &Attribute) -> Result<Vec<Entry>, ParseError> {
+        let value = attribute.attribute_value;
+        let (source, offset) = self.import_file(value)?;
+
+        parse_bibliography(value.text.trim(), source).map_err(|e| {
+            self.make_error(
+                Span::new(offset + e.start, offset + e.end),
+                ParseErrorType::InvalidBibliography { reason: e.reason },
+            )
+        })
+    }
+
+    /// Give every citation in a list of nodes its label, adding every entry
+    /// that is cited for the first time to `cited`
+    fn cite_nodes(
+        &self,
+        nodes: &mut [Node],
+        entries: &[Entry],
+        style: CitationStyle,
+        cited: &mut Vec<Reference>,
+    ) -> Result<(), ParseError> {
+        for node in nodes {
+            if let Node::Cite {
+                attributes,
+                label,
+                span,
+                ..
+            } = node
+            {
+                let Some(key) = attributes
+                    .iter()
+                    .rfind(|a| a.attribute_name.name == "key")
+                    .map(|a| a.attribute_value.text.trim())
+                else {
+                    return Err(self.make_error(
+                        *span,
+                        ParseErrorType::MissingAttribute {
+                            node: "cite".to_owned(),
+                            name: "key".to_owned(),
+                        },
+                    ));
+                };
+
+                let number = match cited.iter().position(|r| r.entry.key == key) {
+                    Some(idx) => idx + 1,
+                    None => {
+                        let entry = self.find_entry(entries, key, *span)?;
+                        cited.push(Reference {
+                            label: style.reference_label(entry, cited.len() + 1),
+                            entry: entry.clone(),
+                            segments: vec![],
+                        });
+
+                        cited.len()
+                    }
+                };
+
+                *label = Some(style.citation_label(&cited[number - 1].entry, number));
+
+                continue;
+            }
+
+            if let Some(children) = node.children_mut() {
+                self.cite_nodes(children, entries, style, cited)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the entry with a given key, reporting an error at the citation
+    /// `span` if there is none
+    fn find_entry<'e>(
+        &self,
+        entries: &'e [Entry],
+        key: &str,
+        span: Span,
+    ) -> Result<&'e Entry, ParseError> {
+        if let Some(entry) = entries.iter().find(|e| e.key == key) {
+            return Ok(entry);
+        }
+
+        let mut error = self.make_error(
+            span,
+            ParseErrorType::UnknownCitation {
+                key: key.to_owned(),
+            },
+        );
+
+        if entries.is_empty() {
+            error = error.add_context(
+                "bibliography files are added using a `[bibliography path]` page attribute"
+                    .to_owned(),
+            );
+        } else if let Some(suggestion) = did_you_mean(key, entries.iter().map(|e| e.key.as_str())) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        Err(error)
+    }
+}
+
+/// List all cited entries in every `(bibliography)` node
+fn fill_bibliographies(nodes: &mut [Node], cited: &[Reference]) {
+    for node in nodes {
+        match node {
+            Node::Bibliography { references, .. } => *references = cited.to_vec(),
+            _ => {
+                if let Some(children) = node.children_mut() {
+                    fill_bibliographies(children, cited);
+                }
+            }
+        }
+    }
+}
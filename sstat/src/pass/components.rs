@@ -0,0 +1,320 @@
+//! User-defined components
+
+use std::collections::{HashMap, HashSet};
+
+use crate::parse::*;
+use crate::{did_you_mean, ParseError, ParseErrorType};
+
+/// Attributes whose values are node IDs, and which are made unique for every
+/// use of a component
+///
+/// References are only made unique when they point at a node declared in the
+/// component itself, so that components can refer to the rest of the page.
+const ID_ATTRIBUTES: &[&str] = &["id", "ref_id"];
+
+/// A user-defined component
+struct Component<'s> {
+    name: Identifier<'s>,
+    span: Span,
+    params: Vec<&'s str>,
+    body: Vec<Node<'s>>,
+    /// The IDs of all nodes in the body
+    ids: HashSet<&'s str>,
+}
+
+/// All state needed while expanding components
+struct Expander<'s> {
+    components: HashMap<&'s str, Component<'s>>,
+    /// The number of times each component has been used so far
+    uses: HashMap<&'s str, usize>,
+}
+
+/// A single use of a component
+struct Instance<'s, 'a> {
+    component: &'s str,
+    /// How many times the component was used up to and including this use
+    index: usize,
+    arguments: &'a HashMap<&'s str, Text<'s>>,
+    children: &'a [Node<'s>],
+    ids: &'a HashSet<&'s str>,
+}
+
+impl Parser {
+    /// Replace every use of a user-defined component with the body of its
+    /// definition, and remove all definitions from the page
+    ///
+    /// Any `(var ...)` node in a component body naming one of its parameters
+    /// is replaced by the value passed for that parameter, and any `(slot)`
+    /// node by the children passed to the component
+    pub(crate) fn expand_components<'s>(&'s self, page: &mut Page<'s>) -> Result<(), ParseError> {
+        let mut definitions = vec![];
+        collect_definitions(&mut page.library, &mut definitions);
+        collect_definitions(&mut page.doc.nodes, &mut definitions);
+
+        let mut expander = Expander {
+            components: HashMap::new(),
+            uses: HashMap::new(),
+        };
+        for definition in definitions {
+            let component = self.make_component(definition)?;
+            expander.components.insert(component.name.name, component);
+        }
+
+        self.expand_nodes(&mut page.doc.nodes, &mut expander)
+    }
+
+    fn make_component<'s>(&self, definition: Node<'s>) -> Result<Component<'s>, ParseError> {
+        let Node::Defnode {
+            name,
+            attributes,
+            mut inner,
+            span,
+            ..
+        } = definition
+        else {
+            unreachable!()
+        };
+
+        let mut params = vec![];
+        for attribute in attributes {
+            if attribute.attribute_name.name != "params" {
+                return Err(self.make_error(
+                    attribute.attribute_name.span,
+                    ParseErrorType::UnexpectedAttribute {
+                        node: "defnode".to_owned(),
+                        name: attribute.attribute_name.name.to_owned(),
+                    },
+                ));
+            }
+
+            params.extend(attribute.attribute_value.text.split_whitespace());
+        }
+
+        let mut ids = HashSet::new();
+        collect_ids(&mut inner, &mut ids);
+
+        Ok(Component {
+            name,
+            span,
+            params,
+            body: inner,
+            ids,
+        })
+    }
+
+    fn expand_nodes<'s>(
+        &'s self,
+        nodes: &mut Vec<Node<'s>>,
+        expander: &mut Expander<'s>,
+    ) -> Result<(), ParseError> {
+        let mut expanded = Vec::with_capacity(nodes.len());
+
+        for mut node in std::mem::take(nodes) {
+            match node {
+                Node::Slot { span, .. } => {
+                    return Err(self.make_error(span, ParseErrorType::StraySlot));
+                }
+                Node::Component { .. } => {
+                    expanded.extend(self.expand_component(node, expander)?);
+                }
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.expand_nodes(children, expander)?;
+                    }
+
+                    expanded.push(node);
+                }
+            }
+        }
+
+        *nodes = expanded;
+
+        Ok(())
+    }
+
+    fn expand_component<'s>(
+        &'s self,
+        call: Node<'s>,
+        expander: &mut Expander<'s>,
+    ) -> Result<Vec<Node<'s>>, ParseError> {
+        let Node::Component {
+            name,
+            attributes,
+            mut inner,
+            span,
+            ..
+        } = call
+        else {
+            unreachable!()
+        };
+
+        // The children belong to the caller, so expand them before they end up
+        // inside the component
+        self.expand_nodes(&mut inner, expander)?;
+
+        let component = &expander.components[name.name];
+
+        let mut arguments = HashMap::new();
+        for attribute in attributes {
+            let param = attribute.attribute_name;
+
+            if !component.params.contains(&param.name) {
+                let mut error = self.make_error(
+                    param.span,
+                    ParseErrorType::UnknownParameter {
+                        name: param.name.to_owned(),
+                    },
+                );
+                if let Some(suggestion) = did_you_mean(param.name, component.params.iter().copied())
+                {
+                    error = error.add_context(format!("did you mean '{suggestion}'?"));
+                }
+
+                return Err(self.add_related(error, component.name.span, "component defined here"));
+            }
+
+            let value = attribute.attribute_value;
+            let text = value.text.trim();
+            let start = value.span.start + (value.text.len() - value.text.trim_start().len());
+
+            arguments.insert(
+                param.name,
+                Text {
+                    text,
+                    span: Span::new(start, start + text.len()),
+                },
+            );
+        }
+
+        if let Some(missing) = component
+            .params
+            .iter()
+            .find(|param| !arguments.contains_key(*param))
+        {
+            let error = self.make_error(
+                name.span,
+                ParseErrorType::MissingParameter {
+                    name: (*missing).to_owned(),
+                },
+            );
+
+            return Err(self.add_related(error, component.name.span, "component defined here"));
+        }
+
+        let uses = expander.uses.entry(name.name).or_insert(0);
+        *uses += 1;
+
+        let instance = Instance {
+            component: name.name,
+            index: *uses,
+            arguments: &arguments,
+            children: &inner,
+            ids: &component.ids,
+        };
+        // Every copy gets spans of its own, before it is filled in with nodes
+        // from the use
+        let mut body = component.body.clone();
+        move_spans(&mut body, self.record_expansion(component.span, span));
+        self.instantiate(&mut body, &instance);
+
+        // Expand any components used within the definition itself
+        self.expand_nodes(&mut body, expander)?;
+
+        Ok(body)
+    }
+
+    /// Fill in the parameters and slots of a copy of a component body
+    fn instantiate<'s>(&'s self, body: &mut Vec<Node<'s>>, instance: &Instance<'s, '_>) {
+        let mut instantiated = Vec::with_capacity(body.len());
+
+        for mut node in std::mem::take(body) {
+            match node {
+                Node::Var { name, .. } if instance.arguments.contains_key(name.name) => {
+                    let inner = instance.arguments[name.name];
+                    instantiated.push(Node::Text { inner });
+                }
+                Node::Slot { .. } => {
+                    instantiated.extend(instance.children.iter().cloned());
+                }
+                _ => {
+                    if let Some(attributes) = node.attributes_mut() {
+                        for attribute in attributes {
+                            let name = attribute.attribute_name.name;
+                            let value = attribute.attribute_value.text.trim();
+
+                            if ID_ATTRIBUTES.contains(&name) && instance.ids.contains(value) {
+                                let id =
+                                    format!("{}-{}-{}", instance.component, instance.index, value);
+                                attribute.attribute_value.text = self.alloc_str(&id);
+                            }
+                        }
+                    }
+
+                    if let Some(children) = node.children_mut() {
+                        self.instantiate(children, instance);
+                    }
+
+                    instantiated.push(node);
+                }
+            }
+        }
+
+        *body = instantiated;
+    }
+}
+
+/// Move all spans of a list of nodes and their children by `offset` bytes
+fn move_spans(nodes: &mut [Node], offset: usize) {
+    for node in nodes {
+        for span in node.spans_mut() {
+            *span = Span::new(span.start + offset, span.end + offset);
+        }
+        for attribute in node.attributes_mut().into_iter().flatten() {
+            for span in [
+                &mut attribute.lbracket.span,
+                &mut attribute.attribute_name.span,
+                &mut attribute.attribute_value.span,
+                &mut attribute.rbracket.span,
+                &mut attribute.span,
+            ] {
+                *span = Span::new(span.start + offset, span.end + offset);
+            }
+        }
+
+        if let Some(children) = node.children_mut() {
+            move_spans(children, offset);
+        }
+    }
+}
+
+/// Collect the IDs of a list of nodes and their children
+fn collect_ids<'s>(nodes: &mut [Node<'s>], ids: &mut HashSet<&'s str>) {
+    for node in nodes {
+        ids.extend(
+            node.attributes()
+                .iter()
+                .filter(|a| a.attribute_name.name == "id")
+                .map(|a| a.attribute_value.text.trim()),
+        );
+
+        if let Some(children) = node.children_mut() {
+            collect_ids(children, ids);
+        }
+    }
+}
+
+/// Remove all component definitions from a list of nodes and their children
+fn collect_definitions<'s>(nodes: &mut Vec<Node<'s>>, definitions: &mut Vec<Node<'s>>) {
+    for node in nodes.iter_mut() {
+        if let Some(children) = node.children_mut() {
+            collect_definitions(children, definitions);
+        }
+    }
+
+    let (found, rest) = std::mem::take(nodes)
+        .into_iter()
+        .partition(|n| matches!(n, Node::Defnode { .. }));
+
+    *nodes = rest;
+    definitions.extend(found);
+}
@@ -0,0 +1,89 @@
+//! Conditional content
+
+use crate::parse::*;
+use crate::{Config, ParseError, ParseErrorType};
+
+impl Parser {
+    /// Replace every `(if ...)` and `(else ...)` node with its children if it
+    /// applies to the given configuration, and remove it otherwise
+    ///
+    /// Every attribute of an `if` node is a condition, all of which must hold
+    /// for its children to be kept. `[name value]` holds if the flag `name` is
+    /// set to `value`, while `[name]` holds if the flag `name` is set at all.
+    pub(crate) fn evaluate_conditionals(
+        &self,
+        page: &mut Page,
+        config: &Config,
+    ) -> Result<(), ParseError> {
+        self.evaluate_nodes(&mut page.doc.nodes, config)
+    }
+
+    fn evaluate_nodes(&self, nodes: &mut Vec<Node>, config: &Config) -> Result<(), ParseError> {
+        let mut evaluated = Vec::with_capacity(nodes.len());
+
+        // Whether the previous node was an `if`, and if so whether its
+        // conditions held
+        let mut previous_if = None;
+
+        for mut node in std::mem::take(nodes) {
+            if let Some(children) = node.children_mut() {
+                self.evaluate_nodes(children, config)?;
+            }
+
+            match node {
+                Node::If {
+                    attributes, inner, ..
+                } => {
+                    let holds = attributes.iter().all(|a| condition_holds(a, config));
+                    if holds {
+                        evaluated.extend(inner);
+                    }
+
+                    previous_if = Some(holds);
+                }
+                Node::Else {
+                    attributes,
+                    inner,
+                    span,
+                    ..
+                } => {
+                    if let Some(attribute) = attributes.first() {
+                        return Err(self.make_error(
+                            attribute.attribute_name.span,
+                            ParseErrorType::UnexpectedAttribute {
+                                node: "else".to_owned(),
+                                name: attribute.attribute_name.name.to_owned(),
+                            },
+                        ));
+                    }
+
+                    match previous_if {
+                        Some(false) => evaluated.extend(inner),
+                        Some(true) => (),
+                        None => return Err(self.make_error(span, ParseErrorType::StrayElse)),
+                    }
+
+                    previous_if = None;
+                }
+                _ => {
+                    evaluated.push(node);
+                    previous_if = None;
+                }
+            }
+        }
+
+        *nodes = evaluated;
+
+        Ok(())
+    }
+}
+
+/// Check a single condition of an `if` node
+fn condition_holds(condition: &Attribute, config: &Config) -> bool {
+    let expected = condition.attribute_value.text.trim();
+
+    match config.flags.get(condition.attribute_name.name) {
+        Some(value) => expected.is_empty() || value == expected,
+        None => false,
+    }
+}
@@ -0,0 +1,49 @@
+//! Escape sequences in text
+
+use crate::parse::*;
+
+/// The characters that can be escaped using a backslash
+const ESCAPABLE: &[char] = &['(', ')', '\\'];
+
+impl Parser {
+    /// Replace every escape sequence in text with the character it escapes
+    ///
+    /// The content of `codeblock` and `html` nodes, and of table cells
+    /// imported from CSV files, is left untouched.
+    pub(crate) fn process_escapes<'s>(&'s self, page: &mut Page<'s>) {
+        self.unescape_nodes(&mut page.doc.nodes);
+    }
+
+    fn unescape_nodes<'s>(&'s self, nodes: &mut [Node<'s>]) {
+        for node in nodes {
+            match node {
+                Node::Text { inner } if inner.text.contains('\\') => {
+                    inner.text = self.alloc_str(&unescape(inner.text));
+                }
+                Node::Tcell { literal: true, .. } => (),
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.unescape_nodes(children);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && ESCAPABLE.contains(next) => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
@@ -0,0 +1,66 @@
+//! Boolean attributes
+
+use crate::parse::*;
+use crate::{did_you_mean, ParseError, ParseErrorType};
+
+/// The nodes that accept boolean attributes, along with the names of those
+/// attributes
+const FLAGS: &[(&str, &[&str])] = &[
+    ("table", &["header"]),
+    ("details", &["open"]),
+    ("important", &["open", "collapsible"]),
+    ("note", &["open", "collapsible"]),
+    ("tip", &["open", "collapsible"]),
+    ("caution", &["open", "collapsible"]),
+    ("warning", &["open", "collapsible"]),
+];
+
+impl Parser {
+    /// Check that every boolean attribute in a page has a valid value
+    pub(crate) fn check_flags(&self, page: &mut Page) -> Result<(), ParseError> {
+        self.check_node_flags(&mut page.doc.nodes)
+    }
+
+    fn check_node_flags(&self, nodes: &mut [Node]) -> Result<(), ParseError> {
+        for node in nodes {
+            let flags = FLAGS
+                .iter()
+                .find(|(n, _)| *n == node.name())
+                .map_or(&[] as &[&str], |(_, flags)| flags);
+
+            for attribute in node.attributes() {
+                if !flags.contains(&attribute.attribute_name.name) {
+                    continue;
+                }
+
+                self.parse_flag_attribute(attribute)?;
+            }
+
+            if let Some(children) = node.children_mut() {
+                self.check_node_flags(children)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse the value of a boolean attribute
+    pub(crate) fn parse_flag_attribute(&self, attribute: &Attribute) -> Result<bool, ParseError> {
+        let value = attribute.attribute_value.text.trim();
+
+        parse_flag(value).ok_or_else(|| {
+            let mut error = self.make_error(
+                attribute.attribute_value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: attribute.attribute_name.name.to_owned(),
+                    value: value.to_owned(),
+                },
+            );
+            if let Some(suggestion) = did_you_mean(value, ["true", "false"].into_iter()) {
+                error = error.add_context(format!("did you mean '{suggestion}'?"));
+            }
+
+            error
+        })
+    }
+}
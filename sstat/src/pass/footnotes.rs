@@ -0,0 +1,218 @@
+//! Footnotes and the lists showing them
+
+use crate::parse::*;
+use crate::{did_you_mean, ParseError, ParseErrorType};
+
+/// A footnote, as listed by a `fnotes` node
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Footnote<'s> {
+    pub(crate) number: usize,
+    pub(crate) inner: Vec<Node<'s>>,
+}
+
+/// A footnote defined by a `fnotedef` node
+struct Definition<'s> {
+    id: &'s str,
+    inner: Vec<Node<'s>>,
+    span: Span,
+    /// The number of the footnote, once it has been referenced
+    number: Option<usize>,
+}
+
+/// The footnotes of a page while they are being numbered
+struct Footnotes<'s> {
+    definitions: Vec<Definition<'s>>,
+    count: usize,
+    /// The footnotes referenced since the last `fnotes` node
+    pending: Vec<Footnote<'s>>,
+}
+
+impl Parser {
+    /// Number every footnote in the order it is first referenced, and list
+    /// each of them in the first `fnotes` node after its first reference
+    ///
+    /// `fnotedef` nodes are removed, as their content is shown where they are
+    /// listed instead. Footnotes that are not listed by any `fnotes` node are
+    /// listed at the end of the page.
+    pub(crate) fn resolve_footnotes<'s>(&'s self, page: &mut Page<'s>) -> Result<(), ParseError> {
+        let mut definitions = vec![];
+        self.take_definitions(&mut page.doc.nodes, &mut definitions)?;
+
+        let mut footnotes = Footnotes {
+            definitions,
+            count: 0,
+            pending: vec![],
+        };
+        self.number_footnotes(&mut page.doc.nodes, &mut footnotes)?;
+
+        if !footnotes.pending.is_empty() {
+            // The implicit list has no delimiters or name in the source, so
+            // these point at the end of the page instead
+            let end = Span::new(page.doc.rparen.span.start, page.doc.rparen.span.start);
+
+            page.doc.nodes.push(Node::Fnotes {
+                lparen: LParen { span: end },
+                fnotes: Fnotes { span: end },
+                attributes: vec![],
+                footnotes: take_pending(&mut footnotes),
+                rparen: RParen { span: end },
+                span: end,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Remove every `fnotedef` node from a list of nodes, adding it to
+    /// `definitions`
+    fn take_definitions<'s>(
+        &'s self,
+        nodes: &mut Vec<Node<'s>>,
+        definitions: &mut Vec<Definition<'s>>,
+    ) -> Result<(), ParseError> {
+        for node in std::mem::take(nodes) {
+            let Node::Fnotedef { inner, span, .. } = &node else {
+                nodes.push(node);
+                continue;
+            };
+
+            let Some(id) = node.attribute("id") else {
+                return Err(self.make_error(
+                    *span,
+                    ParseErrorType::MissingAttribute {
+                        node: "fnotedef".to_owned(),
+                        name: "id".to_owned(),
+                    },
+                ));
+            };
+
+            if let Some(previous) = definitions.iter().find(|d| d.id == id) {
+                let error = self.make_error(
+                    *span,
+                    ParseErrorType::DuplicateFootnote { id: id.to_owned() },
+                );
+                return Err(self.add_related(error, previous.span, "previously defined here"));
+            }
+
+            definitions.push(Definition {
+                id,
+                inner: inner.clone(),
+                span: *span,
+                number: None,
+            });
+        }
+
+        for node in nodes {
+            if let Some(children) = node.children_mut() {
+                self.take_definitions(children, definitions)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number the footnotes referenced in a list of nodes, and fill every
+    /// `fnotes` node with the footnotes referenced since the last one,
+    /// removing those with none left to show
+    fn number_footnotes<'s>(
+        &'s self,
+        nodes: &mut Vec<Node<'s>>,
+        footnotes: &mut Footnotes<'s>,
+    ) -> Result<(), ParseError> {
+        for node in nodes.iter_mut() {
+            match node {
+                Node::Fnote { inner, number, .. } => {
+                    footnotes.count += 1;
+                    let count = footnotes.count;
+                    *number = Some(count);
+
+                    self.number_footnotes(inner, footnotes)?;
+                    footnotes.pending.push(Footnote {
+                        number: count,
+                        inner: inner.clone(),
+                    });
+                }
+                Node::Fnoteref { .. } => {
+                    let Some(id) = node.attribute("ref_id") else {
+                        return Err(self.make_error(
+                            node.span(),
+                            ParseErrorType::MissingAttribute {
+                                node: "fnoteref".to_owned(),
+                                name: "ref_id".to_owned(),
+                            },
+                        ));
+                    };
+                    let Node::Fnoteref {
+                        number, note, span, ..
+                    } = node
+                    else {
+                        unreachable!()
+                    };
+
+                    let index = self.find_definition(&footnotes.definitions, id, *span)?;
+                    if let Some(count) = footnotes.definitions[index].number {
+                        *number = Some(count);
+                        continue;
+                    }
+
+                    footnotes.count += 1;
+                    let count = footnotes.count;
+                    footnotes.definitions[index].number = Some(count);
+                    *number = Some(count);
+
+                    let mut inner = footnotes.definitions[index].inner.clone();
+                    self.number_footnotes(&mut inner, footnotes)?;
+                    footnotes.pending.push(Footnote {
+                        number: count,
+                        inner: inner.clone(),
+                    });
+                    *note = Some(inner);
+                }
+                Node::Fnotes {
+                    footnotes: listed, ..
+                } => *listed = take_pending(footnotes),
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.number_footnotes(children, footnotes)?;
+                    }
+                }
+            }
+        }
+
+        nodes.retain(|n| !matches!(n, Node::Fnotes { footnotes, .. } if footnotes.is_empty()));
+
+        Ok(())
+    }
+
+    /// Find the index of the footnote with a given id, reporting an error at
+    /// the reference `span` if there is none
+    fn find_definition(
+        &self,
+        definitions: &[Definition],
+        id: &str,
+        span: Span,
+    ) -> Result<usize, ParseError> {
+        if let Some(index) = definitions.iter().position(|d| d.id == id) {
+            return Ok(index);
+        }
+
+        let mut error =
+            self.make_error(span, ParseErrorType::UnknownFootnote { id: id.to_owned() });
+        if let Some(suggestion) = did_you_mean(id, definitions.iter().map(|d| d.id)) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        Err(error.add_context("footnotes are defined using `(fnotedef [id ...] ...)`".to_owned()))
+    }
+}
+
+/// Take the footnotes referenced since the last `fnotes` node, in order
+///
+/// Footnotes referenced inside other footnotes are numbered after them, but
+/// are pending before them.
+fn take_pending<'s>(footnotes: &mut Footnotes<'s>) -> Vec<Footnote<'s>> {
+    let mut pending = std::mem::take(&mut footnotes.pending);
+    pending.sort_by_key(|f| f.number);
+
+    pending
+}
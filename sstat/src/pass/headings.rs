@@ -0,0 +1,69 @@
+//! Ids of titles and headers
+
+use super::metadata::plain_text;
+use super::Kind;
+use crate::parse::*;
+use crate::{Config, Slugger};
+
+impl Parser {
+    /// Give every `title` and `header` without an `[id ...]` a slug of its
+    /// text as its id
+    ///
+    /// Every `[id ...]` on the page, and the generated id of every numbered
+    /// node, is reserved first, so that slugs never clash with them.
+    pub(crate) fn assign_heading_ids(&self, page: &mut Page, config: &Config) {
+        let mut slugger = Slugger::new();
+
+        for attribute in &page.doc.attributes {
+            if attribute.attribute_name.name == "id" {
+                slugger.reserve(attribute.attribute_value.text.trim());
+            }
+        }
+        reserve_ids(
+            &mut page.doc.nodes,
+            config.classes.id_prefix(),
+            &mut slugger,
+        );
+
+        slug_titles(&mut page.doc.nodes, &mut slugger);
+    }
+}
+
+/// Reserve the `[id ...]` or generated id of every node in a list of nodes
+fn reserve_ids(nodes: &mut [Node], prefix: &str, slugger: &mut Slugger) {
+    for node in nodes {
+        if let Some(id) = node.attribute("id") {
+            slugger.reserve(id);
+        } else if let Some((kind, number)) = Kind::of(node) {
+            slugger.reserve(&kind.default_id(prefix, number));
+        }
+
+        if let Some(children) = node.children_mut() {
+            reserve_ids(children, prefix, slugger);
+        }
+    }
+}
+
+/// Give every `title` and `header` without an `[id ...]` in a list of nodes a
+/// slug, in document order
+fn slug_titles(nodes: &mut [Node], slugger: &mut Slugger) {
+    for node in nodes {
+        let has_id = node.attribute("id").is_some();
+
+        match node {
+            Node::Title { inner, slug, .. } | Node::Header { inner, slug, .. } => {
+                if !has_id {
+                    let mut text = String::new();
+                    plain_text(inner, &mut text);
+
+                    *slug = Some(slugger.slug(&text));
+                }
+            }
+            node => {
+                if let Some(children) = node.children_mut() {
+                    slug_titles(children, slugger);
+                }
+            }
+        }
+    }
+}
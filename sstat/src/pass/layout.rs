@@ -0,0 +1,322 @@
+//! Layout templates
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::parse::*;
+use crate::{did_you_mean, Config, ParseError, ParseErrorType};
+
+/// The name of the layout used by pages without a `[layout ...]` attribute,
+/// which is built in unless the layout directory overrides it
+const DEFAULT_LAYOUT: &str = "default";
+
+/// The layout a page is rendered into
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Layout {
+    /// The templates of the layout, from the one named by the page to the
+    /// one it extends last, each inserted into the body of the next
+    pub(crate) templates: Vec<Template>,
+    /// Whether the last template is inserted into the built-in default
+    /// layout
+    pub(crate) builtin: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            templates: vec![],
+            builtin: true,
+        }
+    }
+}
+
+/// A single layout file
+pub(crate) type Template = Vec<Piece>;
+
+/// A part of a layout file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Piece {
+    /// Text copied into the output as is
+    Text(String),
+    /// A placeholder replaced by part of the page
+    Placeholder(Placeholder),
+}
+
+/// A `{{ name }}` placeholder in a layout file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Placeholder {
+    /// The rendered `<main>` element, or the layout extending this one
+    Body,
+    /// The elements of the generated `<head>`
+    Head,
+    /// A table of contents listing every `title`
+    Toc,
+    /// The site navigation of the [`Config`]
+    Nav,
+    /// The title of the page
+    Title,
+    /// The author of the page
+    Author,
+    /// The description of the page
+    Description,
+    /// The language of the page
+    Lang,
+}
+
+impl Placeholder {
+    /// The names of all placeholders, as used in layouts
+    const NAMES: &'static [&'static str] = &[
+        "body",
+        "head",
+        "toc",
+        "nav",
+        "title",
+        "author",
+        "description",
+        "lang",
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "body" => Some(Self::Body),
+            "head" => Some(Self::Head),
+            "toc" => Some(Self::Toc),
+            "nav" => Some(Self::Nav),
+            "title" => Some(Self::Title),
+            "author" => Some(Self::Author),
+            "description" => Some(Self::Description),
+            "lang" => Some(Self::Lang),
+            _ => None,
+        }
+    }
+}
+
+impl Parser {
+    /// Load the layout named by the `[layout ...]` page attribute, along with
+    /// every layout it extends
+    ///
+    /// Layouts are read from `{name}.html` in the layout directory of the
+    /// [`Config`]. Without a `[layout ...]` attribute the default layout is
+    /// used, which falls back to the built-in one.
+    pub(crate) fn load_layout<'s>(
+        &'s self,
+        page: &mut Page<'s>,
+        config: &Config,
+    ) -> Result<(), ParseError> {
+        let dir = config
+            .layouts
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("layouts"));
+
+        let named = page
+            .attributes
+            .iter()
+            .rev()
+            .find(|a| a.attribute_name.name == "layout")
+            .map(|a| (a.attribute_value.text.trim(), a.attribute_value.span));
+
+        let mut layout = Layout {
+            templates: vec![],
+            builtin: false,
+        };
+        let mut seen = vec![];
+        let (mut name, mut span) = match named {
+            Some((name, span)) => (name, Some(span)),
+            None => (DEFAULT_LAYOUT, None),
+        };
+
+        loop {
+            // Untrusted pages may only name layouts in the layout directory
+            if self.is_safe() && !is_file_name(name) {
+                let span = span.expect("the default layout is always in the layout directory");
+                return Err(self.make_error(
+                    span,
+                    ParseErrorType::UnsafePath {
+                        path: name.to_owned(),
+                    },
+                ));
+            }
+
+            let path = dir.join(format!("{name}.html"));
+
+            if !path.is_file() {
+                if name == DEFAULT_LAYOUT {
+                    layout.builtin = true;
+                    break;
+                }
+
+                let span = span.expect("only named layouts can be missing");
+                return Err(self.unknown_layout(&dir, name, span));
+            }
+
+            if seen.contains(&name) {
+                let span = span.expect("only extended layouts can be seen twice");
+                return Err(self.make_error(
+                    span,
+                    ParseErrorType::RecursiveLayout {
+                        name: name.to_owned(),
+                    },
+                ));
+            }
+            seen.push(name);
+
+            let source = std::fs::read_to_string(&path).map_err(|e| {
+                self.make_error(
+                    span.unwrap_or(page.span),
+                    ParseErrorType::ImportFailed {
+                        path: path.display().to_string(),
+                        reason: e.to_string(),
+                    },
+                )
+            })?;
+            let (source, offset) = self.register_file(&path, source);
+
+            let (template, extends) = self.parse_template(source, offset)?;
+            layout.templates.push(template);
+
+            match extends {
+                Some(parent) => (name, span) = (parent.name, Some(parent.span)),
+                None => break,
+            }
+        }
+
+        page.layout = layout;
+
+        Ok(())
+    }
+
+    /// The error for a layout that does not exist, suggesting a similarly
+    /// named one
+    fn unknown_layout(&self, dir: &Path, name: &str, span: Span) -> ParseError {
+        let mut names: Vec<_> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let stem = path.file_stem()?.to_str()?;
+
+                (path.extension()? == "html").then(|| stem.to_owned())
+            })
+            .collect();
+        names.push(DEFAULT_LAYOUT.to_owned());
+
+        let mut error = self
+            .make_error(
+                span,
+                ParseErrorType::UnknownLayout {
+                    name: name.to_owned(),
+                },
+            )
+            .add_context(format!(
+                "layouts are read from '{}'",
+                dir.join(format!("{name}.html")).display()
+            ));
+        if let Some(suggestion) = did_you_mean(name, names.iter().map(String::as_str)) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        error
+    }
+
+    /// Parse a layout file starting at `offset`, along with the name of the
+    /// layout it extends, if any
+    ///
+    /// ```ebnf
+    /// template = [ "{{" "extends" NAME "}}" ] { TEXT | "{{" NAME "}}" };
+    /// ```
+    fn parse_template<'s>(
+        &self,
+        source: &'s str,
+        offset: usize,
+    ) -> Result<(Template, Option<Identifier<'s>>), ParseError> {
+        let mut template = vec![];
+        let mut extends = None;
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            let (text, after) = rest.split_at(start);
+            let after = &after[2..];
+            let inner_start = offset + source.len() - after.len();
+
+            let Some(end) = after.find("}}") else {
+                return Err(self.make_error(
+                    Span::new(inner_start - 2, inner_start),
+                    ParseErrorType::UnexpectedEof {
+                        expected: "}}".to_owned(),
+                    },
+                ));
+            };
+            let inner = &after[..end];
+            rest = &after[end + 2..];
+
+            // The span of the trimmed content of the placeholder
+            let leading = inner.len() - inner.trim_start().len();
+            let name = inner.trim();
+            let span = Span::new(inner_start + leading, inner_start + leading + name.len());
+
+            if !text.is_empty() {
+                template.push(Piece::Text(text.to_owned()));
+            }
+
+            if let Some(("extends", parent)) = name.split_once(char::is_whitespace) {
+                let is_first = template
+                    .iter()
+                    .all(|piece| matches!(piece, Piece::Text(text) if text.trim().is_empty()));
+                if !is_first || extends.is_some() {
+                    return Err(self.make_error(span, ParseErrorType::MisplacedExtends));
+                }
+
+                // Only whitespace can come before the placeholder, which is dropped
+                template.clear();
+
+                let parent = parent.trim_start();
+                extends = Some(Identifier {
+                    name: parent,
+                    span: Span::new(span.end - parent.len(), span.end),
+                });
+                continue;
+            }
+
+            let Some(placeholder) = Placeholder::from_name(name) else {
+                let mut error = self.make_error(
+                    span,
+                    ParseErrorType::UnknownPlaceholder {
+                        name: name.to_owned(),
+                    },
+                );
+                if let Some(suggestion) = did_you_mean(name, Placeholder::NAMES.iter().copied()) {
+                    error = error.add_context(format!("did you mean '{suggestion}'?"));
+                }
+
+                return Err(error);
+            };
+            template.push(Piece::Placeholder(placeholder));
+        }
+
+        if !rest.is_empty() {
+            template.push(Piece::Text(rest.to_owned()));
+        }
+
+        // A layout extending another one is inserted into its body, where
+        // surrounding blank lines would only get in the way
+        if extends.is_some() {
+            if let Some(Piece::Text(text)) = template.first_mut() {
+                *text = text.trim_start().to_owned();
+            }
+            if let Some(Piece::Text(text)) = template.last_mut() {
+                *text = text.trim_end().to_owned();
+            }
+        }
+
+        Ok((template, extends))
+    }
+}
+
+/// Whether a layout name is the name of a file, rather than a path that could
+/// lead out of the layout directory
+fn is_file_name(name: &str) -> bool {
+    matches!(
+        Path::new(name).components().collect::<Vec<_>>()[..],
+        [Component::Normal(_)]
+    )
+}
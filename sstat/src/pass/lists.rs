@@ -0,0 +1,76 @@
+//! Lists and description lists
+
+use crate::parse::*;
+use crate::{ParseError, ParseErrorType};
+
+impl Parser {
+    /// Check that `list` and `ordlist` nodes only contain `li` nodes, that
+    /// `dlist` nodes only contain `dterm` and `ddesc` nodes, and that those
+    /// items are not used anywhere else
+    pub(crate) fn check_lists(&self, page: &mut Page) -> Result<(), ParseError> {
+        self.check_node_lists(&mut page.doc.nodes, "doc")
+    }
+
+    fn check_node_lists(&self, nodes: &mut [Node], parent: &str) -> Result<(), ParseError> {
+        let items = list_items(parent);
+
+        for node in nodes {
+            let name = node.name();
+
+            match node {
+                Node::Text { inner } if items.is_some() && !inner.text.trim().is_empty() => {
+                    return Err(self.unexpected_item(inner.span, parent, "text"));
+                }
+                Node::Text { .. } => (),
+                _ if items.is_some_and(|items| !items.contains(&name)) => {
+                    return Err(self.unexpected_item(node.span(), parent, name));
+                }
+                Node::Li { span, .. } | Node::Dterm { span, .. } | Node::Ddesc { span, .. }
+                    if items.is_none() =>
+                {
+                    return Err(self.unexpected_item(*span, parent, name));
+                }
+                _ => (),
+            }
+
+            if let Some(children) = node.children_mut() {
+                self.check_node_lists(children, name)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unexpected_item(&self, span: Span, parent: &str, child: &str) -> ParseError {
+        let error = self.make_error(
+            span,
+            ParseErrorType::UnexpectedChild {
+                parent: parent.to_owned(),
+                child: child.to_owned(),
+            },
+        );
+
+        match (list_items(parent), child) {
+            (Some(["li"]), _) => {
+                error.add_context(format!("'{parent}' can only contain 'li' nodes"))
+            }
+            (Some(_), _) => error.add_context(format!(
+                "'{parent}' can only contain 'dterm' and 'ddesc' nodes"
+            )),
+            (None, "li") => {
+                error.add_context("'li' can only be inside 'list' or 'ordlist'".to_owned())
+            }
+            (None, _) => error.add_context(format!("'{child}' can only be inside 'dlist'")),
+        }
+    }
+}
+
+/// The names of the items a list can contain, or `None` if a node is not a
+/// list
+fn list_items(name: &str) -> Option<&'static [&'static str]> {
+    match name {
+        "list" | "ordlist" => Some(&["li"]),
+        "dlist" => Some(&["dterm", "ddesc"]),
+        _ => None,
+    }
+}
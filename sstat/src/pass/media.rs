@@ -0,0 +1,208 @@
+//! Video and audio clips
+
+use crate::parse::*;
+use crate::{ParseError, ParseErrorType};
+
+/// The MIME types of video files, by extension
+const VIDEO_TYPES: &[(&str, &str)] = &[
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("ogg", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("mkv", "video/x-matroska"),
+];
+
+/// The MIME types of audio files, by extension
+const AUDIO_TYPES: &[(&str, &str)] = &[
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("wav", "audio/wav"),
+    ("flac", "audio/flac"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("webm", "audio/webm"),
+];
+
+impl Parser {
+    /// Check that every `video` and `audio` node has at least one source, and
+    /// that every local file they reference exists, then infer the MIME type
+    /// of every source without a `[type ...]` from its extension
+    pub(crate) fn check_media(&self, page: &mut Page) -> Result<(), ParseError> {
+        self.check_node_media(&mut page.doc.nodes, "doc")
+    }
+
+    fn check_node_media(&self, nodes: &mut [Node], parent: &str) -> Result<(), ParseError> {
+        for node in nodes {
+            let types = match node {
+                Node::Video { .. } => VIDEO_TYPES,
+                Node::Audio { .. } => AUDIO_TYPES,
+                Node::Source { span, .. } => {
+                    return Err(self.unexpected_child(*span, parent, "source"));
+                }
+                _ => {
+                    let name = node.name();
+                    if let Some(children) = node.children_mut() {
+                        self.check_node_media(children, name)?;
+                    }
+
+                    continue;
+                }
+            };
+
+            let name = node.name();
+            for attribute in node.attributes() {
+                if let "src" | "poster" = attribute.attribute_name.name {
+                    self.check_file(attribute.attribute_value)?;
+                }
+            }
+
+            let src = node.attribute("src");
+            let has_type = node_type(node.attributes()).is_some();
+            let (Node::Video {
+                inner, mime, span, ..
+            }
+            | Node::Audio {
+                inner, mime, span, ..
+            }) = node
+            else {
+                unreachable!()
+            };
+
+            if !has_type {
+                *mime = src.and_then(|s| media_type(s, types));
+            }
+
+            let mut has_source = src.is_some();
+            for child in inner {
+                let Node::Source {
+                    attributes,
+                    mime,
+                    span,
+                    ..
+                } = child
+                else {
+                    match child {
+                        Node::Text { inner } if inner.text.trim().is_empty() => continue,
+                        Node::Text { inner } => {
+                            return Err(self.unexpected_child(inner.span, name, "text"));
+                        }
+                        _ => {
+                            return Err(self.unexpected_child(child.span(), name, child.name()));
+                        }
+                    }
+                };
+
+                let Some(src) = attributes.iter().rfind(|a| a.attribute_name.name == "src") else {
+                    return Err(self.make_error(
+                        *span,
+                        ParseErrorType::MissingAttribute {
+                            node: "source".to_owned(),
+                            name: "src".to_owned(),
+                        },
+                    ));
+                };
+                self.check_file(src.attribute_value)?;
+
+                if node_type(attributes).is_none() {
+                    *mime = media_type(src.attribute_value.text.trim(), types);
+                }
+                has_source = true;
+            }
+
+            if !has_source {
+                return Err(self
+                    .make_error(
+                        *span,
+                        ParseErrorType::MissingAttribute {
+                            node: name.to_owned(),
+                            name: "src".to_owned(),
+                        },
+                    )
+                    .add_context(format!(
+                        "a '{name}' needs either a `[src ...]` attribute or `(source [src ...])` \
+                         children"
+                    )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the file named by an attribute value exists, if it is a
+    /// local file rather than a URL
+    ///
+    /// Paths starting with `/` are relative to the root the site is served
+    /// from, which pages do not know, so they are not checked either.
+    fn check_file(&self, value: Text) -> Result<(), ParseError> {
+        let path = value.text.trim();
+        if !is_local(path) || path.starts_with('/') {
+            return Ok(());
+        }
+
+        // Media fragments and query strings are not part of the file name
+        let file = path.split(['#', '?']).next().unwrap_or_default();
+        if self.resolve_path(file, value.span)?.exists() {
+            return Ok(());
+        }
+
+        Err(self.make_error(
+            value.span,
+            ParseErrorType::MissingFile {
+                path: file.to_owned(),
+            },
+        ))
+    }
+
+    fn unexpected_child(&self, span: Span, parent: &str, child: &str) -> ParseError {
+        let error = self.make_error(
+            span,
+            ParseErrorType::UnexpectedChild {
+                parent: parent.to_owned(),
+                child: child.to_owned(),
+            },
+        );
+
+        match child {
+            "source" => {
+                error.add_context("'source' can only be inside 'video' or 'audio'".to_owned())
+            }
+            _ => error.add_context(format!("'{parent}' can only contain 'source' nodes")),
+        }
+    }
+}
+
+/// The explicitly given `[type ...]` of a media source, if any
+fn node_type<'s>(attributes: &[Attribute<'s>]) -> Option<&'s str> {
+    attributes
+        .iter()
+        .rfind(|a| a.attribute_name.name == "type")
+        .map(|a| a.attribute_value.text.trim())
+}
+
+/// The MIME type of a media file, inferred from its extension
+fn media_type(path: &str, types: &[(&str, &'static str)]) -> Option<&'static str> {
+    let file = path.split(['#', '?']).next().unwrap_or_default();
+    let (_, extension) = file.rsplit_once('.')?;
+
+    types
+        .iter()
+        .find(|(e, _)| e.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+}
+
+/// Whether a path refers to a local file, rather than to a URL with a scheme
+/// like `https:` or a protocol-relative URL like `//example.com/clip.mp4`
+pub(super) fn is_local(path: &str) -> bool {
+    let has_scheme = path.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+
+    !has_scheme && !path.starts_with("//")
+}
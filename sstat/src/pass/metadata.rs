@@ -0,0 +1,176 @@
+//! Page metadata
+
+use super::media::is_local;
+use super::semantics::{is_datetime, is_time};
+use crate::parse::*;
+use crate::{Config, ParseError, ParseErrorType};
+
+/// Information about a page as a whole, used in the `<head>` of its document
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Metadata {
+    /// The title of the page, from a `[title ...]` page attribute or the
+    /// first `title` node
+    pub(crate) title: Option<String>,
+    /// The author of the page
+    pub(crate) author: Option<String>,
+    /// A short summary of the page
+    pub(crate) description: Option<String>,
+    /// When the page was published, as a date optionally followed by a time
+    pub(crate) date: Option<String>,
+    /// The URL of an image shown when the page is shared, absolute if the
+    /// [`Config`] has a base URL
+    pub(crate) cover: Option<String>,
+    /// The language the page is written in
+    pub(crate) language: Option<String>,
+    /// The stylesheets linked from the page, those of the [`Config`] first
+    pub(crate) stylesheets: Vec<String>,
+    /// Every `title` node of the page, in document order
+    pub(crate) toc: Vec<TocEntry>,
+    /// Whether titles end in a link to themselves
+    pub(crate) heading_anchors: bool,
+}
+
+/// A `title` node, as listed by a table of contents
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TocEntry {
+    /// The number of sections the `title` is in
+    pub(crate) depth: usize,
+    /// The text of the `title`
+    pub(crate) title: String,
+    /// The HTML id of the `title`, given explicitly or generated, if it has
+    /// one
+    pub(crate) id: Option<String>,
+}
+
+impl Parser {
+    /// Collect the metadata of a page from its page attributes, falling back
+    /// to the [`Config`]
+    ///
+    /// Without a `[title ...]` page attribute, the text of the first `title`
+    /// node is used as the title of the page. Every `title` node is also
+    /// listed in a table of contents.
+    pub(crate) fn collect_metadata(
+        &self,
+        page: &mut Page,
+        config: &Config,
+    ) -> Result<(), ParseError> {
+        let mut metadata = Metadata {
+            language: config.language.clone(),
+            stylesheets: config.stylesheets.clone(),
+            heading_anchors: config.heading_anchors,
+            ..Metadata::default()
+        };
+
+        for attribute in &page.attributes {
+            let value = attribute.attribute_value.text.trim().to_owned();
+
+            match attribute.attribute_name.name {
+                "title" => metadata.title = Some(value),
+                "author" => metadata.author = Some(value),
+                "description" => metadata.description = Some(value),
+                "lang" => metadata.language = Some(value),
+                "stylesheet" => metadata.stylesheets.push(value),
+                "heading_anchors" => {
+                    metadata.heading_anchors = self.parse_flag_attribute(attribute)?;
+                }
+                "date" => {
+                    // A time alone does not say when the page was published
+                    if !is_datetime(&value) || is_time(&value) {
+                        return Err(self
+                            .make_error(
+                                attribute.attribute_value.span,
+                                ParseErrorType::InvalidAttributeValue {
+                                    name: "date".to_owned(),
+                                    value,
+                                },
+                            )
+                            .add_context(
+                                "expected a date like '2026-10-17', optionally followed by a \
+                                 time, like '2026-10-17T14:30Z'"
+                                    .to_owned(),
+                            ));
+                    }
+
+                    metadata.date = Some(value);
+                }
+                "cover" => {
+                    let url = absolute_url(&value, config.base_url.as_deref());
+                    if is_local(&url) {
+                        self.warn(
+                            attribute.attribute_value.span,
+                            ParseErrorType::RelativeUrl { url: url.clone() },
+                        );
+                    }
+
+                    metadata.cover = Some(url);
+                }
+                _ => (),
+            }
+        }
+
+        collect_toc(&mut page.doc.nodes, 0, &mut metadata.toc);
+        if metadata.title.is_none() {
+            metadata.title = metadata.toc.first().map(|entry| entry.title.clone());
+        }
+
+        page.metadata = metadata;
+
+        Ok(())
+    }
+}
+
+/// Resolve a URL relative to the root of the site against its base URL, eg.
+/// `/images/cover.webp` against `https://example.com/blog/`
+fn absolute_url(url: &str, base_url: Option<&str>) -> String {
+    match base_url {
+        Some(base_url) if is_local(url) => format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        ),
+        _ => url.to_owned(),
+    }
+}
+
+/// Add every `title` node in a list of nodes to a table of contents
+fn collect_toc(nodes: &mut [Node], depth: usize, toc: &mut Vec<TocEntry>) {
+    for node in nodes {
+        let id = node.attribute("id").map(str::to_owned);
+
+        match node {
+            Node::Title { inner, slug, .. } => {
+                let mut title = String::new();
+                plain_text(inner, &mut title);
+                let id = id.or_else(|| slug.clone());
+
+                toc.push(TocEntry { depth, title, id });
+            }
+            Node::Sec { inner, .. } => collect_toc(inner, depth + 1, toc),
+            node => {
+                if let Some(children) = node.children_mut() {
+                    collect_toc(children, depth, toc);
+                }
+            }
+        }
+    }
+}
+
+/// The text of a list of nodes without any markup
+pub(super) fn plain_text(nodes: &mut [Node], output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text { inner } => output.push_str(inner.text),
+            Node::Ref {
+                label: Some(label), ..
+            }
+            | Node::Cite {
+                label: Some(label), ..
+            } => output.push_str(label),
+            node => {
+                if let Some(children) = node.children_mut() {
+                    plain_text(children, output);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Semantic passes run over a parsed page
+
+mod citations;
+mod components;
+mod conditionals;
+mod escapes;
+mod flags;
+mod footnotes;
+mod headings;
+mod layout;
+mod lists;
+mod media;
+mod metadata;
+mod numbering;
+mod paragraphs;
+mod safety;
+mod semantics;
+mod tables;
+mod typography;
+mod variables;
+mod whitespace;
+
+pub(crate) use footnotes::Footnote;
+pub(crate) use layout::{Layout, Piece, Placeholder, Template};
+pub(crate) use metadata::{Metadata, TocEntry};
+pub(crate) use numbering::{Kind, ListofEntry};
+pub(crate) use tables::Align;
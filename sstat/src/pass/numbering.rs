@@ -0,0 +1,273 @@
+//! Numbered figures, tables, and listings, and cross-references to them
+
+use crate::parse::*;
+use crate::{did_you_mean, Config, ParseError, ParseErrorType};
+
+/// The kinds of nodes that are numbered, each counted separately
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    /// An `image`
+    Figure,
+    /// A `table` with a caption
+    Table,
+    /// A `codeblock` with a caption
+    Listing,
+}
+
+impl Kind {
+    /// The names of all kinds, as used in attributes
+    const NAMES: &'static [&'static str] = &["figure", "table", "listing"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "figure" => Some(Self::Figure),
+            "table" => Some(Self::Table),
+            "listing" => Some(Self::Listing),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Figure => "figure",
+            Self::Table => "table",
+            Self::Listing => "listing",
+        }
+    }
+
+    /// The label of a node of this kind, eg. `Figure 3`
+    pub(crate) fn label(self, number: usize) -> String {
+        let word = match self {
+            Self::Figure => "Figure",
+            Self::Table => "Table",
+            Self::Listing => "Listing",
+        };
+
+        format!("{word} {number}")
+    }
+
+    /// The kind and number of a node that was numbered
+    pub(crate) fn of(node: &Node) -> Option<(Self, usize)> {
+        match node {
+            Node::Image {
+                number: Some(number),
+                ..
+            } => Some((Self::Figure, *number)),
+            Node::Table {
+                number: Some(number),
+                ..
+            } => Some((Self::Table, *number)),
+            Node::Codeblock {
+                number: Some(number),
+                ..
+            } => Some((Self::Listing, *number)),
+            _ => None,
+        }
+    }
+
+    /// The HTML id of a numbered node that was not given an `[id ...]`,
+    /// starting with the prefix of generated ids
+    pub(crate) fn default_id(self, prefix: &str, number: usize) -> String {
+        format!("{prefix}{}-{number}", self.name())
+    }
+}
+
+/// A numbered node, as listed by a `listof` node
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ListofEntry {
+    pub(crate) kind: Kind,
+    /// The label of the node, eg. `Figure 3`
+    pub(crate) label: String,
+    /// The HTML id of the node
+    pub(crate) id: String,
+    pub(crate) caption: String,
+}
+
+impl Parser {
+    /// Number every `image`, and every `table` and `codeblock` with a
+    /// `[caption ...]`, then resolve all `ref` and `listof` nodes
+    ///
+    /// Each kind of node is numbered separately, in document order.
+    pub(crate) fn number_nodes(&self, page: &mut Page, config: &Config) -> Result<(), ParseError> {
+        let mut numbered = vec![];
+        self.number(
+            &mut page.doc.nodes,
+            config.classes.id_prefix(),
+            &mut numbered,
+        )?;
+
+        self.resolve_references(&mut page.doc.nodes, &numbered)
+    }
+
+    fn number(
+        &self,
+        nodes: &mut [Node],
+        prefix: &str,
+        numbered: &mut Vec<ListofEntry>,
+    ) -> Result<(), ParseError> {
+        for node in nodes {
+            if let Node::Image { .. } = node {
+                for name in ["src", "alt", "caption"] {
+                    if node.attribute(name).is_none() {
+                        return Err(self.make_error(
+                            node.span(),
+                            ParseErrorType::MissingAttribute {
+                                node: "image".to_owned(),
+                                name: name.to_owned(),
+                            },
+                        ));
+                    }
+                }
+            }
+
+            let kind = match node {
+                Node::Image { .. } => Kind::Figure,
+                Node::Table { .. } if node.attribute("caption").is_some() => Kind::Table,
+                Node::Codeblock { .. } if node.attribute("caption").is_some() => Kind::Listing,
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.number(children, prefix, numbered)?;
+                    }
+
+                    continue;
+                }
+            };
+
+            let count = numbered.iter().filter(|e| e.kind == kind).count() + 1;
+            numbered.push(ListofEntry {
+                kind,
+                label: kind.label(count),
+                id: node
+                    .attribute("id")
+                    .map_or_else(|| kind.default_id(prefix, count), str::to_owned),
+                caption: node.attribute("caption").unwrap().to_owned(),
+            });
+
+            match node {
+                Node::Image { number, .. } | Node::Codeblock { number, .. } => {
+                    *number = Some(count)
+                }
+                Node::Table { number, inner, .. } => {
+                    *number = Some(count);
+                    self.number(inner, prefix, numbered)?;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Give every `ref` without children the label of the node it references,
+    /// and list the numbered nodes of the right kind in every `listof`
+    fn resolve_references(
+        &self,
+        nodes: &mut [Node],
+        numbered: &[ListofEntry],
+    ) -> Result<(), ParseError> {
+        for node in nodes {
+            let required = match node {
+                Node::Ref { .. } => "ref_id",
+                Node::Listof { .. } => "kind",
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.resolve_references(children, numbered)?;
+                    }
+
+                    continue;
+                }
+            };
+
+            let Some(value) = node.attribute(required) else {
+                return Err(self.make_error(
+                    node.span(),
+                    ParseErrorType::MissingAttribute {
+                        node: node.name().to_owned(),
+                        name: required.to_owned(),
+                    },
+                ));
+            };
+
+            match node {
+                Node::Ref {
+                    inner, label, span, ..
+                } => {
+                    if inner.iter().all(is_blank) {
+                        let target = self.find_numbered(numbered, value, *span)?;
+                        *label = Some(target.label.clone());
+                    }
+
+                    self.resolve_references(inner, numbered)?;
+                }
+                Node::Listof {
+                    attributes,
+                    entries,
+                    ..
+                } => {
+                    let kind = self.parse_kind(attributes)?;
+                    *entries = numbered
+                        .iter()
+                        .filter(|e| e.kind == kind)
+                        .cloned()
+                        .collect();
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the numbered node with a given id, reporting an error at the
+    /// reference `span` if there is none
+    fn find_numbered<'e>(
+        &self,
+        numbered: &'e [ListofEntry],
+        id: &str,
+        span: Span,
+    ) -> Result<&'e ListofEntry, ParseError> {
+        if let Some(entry) = numbered.iter().find(|e| e.id == id) {
+            return Ok(entry);
+        }
+
+        let mut error =
+            self.make_error(span, ParseErrorType::UnknownReference { id: id.to_owned() });
+        if let Some(suggestion) = did_you_mean(id, numbered.iter().map(|e| e.id.as_str())) {
+            error = error.add_context(format!("did you mean '{suggestion}'?"));
+        }
+
+        Err(error.add_context(
+            "a 'ref' without content must reference an image, or a table or codeblock with a \
+             caption"
+                .to_owned(),
+        ))
+    }
+
+    /// Parse the value of the `[kind ...]` attribute of a `listof` node
+    fn parse_kind(&self, attributes: &[Attribute]) -> Result<Kind, ParseError> {
+        let attribute = attributes
+            .iter()
+            .rfind(|a| a.attribute_name.name == "kind")
+            .unwrap();
+        let value = attribute.attribute_value.text.trim();
+
+        Kind::from_name(value).ok_or_else(|| {
+            let mut error = self.make_error(
+                attribute.attribute_value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: "kind".to_owned(),
+                    value: value.to_owned(),
+                },
+            );
+            if let Some(suggestion) = did_you_mean(value, Kind::NAMES.iter().copied()) {
+                error = error.add_context(format!("did you mean '{suggestion}'?"));
+            }
+
+            error
+        })
+    }
+}
+
+fn is_blank(node: &Node) -> bool {
+    matches!(node, Node::Text { inner } if inner.text.trim().is_empty())
+}
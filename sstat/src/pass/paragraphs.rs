@@ -0,0 +1,175 @@
+//! Implicit paragraphs
+
+use crate::parse::*;
+use crate::ParseErrorType;
+
+impl Parser {
+    /// Wrap all inline content in block context in paragraphs, splitting them
+    /// on blank lines
+    ///
+    /// Also warns about any block content found in inline context
+    pub(crate) fn wrap_paragraphs(&self, page: &mut Page) {
+        self.wrap_nodes(&mut page.doc.nodes, true);
+    }
+
+    fn wrap_nodes(&self, nodes: &mut Vec<Node>, block_context: bool) {
+        if block_context {
+            *nodes = self.wrap_inline_runs(std::mem::take(nodes));
+        }
+
+        for node in nodes {
+            let name = node.name();
+            let has_block_content = node.has_block_content();
+            let has_inline_content = node.has_inline_content();
+
+            let Some(children) = node.children_mut() else {
+                continue;
+            };
+
+            if has_inline_content {
+                for child in children.iter().filter(|c| c.is_block()) {
+                    self.warn(
+                        child.span(),
+                        ParseErrorType::BlockInInline {
+                            block: child.name().to_owned(),
+                            inline: name.to_owned(),
+                        },
+                    );
+                }
+            }
+
+            self.wrap_nodes(children, has_block_content);
+        }
+    }
+
+    /// Group every run of consecutive inline nodes into a paragraph
+    fn wrap_inline_runs<'s>(&self, nodes: Vec<Node<'s>>) -> Vec<Node<'s>> {
+        let mut wrapped = vec![];
+        let mut run = vec![];
+        let mut previous_end = None;
+
+        for node in nodes {
+            // The whitespace between nodes is not part of the AST, so check the
+            // source for blank lines in between
+            if let Some(end) = previous_end {
+                if self.has_blank_line(Span::new(end, node.span().start)) {
+                    flush_run(&mut run, &mut wrapped);
+                }
+            }
+            previous_end = Some(node.span().end);
+
+            match node {
+                // Raw HTML can be either, so leave it alone
+                _ if node.is_block() || matches!(node, Node::Html { .. }) => {
+                    flush_run(&mut run, &mut wrapped);
+                    wrapped.push(node);
+                }
+                Node::Text { inner } => {
+                    let mut pieces = split_blank_lines(inner).into_iter().peekable();
+
+                    while let Some(piece) = pieces.next() {
+                        if !piece.text.is_empty() {
+                            run.push(Node::Text { inner: piece });
+                        }
+
+                        // Every piece but the last one ends in a blank line
+                        if pieces.peek().is_some() {
+                            flush_run(&mut run, &mut wrapped);
+                        }
+                    }
+                }
+                _ => run.push(node),
+            }
+        }
+
+        flush_run(&mut run, &mut wrapped);
+
+        wrapped
+    }
+
+    /// Whether the source code in a span contains a blank line
+    fn has_blank_line(&self, span: Span) -> bool {
+        self.check_source(span, |gap| split_blank_lines_at(gap).len() > 1)
+    }
+}
+
+/// Turn a run of inline nodes into a paragraph, unless it only consists of
+/// whitespace
+fn flush_run<'s>(run: &mut Vec<Node<'s>>, wrapped: &mut Vec<Node<'s>>) {
+    let inner = std::mem::take(run);
+
+    let is_blank = inner
+        .iter()
+        .all(|n| matches!(n, Node::Text { inner } if inner.text.trim().is_empty()));
+    if is_blank {
+        return;
+    }
+
+    let start = inner.first().unwrap().span().start;
+    let end = inner.last().unwrap().span().end;
+
+    // Implicit paragraphs have no delimiters or name in the source, so these
+    // point at the start and end of their content instead
+    wrapped.push(Node::P {
+        lparen: LParen {
+            span: Span::new(start, start),
+        },
+        p: P {
+            span: Span::new(start, start),
+        },
+        attributes: vec![],
+        inner,
+        rparen: RParen {
+            span: Span::new(end, end),
+        },
+        span: Span::new(start, end),
+    });
+}
+
+/// Split text on every blank line, ie. every line that contains only
+/// whitespace
+///
+/// Text without any blank line keeps its span as it is, as text substituted
+/// for a variable has the span of the `var` node instead of its own.
+fn split_blank_lines(text: Text) -> Vec<Text> {
+    let pieces = split_blank_lines_at(text.text);
+    if pieces.len() == 1 {
+        return vec![text];
+    }
+
+    pieces
+        .into_iter()
+        .map(|(start, end)| Text {
+            text: &text.text[start..end],
+            span: Span::new(text.span.start + start, text.span.start + end),
+        })
+        .collect()
+}
+
+/// The byte ranges of the pieces of some source code between blank lines
+fn split_blank_lines_at(source: &str) -> Vec<(usize, usize)> {
+    let mut pieces = vec![];
+    let mut piece_start = 0;
+    let mut search_start = 0;
+
+    while let Some(i) = source[search_start..].find('\n') {
+        let newline = search_start + i;
+
+        let after = &source[newline + 1..];
+        let indent = after.len() - after.trim_start_matches([' ', '\t', '\r']).len();
+
+        if after[indent..].starts_with('\n') {
+            let blank_end = newline + 1 + indent + 1;
+
+            pieces.push((piece_start, newline));
+            piece_start = blank_end;
+            search_start = blank_end;
+        } else {
+            search_start = newline + 1;
+        }
+    }
+
+    pieces.push((piece_start, source.len()));
+
+    pieces
+}
@@ -0,0 +1,568 @@
+//! Safe mode for pages from untrusted authors
+
+use std::fmt::Write;
+
+use crate::bibliography::{Reference, Segment};
+use crate::parse::*;
+use crate::{Config, ParseErrorType};
+
+/// The tags allowed in `html` nodes in safe mode, each along with the
+/// attributes allowed on it besides [`GLOBAL_ATTRIBUTES`]
+const ALLOWED_TAGS: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("abbr", &[]),
+    ("b", &[]),
+    ("blockquote", &["cite"]),
+    ("br", &[]),
+    ("caption", &[]),
+    ("cite", &[]),
+    ("code", &[]),
+    ("col", &["span"]),
+    ("colgroup", &["span"]),
+    ("dd", &[]),
+    ("del", &["cite", "datetime"]),
+    ("details", &["open"]),
+    ("dfn", &[]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("figcaption", &[]),
+    ("figure", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("ins", &["cite", "datetime"]),
+    ("kbd", &[]),
+    ("li", &["value"]),
+    ("mark", &[]),
+    ("ol", &["start", "reversed", "type"]),
+    ("p", &[]),
+    ("pre", &[]),
+    ("q", &["cite"]),
+    ("rp", &[]),
+    ("rt", &[]),
+    ("ruby", &[]),
+    ("s", &[]),
+    ("samp", &[]),
+    ("small", &[]),
+    ("span", &[]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["colspan", "rowspan"]),
+    ("tfoot", &[]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("thead", &[]),
+    ("time", &["datetime"]),
+    ("tr", &[]),
+    ("u", &[]),
+    ("ul", &[]),
+    ("var", &[]),
+    ("wbr", &[]),
+];
+
+/// The attributes allowed on every tag in `html` nodes in safe mode
+const GLOBAL_ATTRIBUTES: &[&str] = &["id", "class", "title", "lang", "dir", "style"];
+
+/// Tags whose content is not HTML, which is removed along with them
+const RAW_TEXT_TAGS: &[&str] = &[
+    "script",
+    "style",
+    "textarea",
+    "title",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+];
+
+/// Attributes whose values are URLs
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "poster", "cite"];
+
+/// The schemes URLs can have in safe mode, besides being relative
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// The CSS properties allowed in styles in safe mode
+const CSS_PROPERTIES: &[&str] = &[
+    "color",
+    "background-color",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "text-align",
+    "text-decoration",
+    "text-indent",
+    "text-transform",
+    "letter-spacing",
+    "word-spacing",
+    "line-height",
+    "white-space",
+    "vertical-align",
+    "list-style-type",
+    "border-collapse",
+    "width",
+    "height",
+    "min-width",
+    "max-width",
+    "min-height",
+    "max-height",
+    "opacity",
+];
+
+/// CSS shorthand properties allowed in safe mode along with all of their
+/// longhand properties, eg. `margin-top` for `margin`
+const CSS_PROPERTY_FAMILIES: &[&str] = &["margin", "padding", "border"];
+
+/// Text that is never allowed in CSS values in safe mode, as it can load
+/// resources, run scripts or hide either of those
+const CSS_FORBIDDEN: &[&str] = &[
+    "url(",
+    "image-set(",
+    "expression(",
+    "javascript:",
+    "\\",
+    "/*",
+    "@",
+    "<",
+];
+
+impl Parser {
+    /// In safe mode, remove everything from a page that could run scripts or
+    /// load unexpected resources, reporting each removal as a warning
+    ///
+    /// `html` nodes are reduced to an allowlist of tags and attributes, URLs
+    /// must be relative or use an allowed scheme, and styles are reduced to
+    /// an allowlist of CSS properties.
+    pub(crate) fn sanitize<'s>(&'s self, page: &mut Page<'s>, config: &Config) {
+        if !config.safe {
+            return;
+        }
+
+        page.attributes
+            .retain(|attribute| match attribute.attribute_name.name {
+                "stylesheet" | "cover" => self.check_url(attribute.attribute_value),
+                _ => true,
+            });
+        self.sanitize_attributes(&mut page.doc.attributes);
+        self.sanitize_nodes(&mut page.doc.nodes);
+    }
+
+    fn sanitize_nodes<'s>(&'s self, nodes: &mut [Node<'s>]) {
+        for node in nodes {
+            if let Node::Html { text, .. } = node {
+                text.text = self.alloc_str(&self.sanitize_html(*text));
+            }
+            if let Node::Bibliography {
+                references, span, ..
+            } = node
+            {
+                self.sanitize_references(references, *span);
+            }
+            if let Some(attributes) = node.attributes_mut() {
+                self.sanitize_attributes(attributes);
+            }
+            if let Some(children) = node.children_mut() {
+                self.sanitize_nodes(children);
+            }
+        }
+    }
+
+    /// Empty URL attributes with disallowed schemes, which nodes may require,
+    /// and filter styles
+    fn sanitize_attributes<'s>(&'s self, attributes: &mut Vec<Attribute<'s>>) {
+        attributes.retain_mut(|attribute| {
+            let value = attribute.attribute_value;
+
+            match attribute.attribute_name.name {
+                name if URL_ATTRIBUTES.contains(&name) => {
+                    if !self.check_url(value) {
+                        attribute.attribute_value.text = "";
+                    }
+
+                    true
+                }
+                "style" => {
+                    let style = self.filter_style(value.text, value.span);
+                    attribute.attribute_value.text = self.alloc_str(&style);
+
+                    !style.is_empty()
+                }
+                _ => true,
+            }
+        });
+    }
+
+    /// Remove the links of bibliography entries whose URLs are not allowed,
+    /// warning about them at the `bibliography` node listing them
+    fn sanitize_references(&self, references: &mut [Reference], span: Span) {
+        for reference in references {
+            reference.segments.retain(|segment| match segment {
+                Segment::Link(url) => self.check_url(Text { text: url, span }),
+                _ => true,
+            });
+        }
+    }
+
+    /// Whether a URL is allowed, warning if it is not
+    fn check_url(&self, value: Text) -> bool {
+        let url = value.text.trim();
+        if is_safe_url(url) {
+            return true;
+        }
+
+        self.warn(
+            value.span,
+            ParseErrorType::UnsafeUrl {
+                url: url.to_owned(),
+            },
+        );
+
+        false
+    }
+
+    /// The declarations of a style that only use allowed properties and
+    /// values, warning about every other one
+    fn filter_style(&self, style: &str, span: Span) -> String {
+        let mut kept = vec![];
+
+        for declaration in style.split(';').map(str::trim) {
+            if declaration.is_empty() {
+                continue;
+            }
+
+            let allowed = declaration
+                .split_once(':')
+                .is_some_and(|(property, value)| {
+                    let property = property.trim().to_ascii_lowercase();
+                    let value = value.to_ascii_lowercase().replace(char::is_whitespace, "");
+
+                    let known = CSS_PROPERTIES.contains(&property.as_str())
+                        || CSS_PROPERTY_FAMILIES.iter().any(|family| {
+                            property == *family
+                                || property
+                                    .strip_prefix(family)
+                                    .is_some_and(|rest| rest.starts_with('-'))
+                        });
+
+                    known && !CSS_FORBIDDEN.iter().any(|text| value.contains(text))
+                });
+
+            match allowed {
+                true => kept.push(declaration),
+                false => self.warn(
+                    span,
+                    ParseErrorType::UnsafeStyle {
+                        declaration: declaration.to_owned(),
+                    },
+                ),
+            }
+        }
+
+        kept.join("; ")
+    }
+
+    /// Rebuild the content of an `html` node from its allowed tags and
+    /// attributes, warning about everything else
+    ///
+    /// Text is kept as is, comments are dropped, and any `<` that does not
+    /// start a tag is escaped, so that only the rebuilt tags remain.
+    fn sanitize_html(&self, text: Text) -> String {
+        let source = text.text;
+        let mut output = String::with_capacity(source.len());
+        let mut rest = source;
+
+        while let Some(start) = rest.find('<') {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let offset = source.len() - rest.len();
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+
+            let Some(tag) = parse_tag(rest) else {
+                output.push_str("&lt;");
+                rest = &rest[1..];
+                continue;
+            };
+            rest = &rest[tag.len..];
+
+            // Spans into the source only exist if the text is still the
+            // source, rather than the value of a variable
+            let span = match text.span.end - text.span.start == source.len() {
+                true => Span::new(text.span.start + offset, text.span.start + offset + tag.len),
+                false => text.span,
+            };
+
+            let allowed = ALLOWED_TAGS.iter().find(|(name, _)| *name == tag.name);
+            let Some((name, allowed)) = allowed else {
+                // Only opening tags are reported, as closing tags only come
+                // with them
+                if !tag.closing {
+                    self.warn(
+                        span,
+                        ParseErrorType::UnsafeTag {
+                            tag: tag.name.clone(),
+                        },
+                    );
+
+                    if RAW_TEXT_TAGS.contains(&tag.name.as_str()) {
+                        rest = skip_raw_text(rest, &tag.name);
+                    }
+                }
+
+                continue;
+            };
+
+            if tag.closing {
+                write!(output, "</{name}>").unwrap();
+                continue;
+            }
+
+            write!(output, "<{name}").unwrap();
+            for (attribute, value) in tag.attributes {
+                let attribute = attribute.to_ascii_lowercase();
+
+                if !GLOBAL_ATTRIBUTES.contains(&attribute.as_str())
+                    && !allowed.contains(&attribute.as_str())
+                {
+                    self.warn(span, ParseErrorType::UnsafeAttribute { name: attribute });
+                    continue;
+                }
+
+                let Some(value) = value else {
+                    write!(output, " {attribute}").unwrap();
+                    continue;
+                };
+
+                // URLs and styles are written out as they were checked, so that
+                // character references cannot change them afterwards
+                let (value, decoded) = match attribute.as_str() {
+                    name if URL_ATTRIBUTES.contains(&name) => {
+                        let url = decode_entities(value);
+                        if !is_safe_url(&url) {
+                            self.warn(
+                                span,
+                                ParseErrorType::UnsafeUrl {
+                                    url: value.to_owned(),
+                                },
+                            );
+                            continue;
+                        }
+
+                        (url, true)
+                    }
+                    "style" => (self.filter_style(&decode_entities(value), span), true),
+                    _ => (value.to_owned(), false),
+                };
+
+                write!(output, " {attribute}=\"").unwrap();
+                for c in value.chars() {
+                    match c {
+                        '&' if decoded => output.push_str("&amp;"),
+                        '"' => output.push_str("&quot;"),
+                        '<' => output.push_str("&lt;"),
+                        '>' => output.push_str("&gt;"),
+                        c => output.push(c),
+                    }
+                }
+                output.push('"');
+            }
+            output.push('>');
+        }
+        output.push_str(rest);
+
+        output
+    }
+}
+
+/// A tag in an `html` node
+struct Tag<'a> {
+    /// The name of the tag, in lowercase
+    name: String,
+    /// Whether this is a closing tag, eg. `</p>`
+    closing: bool,
+    /// The attributes of the tag as written, along with their values if they
+    /// have one
+    attributes: Vec<(&'a str, Option<&'a str>)>,
+    /// The length of the tag in bytes
+    len: usize,
+}
+
+/// Parse the tag at the start of some HTML, if it starts with one
+fn parse_tag(html: &str) -> Option<Tag<'_>> {
+    let after = html.strip_prefix('<')?;
+    let (closing, after) = match after.strip_prefix('/') {
+        Some(after) => (true, after),
+        None => (false, after),
+    };
+
+    if !after.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name_len = after
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(after.len());
+    let name = after[..name_len].to_ascii_lowercase();
+
+    let mut rest = &after[name_len..];
+    let mut attributes = vec![];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if let Some(after) = rest.strip_prefix('>') {
+            rest = after;
+            break;
+        }
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(rest.len())
+            // A name always has at least one character, even if it is `=`
+            .max(1);
+        let attribute = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        let Some(after) = rest.strip_prefix('=') else {
+            attributes.push((attribute, None));
+            continue;
+        };
+        rest = after.trim_start();
+
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = rest[1..].find(quote)?;
+                let value = &rest[1..end + 1];
+                rest = &rest[end + 2..];
+                value
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                let value = &rest[..end];
+                rest = &rest[end..];
+                value
+            }
+        };
+        attributes.push((attribute, Some(value)));
+    }
+
+    Some(Tag {
+        name,
+        closing,
+        attributes: match closing {
+            true => vec![],
+            false => attributes,
+        },
+        len: html.len() - rest.len(),
+    })
+}
+
+/// Skip the content of an element whose content is not HTML, along with its
+/// closing tag
+fn skip_raw_text<'a>(html: &'a str, name: &str) -> &'a str {
+    let closing = format!("</{name}");
+    let lowercase = html.to_ascii_lowercase();
+
+    match lowercase.find(&closing) {
+        Some(start) => {
+            let rest = &html[start..];
+            rest.find('>').map_or("", |end| &rest[end + 1..])
+        }
+        None => "",
+    }
+}
+
+/// Decode the character references browsers decode in attribute values that
+/// could hide a URL scheme, eg. `&#106;` for `j` or `&colon;` for `:`
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let (c, len) = if let Some(number) = rest.strip_prefix('#') {
+            let (radix, digits) = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => (16, hex),
+                None => (10, number),
+            };
+            let len = digits
+                .find(|c: char| !c.is_digit(radix))
+                .unwrap_or(digits.len());
+            let c = u32::from_str_radix(&digits[..len], radix)
+                .ok()
+                .and_then(char::from_u32);
+
+            (c, number.len() - digits.len() + 1 + len)
+        } else {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let c = match rest[..len].to_ascii_lowercase().as_str() {
+                "colon" => Some(':'),
+                "tab" => Some('\t'),
+                "newline" => Some('\n'),
+                "amp" => Some('&'),
+                _ => None,
+            };
+
+            (c, len)
+        };
+
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[len..];
+                rest = rest.strip_prefix(';').unwrap_or(rest);
+            }
+            None => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+/// Whether a URL is relative or uses one of the [`URL_SCHEMES`]
+///
+/// Browsers ignore whitespace and control characters in schemes, so they are
+/// ignored here as well.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+
+    let Some((scheme, _)) = url.split_once(':') else {
+        return true;
+    };
+    let scheme = scheme.to_ascii_lowercase();
+
+    // A colon after the path has started is not part of a scheme
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    !is_scheme || URL_SCHEMES.contains(&scheme.as_str())
+}
@@ -0,0 +1,143 @@
+//! Attributes of semantic inline nodes
+
+use crate::parse::*;
+use crate::{ParseError, ParseErrorType};
+
+/// The nodes that require attributes, along with the names of those
+/// attributes
+const REQUIRED: &[(&str, &[&str])] = &[("abbr", &["title"]), ("time", &["datetime"])];
+
+/// The nodes that accept a `[datetime ...]` attribute
+const DATETIME_NODES: &[&str] = &["time", "del", "ins"];
+
+impl Parser {
+    /// Check that every `abbr` has a `[title ...]` and every `time` has a
+    /// `[datetime ...]`, and that every `[datetime ...]` is a valid date, time,
+    /// or both
+    pub(crate) fn check_semantics(&self, page: &mut Page) -> Result<(), ParseError> {
+        self.check_node_semantics(&mut page.doc.nodes)
+    }
+
+    fn check_node_semantics(&self, nodes: &mut [Node]) -> Result<(), ParseError> {
+        for node in nodes {
+            let name = node.name();
+
+            let required = REQUIRED
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map_or(&[] as &[&str], |(_, required)| required);
+            for attribute in required {
+                if node.attribute(attribute).is_none() {
+                    return Err(self.make_error(
+                        node.span(),
+                        ParseErrorType::MissingAttribute {
+                            node: name.to_owned(),
+                            name: (*attribute).to_owned(),
+                        },
+                    ));
+                }
+            }
+
+            if DATETIME_NODES.contains(&name) {
+                for attribute in node.attributes() {
+                    if attribute.attribute_name.name != "datetime" {
+                        continue;
+                    }
+
+                    let value = attribute.attribute_value.text.trim();
+                    if !is_datetime(value) {
+                        return Err(self
+                            .make_error(
+                                attribute.attribute_value.span,
+                                ParseErrorType::InvalidAttributeValue {
+                                    name: "datetime".to_owned(),
+                                    value: value.to_owned(),
+                                },
+                            )
+                            .add_context(
+                                "expected a date like '2026-10-17', a time like '14:30', or \
+                                 both, like '2026-10-17T14:30Z'"
+                                    .to_owned(),
+                            ));
+                    }
+                }
+            }
+
+            if let Some(children) = node.children_mut() {
+                self.check_node_semantics(children)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a string is a date (`2026`, `2026-10`, or `2026-10-17`), a time
+/// (`14:30`, `14:30:05`, or `14:30:05.250`), or a date and a time separated by
+/// `T` or a space and optionally followed by a time zone (`Z` or `+02:00`), as
+/// accepted by the HTML `datetime` attribute
+pub(super) fn is_datetime(value: &str) -> bool {
+    if is_date(value) || is_time(value) {
+        return true;
+    }
+
+    let Some((date, time)) = value.split_once(['T', ' ']) else {
+        return false;
+    };
+    let time = match time.strip_suffix('Z') {
+        Some(time) => time,
+        None => match time.rfind(['+', '-']) {
+            Some(idx) if is_zone(&time[idx + 1..]) => &time[..idx],
+            _ => time,
+        },
+    };
+
+    date.split('-').count() == 3 && is_date(date) && is_time(time)
+}
+
+fn is_date(value: &str) -> bool {
+    let parts: Vec<_> = value.split('-').collect();
+
+    match parts[..] {
+        [year] => is_number(year, 4),
+        [year, month] => is_number(year, 4) && is_in_range(month, 1, 12),
+        [year, month, day] => {
+            is_number(year, 4) && is_in_range(month, 1, 12) && is_in_range(day, 1, 31)
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn is_time(value: &str) -> bool {
+    let parts: Vec<_> = value.split(':').collect();
+
+    let (hour, minute, second) = match parts[..] {
+        [hour, minute] => (hour, minute, None),
+        [hour, minute, second] => (hour, minute, Some(second)),
+        _ => return false,
+    };
+
+    let second_valid = second.is_none_or(|second| {
+        let (whole, fraction) = second.split_once('.').unwrap_or((second, "0"));
+        is_in_range(whole, 0, 59)
+            && (1..=3).contains(&fraction.len())
+            && is_number(fraction, fraction.len())
+    });
+
+    is_in_range(hour, 0, 23) && is_in_range(minute, 0, 59) && second_valid
+}
+
+/// Whether a string is a time zone offset like `02:00`
+fn is_zone(value: &str) -> bool {
+    matches!(value.split_once(':'), Some((hours, minutes)) if is_in_range(hours, 0, 23) && is_in_range(minutes, 0, 59))
+}
+
+/// Whether a string consists of exactly `digits` ASCII digits
+fn is_number(value: &str, digits: usize) -> bool {
+    value.len() == digits && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether a string is a two-digit number between `min` and `max`
+fn is_in_range(value: &str, min: u32, max: u32) -> bool {
+    is_number(value, 2) && value.parse().is_ok_and(|n: u32| (min..=max).contains(&n))
+}
@@ -0,0 +1,360 @@
+//! Table layout: cell spans, column alignment, and tables imported from CSV
+
+use crate::parse::*;
+use crate::{did_you_mean, ParseError, ParseErrorType};
+
+/// The alignment of a table column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    /// The names of all alignments, as used in the `[align ...]` attribute
+    const NAMES: &'static [&'static str] = &["l", "c", "r", "left", "center", "right"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "l" | "left" => Some(Self::Left),
+            "c" | "center" => Some(Self::Center),
+            "r" | "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    /// The value of the CSS `text-align` property for this alignment
+    pub(crate) fn css(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+}
+
+/// The largest `[colspan ...]` of a cell, the same limit as in HTML
+const MAX_COLSPAN: usize = 1000;
+
+/// The largest `[rowspan ...]` of a cell, the same limit as in HTML
+const MAX_ROWSPAN: usize = 65534;
+
+/// A column occupied by a cell spanning multiple rows
+#[derive(Clone, Copy)]
+struct Occupied {
+    /// The number of rows, including the current one, the cell still covers
+    rows: usize,
+    /// The number of rows the cell spans in total
+    rowspan: usize,
+    span: Span,
+}
+
+impl Parser {
+    /// Import the rows of every `table` with a `[src ...]` attribute, check
+    /// that every row of a table has the same width once cell spans are taken
+    /// into account, and give every cell the alignment of its column
+    pub(crate) fn layout_tables<'s>(&'s self, page: &mut Page<'s>) -> Result<(), ParseError> {
+        self.layout_node_tables(&mut page.doc.nodes)
+    }
+
+    fn layout_node_tables<'s>(&'s self, nodes: &mut [Node<'s>]) -> Result<(), ParseError> {
+        for node in nodes {
+            if let Node::Table {
+                attributes, inner, ..
+            } = node
+            {
+                if let Some(src) = attributes.iter().rfind(|a| a.attribute_name.name == "src") {
+                    let header = attributes
+                        .iter()
+                        .rfind(|a| a.attribute_name.name == "header")
+                        .and_then(|a| parse_flag(a.attribute_value.text.trim()))
+                        .unwrap_or(false);
+
+                    let rows = self.import_csv(src.attribute_value, header)?;
+                    inner.splice(0..0, rows);
+                }
+
+                let aligns = self.parse_align(attributes)?;
+                self.layout_table(inner, &aligns)?;
+            }
+
+            if let Some(children) = node.children_mut() {
+                self.layout_node_tables(children)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the CSV file named by a `[src ...]` attribute and turn it into
+    /// table rows, with the first one in a `thead` if `header` is set
+    fn import_csv<'s>(&'s self, value: Text, header: bool) -> Result<Vec<Node<'s>>, ParseError> {
+        let (source, offset) = self.import_file(value)?;
+
+        let rows = parse_csv(source).map_err(|e| {
+            self.make_error(
+                Span::new(offset + e.start, offset + e.end),
+                ParseErrorType::InvalidCsv { reason: e.reason },
+            )
+        })?;
+
+        let mut nodes: Vec<Node> = rows
+            .into_iter()
+            .map(|row| {
+                let cells = row
+                    .fields
+                    .into_iter()
+                    .map(|field| {
+                        let span = Span::new(offset + field.start, offset + field.end);
+                        let inner = vec![Node::Text {
+                            inner: Text {
+                                text: self.alloc_str(&field.text),
+                                span,
+                            },
+                        }];
+
+                        // Imported cells have no delimiters or name in the
+                        // source, so these point at their field instead
+                        Node::Tcell {
+                            lparen: LParen {
+                                span: Span::new(span.start, span.start),
+                            },
+                            tcell: Tcell {
+                                span: Span::new(span.start, span.start),
+                            },
+                            attributes: vec![],
+                            inner,
+                            align: None,
+                            literal: true,
+                            rparen: RParen {
+                                span: Span::new(span.end, span.end),
+                            },
+                            span,
+                        }
+                    })
+                    .collect();
+
+                let span = Span::new(offset + row.start, offset + row.end);
+                Node::Trow {
+                    lparen: LParen {
+                        span: Span::new(span.start, span.start),
+                    },
+                    trow: Trow {
+                        span: Span::new(span.start, span.start),
+                    },
+                    attributes: vec![],
+                    inner: cells,
+                    rparen: RParen {
+                        span: Span::new(span.end, span.end),
+                    },
+                    span,
+                }
+            })
+            .collect();
+
+        if header && !nodes.is_empty() {
+            let row = nodes.remove(0);
+            let span = row.span();
+
+            nodes.insert(
+                0,
+                Node::Thead {
+                    lparen: LParen {
+                        span: Span::new(span.start, span.start),
+                    },
+                    thead: Thead {
+                        span: Span::new(span.start, span.start),
+                    },
+                    attributes: vec![],
+                    inner: vec![row],
+                    rparen: RParen {
+                        span: Span::new(span.end, span.end),
+                    },
+                    span,
+                },
+            );
+        }
+
+        Ok(nodes)
+    }
+
+    /// Parse the value of the `[align ...]` attribute of a table, which
+    /// lists the alignment of each column in order
+    fn parse_align(&self, attributes: &[Attribute]) -> Result<Vec<Align>, ParseError> {
+        let Some(attribute) = attributes
+            .iter()
+            .rfind(|a| a.attribute_name.name == "align")
+        else {
+            return Ok(vec![]);
+        };
+
+        attribute
+            .attribute_value
+            .text
+            .split_whitespace()
+            .map(|name| {
+                Align::from_name(name).ok_or_else(|| {
+                    let mut error = self.make_error(
+                        attribute.attribute_value.span,
+                        ParseErrorType::InvalidAttributeValue {
+                            name: "align".to_owned(),
+                            value: name.to_owned(),
+                        },
+                    );
+                    if let Some(suggestion) = did_you_mean(name, Align::NAMES.iter().copied()) {
+                        error = error.add_context(format!("did you mean '{suggestion}'?"));
+                    }
+
+                    error
+                })
+            })
+            .collect()
+    }
+
+    /// Lay out the rows of a table, which are split into the rows of its
+    /// `thead`, its own rows, and the rows of its `tfoot`
+    ///
+    /// Cells may only span rows within the same part of the table.
+    fn layout_table(&self, inner: &mut [Node], aligns: &[Align]) -> Result<(), ParseError> {
+        let mut parts: Vec<Vec<&mut Node>> = vec![];
+        let mut body = vec![];
+
+        for node in inner {
+            match node {
+                Node::Trow { .. } => body.push(node),
+                Node::Thead { inner, .. } | Node::Tfoot { inner, .. } => {
+                    parts.push(inner.iter_mut().collect());
+                }
+                _ => (),
+            }
+        }
+        parts.push(body);
+
+        // The width of the first row, along with its span
+        let mut expected = None;
+
+        for rows in parts {
+            let mut occupied: Vec<Option<Occupied>> = vec![];
+            let mut remaining = rows
+                .iter()
+                .filter(|n| matches!(n, Node::Trow { .. }))
+                .count();
+
+            for row in rows {
+                let row_span = row.span();
+                let Node::Trow { inner, .. } = row else {
+                    continue;
+                };
+
+                let mut column = 0;
+                for cell in inner {
+                    let Node::Tcell {
+                        attributes,
+                        align,
+                        span,
+                        ..
+                    } = cell
+                    else {
+                        continue;
+                    };
+
+                    let colspan = self.parse_cell_span(attributes, "colspan", MAX_COLSPAN)?;
+                    let rowspan = self.parse_cell_span(attributes, "rowspan", MAX_ROWSPAN)?;
+
+                    while occupied.get(column).is_some_and(Option::is_some) {
+                        column += 1;
+                    }
+
+                    if rowspan > remaining {
+                        return Err(self.make_error(
+                            *span,
+                            ParseErrorType::RowspanOverflow { rowspan, remaining },
+                        ));
+                    }
+
+                    if occupied.len() < column + colspan {
+                        occupied.resize(column + colspan, None);
+                    }
+                    for slot in &mut occupied[column..column + colspan] {
+                        if let Some(other) = slot {
+                            let error = self.make_error(*span, ParseErrorType::OverlappingCells);
+                            return Err(self.add_related(
+                                error,
+                                other.span,
+                                &format!("this cell spans {} rows", other.rowspan),
+                            ));
+                        }
+
+                        *slot = Some(Occupied {
+                            rows: rowspan,
+                            rowspan,
+                            span: *span,
+                        });
+                    }
+
+                    *align = aligns.get(column).copied();
+                    column += colspan;
+                }
+
+                let width = occupied.iter().filter(|o| o.is_some()).count();
+                match expected {
+                    None => expected = Some((width, row_span)),
+                    Some((expected, first)) if expected != width => {
+                        let error = self.make_error(
+                            row_span,
+                            ParseErrorType::InconsistentRowWidth {
+                                expected,
+                                found: width,
+                            },
+                        );
+
+                        return Err(self.add_related(
+                            error,
+                            first,
+                            &format!("the first row has {expected} columns"),
+                        ));
+                    }
+                    Some(_) => (),
+                }
+
+                for slot in &mut occupied {
+                    *slot = slot.filter(|o| o.rows > 1).map(|o| Occupied {
+                        rows: o.rows - 1,
+                        ..o
+                    });
+                }
+                remaining -= 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse the value of a `[colspan ...]` or `[rowspan ...]` attribute of a
+    /// cell, which defaults to 1 and can be at most `max`
+    fn parse_cell_span(
+        &self,
+        attributes: &[Attribute],
+        name: &str,
+        max: usize,
+    ) -> Result<usize, ParseError> {
+        let Some(attribute) = attributes.iter().rfind(|a| a.attribute_name.name == name) else {
+            return Ok(1);
+        };
+        let value = attribute.attribute_value.text.trim();
+
+        match value.parse() {
+            Ok(n) if (1..=max).contains(&n) => Ok(n),
+            _ => Err(self
+                .make_error(
+                    attribute.attribute_value.span,
+                    ParseErrorType::InvalidAttributeValue {
+                        name: name.to_owned(),
+                        value: value.to_owned(),
+                    },
+                )
+                .add_context(format!("'{name}' must be a whole number from 1 to {max}"))),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Smart typography
+
+use crate::parse::*;
+use crate::{did_you_mean, Config, ParseError, ParseErrorType, Typography};
+
+const NBSP: char = '\u{a0}';
+
+/// Units that are kept on the same line as the number before them
+const UNITS: &[&str] = &[
+    "%", "‰", "°", "°C", "°F", "K", "mm", "cm", "dm", "m", "km", "mg", "g", "kg", "t", "ml", "l",
+    "ms", "s", "min", "h", "Hz", "kHz", "MHz", "GHz", "B", "kB", "KiB", "MB", "MiB", "GB", "GiB",
+    "TB", "TiB", "W", "kW", "MW", "V", "mA", "A", "J", "kJ", "N", "Pa", "kPa", "px", "pt",
+];
+
+/// Single-letter words that are kept on the same line as the word after them,
+/// for every language where this is customary
+const SINGLE_LETTER_WORDS: &[(&str, &[&str])] = &[
+    ("cs", &["a", "i", "k", "o", "s", "u", "v", "z"]),
+    ("sk", &["a", "i", "k", "o", "s", "u", "v", "z"]),
+    ("pl", &["a", "i", "o", "u", "w", "z"]),
+];
+
+/// Characters after which a quote opens rather than closes
+const OPENING_CONTEXT: &[char] = &['(', '[', '{', '“', '‘', '—', '–', '-', '/'];
+
+/// The typographic conventions applied to a page
+struct Conventions {
+    single_letter_words: &'static [&'static str],
+}
+
+impl Parser {
+    /// Apply smart typography to all text in a page, if enabled
+    ///
+    /// The `[typography ...]` and `[lang ...]` page attributes override the
+    /// [`Config`]. Text inside `mono`, `codeblock` and `html` nodes, and in
+    /// table cells imported from CSV files, is left untouched.
+    pub(crate) fn apply_typography<'s>(
+        &'s self,
+        page: &mut Page<'s>,
+        config: &Config,
+    ) -> Result<(), ParseError> {
+        let mut typography = config.typography;
+        let mut language = config.language.as_deref();
+
+        for attribute in &page.attributes {
+            match attribute.attribute_name.name {
+                "typography" => typography = self.parse_typography(attribute)?,
+                "lang" => language = Some(attribute.attribute_value.text.trim()),
+                _ => (),
+            }
+        }
+
+        if typography == Typography::Plain {
+            return Ok(());
+        }
+
+        // Only the primary language subtag matters, eg. `cs` in `cs-CZ`
+        let language = language.map(|l| l.split('-').next().unwrap());
+        let single_letter_words = SINGLE_LETTER_WORDS
+            .iter()
+            .find(|(l, _)| Some(*l) == language)
+            .map_or(&[] as &[&str], |(_, words)| words);
+
+        let conventions = Conventions {
+            single_letter_words,
+        };
+        self.typeset_nodes(&mut page.doc.nodes, &conventions, &mut None);
+
+        Ok(())
+    }
+
+    /// Parse the value of a `[typography ...]` attribute
+    pub(crate) fn parse_typography(&self, attribute: &Attribute) -> Result<Typography, ParseError> {
+        let value = attribute.attribute_value.text.trim();
+
+        Typography::from_name(value).ok_or_else(|| {
+            let mut error = self.make_error(
+                attribute.attribute_value.span,
+                ParseErrorType::InvalidAttributeValue {
+                    name: "typography".to_owned(),
+                    value: value.to_owned(),
+                },
+            );
+            if let Some(suggestion) = did_you_mean(value, Typography::NAMES.iter().copied()) {
+                error = error.add_context(format!("did you mean '{suggestion}'?"));
+            }
+
+            error
+        })
+    }
+
+    /// Typeset all text in a list of nodes
+    ///
+    /// `previous` is the character before the nodes, which decides whether
+    /// quotes open or close
+    fn typeset_nodes<'s>(
+        &'s self,
+        nodes: &mut [Node<'s>],
+        conventions: &Conventions,
+        previous: &mut Option<char>,
+    ) {
+        for node in nodes {
+            if node.is_block() {
+                *previous = None;
+            }
+
+            match node {
+                Node::Text { inner } => {
+                    let typeset = typeset(inner.text, conventions, previous);
+                    if typeset != inner.text {
+                        inner.text = self.alloc_str(&typeset);
+                    }
+                }
+                // Monospace text is treated as a single word
+                Node::Mono { .. } => *previous = Some('x'),
+                Node::Tcell { literal: true, .. } => (),
+                _ => {
+                    if let Some(children) = node.children_mut() {
+                        self.typeset_nodes(children, conventions, previous);
+                    }
+                }
+            }
+
+            if node.is_block() {
+                *previous = None;
+            }
+        }
+    }
+}
+
+/// Typeset a single piece of text
+fn typeset(text: &str, conventions: &Conventions, previous: &mut Option<char>) -> String {
+    let mut typeset = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let replacement = match c {
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+
+                if chars.peek() == Some(&'-') {
+                    chars.next();
+                    '—'
+                } else {
+                    '–'
+                }
+            }
+            '.' if chars.clone().take(2).eq(['.', '.']) => {
+                chars.nth(1);
+                '…'
+            }
+            '"' if opens_quote(*previous) => '“',
+            '"' => '”',
+            '\'' if opens_quote(*previous) => '‘',
+            '\'' => '’',
+            c => c,
+        };
+
+        typeset.push(replacement);
+        *previous = Some(replacement);
+    }
+
+    insert_nbsp(&typeset, conventions)
+}
+
+fn opens_quote(previous: Option<char>) -> bool {
+    previous.is_none_or(|p| p.is_whitespace() || OPENING_CONTEXT.contains(&p))
+}
+
+/// Replace the spaces after numbers followed by a unit and after single-letter
+/// words with non-breaking spaces
+fn insert_nbsp(text: &str, conventions: &Conventions) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+
+    let mut joined = String::with_capacity(text.len());
+    for (i, word) in words.iter().enumerate() {
+        joined.push_str(word);
+
+        let Some(next) = words.get(i + 1) else {
+            break;
+        };
+
+        let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let next = next.trim_end_matches(|c: char| !c.is_alphanumeric() && !"%‰°".contains(c));
+
+        let is_number = word.chars().any(|c| c.is_ascii_digit())
+            && word
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.' || c == ',');
+        let is_single_letter_word = conventions
+            .single_letter_words
+            .contains(&word.to_lowercase().as_str());
+
+        if is_number && UNITS.contains(&next) || is_single_letter_word {
+            joined.push(NBSP);
+        } else {
+            joined.push(' ');
+        }
+    }
+
+    joined
+}
@@ -0,0 +1,108 @@
+//! Variable definitions and substitution
+
+use std::collections::HashMap;
+
+use crate::parse::*;
+use crate::{did_you_mean, Config, ParseError, ParseErrorType};
+
+/// All variables visible at some point in a page, innermost scope last
+struct Scopes<'s> {
+    frames: Vec<HashMap<&'s str, &'s str>>,
+}
+
+impl<'s> Scopes<'s> {
+    /// Find the value of a variable, preferring the innermost definition
+    fn lookup(&self, name: &str) -> Option<&'s str> {
+        self.frames.iter().rev().find_map(|f| f.get(name).copied())
+    }
+
+    /// The names of all visible variables
+    fn names(&self) -> impl Iterator<Item = &'s str> + '_ {
+        self.frames.iter().flat_map(|f| f.keys().copied())
+    }
+}
+
+impl Parser {
+    /// Replace every `(var ...)` node in a page with the value of the variable
+    /// it names
+    ///
+    /// Variables are looked up in the innermost enclosing node that defines
+    /// them using a `[let name value]` attribute, falling back to the page
+    /// attributes and finally the site-wide [`Config`]
+    pub(crate) fn resolve_variables<'s>(
+        &self,
+        page: &mut Page<'s>,
+        config: &'s Config,
+    ) -> Result<(), ParseError> {
+        let globals = config
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        let mut scopes = Scopes {
+            frames: vec![globals],
+        };
+        scopes.frames.push(self.bindings(&page.attributes)?);
+        scopes.frames.push(self.bindings(&page.doc.attributes)?);
+
+        self.substitute_variables(&mut page.doc.nodes, &mut scopes)
+    }
+
+    /// Collect all variables defined in a list of attributes
+    fn bindings<'s>(
+        &self,
+        attributes: &[Attribute<'s>],
+    ) -> Result<HashMap<&'s str, &'s str>, ParseError> {
+        attributes
+            .iter()
+            .filter(|a| a.attribute_name.name == "let")
+            .map(|a| {
+                self.parse_binding(a)
+                    .map(|(name, value)| (name.name, value.text))
+            })
+            .collect()
+    }
+
+    fn substitute_variables<'s>(
+        &self,
+        nodes: &mut [Node<'s>],
+        scopes: &mut Scopes<'s>,
+    ) -> Result<(), ParseError> {
+        for node in nodes {
+            if let Node::Var { name, span, .. } = node {
+                let (name, span) = (*name, *span);
+
+                let Some(text) = scopes.lookup(name.name) else {
+                    let mut error = self.make_error(
+                        name.span,
+                        ParseErrorType::UndefinedVariable {
+                            name: name.name.to_owned(),
+                        },
+                    );
+                    if let Some(suggestion) = did_you_mean(name.name, scopes.names()) {
+                        error = error.add_context(format!("did you mean '{suggestion}'?"));
+                    }
+
+                    return Err(error);
+                };
+
+                *node = Node::Text {
+                    inner: Text { text, span },
+                };
+
+                continue;
+            }
+
+            scopes.frames.push(self.bindings(node.attributes())?);
+
+            if let Some(children) = node.children_mut() {
+                self.substitute_variables(children, scopes)?;
+            }
+
+            scopes.frames.pop();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! Whitespace normalization
+
+use crate::parse::*;
+
+impl Parser {
+    /// Normalize the whitespace in all inline content
+    ///
+    /// Every run of whitespace becomes a single space, and whitespace at the
+    /// start and end of a node is removed entirely. Whitespace between two
+    /// inline nodes, like in `text (b bold) text`, is kept as a single space.
+    ///
+    /// The content of `codeblock` and `html` nodes is left untouched.
+    pub(crate) fn normalize_whitespace<'s>(&'s self, page: &mut Page<'s>) {
+        self.normalize_nodes(&mut page.doc.nodes, true);
+    }
+
+    fn normalize_nodes<'s>(&'s self, nodes: &mut Vec<Node<'s>>, block_context: bool) {
+        if !block_context {
+            *nodes = self.normalize_inline(std::mem::take(nodes));
+        }
+
+        for node in nodes {
+            let has_block_content = node.has_block_content();
+
+            if let Some(children) = node.children_mut() {
+                self.normalize_nodes(children, has_block_content);
+            }
+        }
+    }
+
+    fn normalize_inline<'s>(&'s self, nodes: Vec<Node<'s>>) -> Vec<Node<'s>> {
+        let mut normalized: Vec<Node> = Vec::with_capacity(nodes.len());
+
+        // Whether there was any whitespace since the last piece of content
+        let mut pending_space = false;
+        let mut previous_end = None;
+
+        for node in nodes {
+            // The whitespace between nodes is not part of the AST, so check the
+            // source for any in between
+            if let Some(end) = previous_end {
+                let gap = Span::new(end, node.span().start);
+                pending_space |= self.check_source(gap, |gap| {
+                    gap.starts_with(|c: char| c.is_ascii_whitespace())
+                });
+            }
+            previous_end = Some(node.span().end);
+
+            if let Node::Text { inner } = &node {
+                pending_space |= inner.text.starts_with(|c: char| c.is_ascii_whitespace());
+            }
+
+            // No space is needed at the start, or next to block content
+            let needs_space = pending_space
+                && !node.is_block()
+                && normalized.last().is_some_and(|n| !n.is_block());
+
+            match node {
+                Node::Text { inner } => {
+                    let collapsed = inner.text.split_ascii_whitespace().collect::<Vec<_>>();
+
+                    if collapsed.is_empty() {
+                        continue;
+                    }
+
+                    let mut text = collapsed.join(" ");
+                    if needs_space {
+                        text.insert(0, ' ');
+                    }
+
+                    let text = if text == inner.text {
+                        inner.text
+                    } else {
+                        self.alloc_str(&text)
+                    };
+
+                    normalized.push(Node::Text {
+                        inner: Text {
+                            text,
+                            span: inner.span,
+                        },
+                    });
+
+                    pending_space = inner.text.ends_with(|c: char| c.is_ascii_whitespace());
+                }
+                _ => {
+                    if needs_space {
+                        let end = normalized.last().unwrap().span().end;
+
+                        normalized.push(Node::Text {
+                            inner: Text {
+                                text: " ",
+                                span: Span::new(end, end),
+                            },
+                        });
+                    }
+
+                    normalized.push(node);
+                    pending_space = false;
+                }
+            }
+        }
+
+        normalized
+    }
+}
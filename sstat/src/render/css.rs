@@ -0,0 +1,68 @@
+//! The default stylesheet
+
+use std::fmt::Write;
+
+use crate::{Classes, Config};
+
+/// The stylesheet, using the default class prefix
+const STYLESHEET: &str = include_str!("style.css");
+
+/// The names of all theme properties that can be overridden, each of which is
+/// the custom property `--sstat-<name>` with underscores replaced by dashes
+pub(crate) const THEME_PROPERTIES: &[&str] = &[
+    "text",
+    "background",
+    "muted",
+    "accent",
+    "border",
+    "surface",
+    "highlight",
+    "deleted",
+    "inserted",
+    "note",
+    "tip",
+    "important",
+    "warning",
+    "caution",
+    "font_body",
+    "font_heading",
+    "font_mono",
+];
+
+/// The stylesheet for every class the renderer generates, using the
+/// configured class prefix and theme
+///
+/// If generated classes are turned off, the default prefix is used.
+pub(crate) fn stylesheet(config: &Config) -> String {
+    let mut css = match &config.classes {
+        Classes::Prefixed(prefix) => STYLESHEET.replace(
+            &format!(".{}", Classes::DEFAULT_PREFIX),
+            &format!(".{prefix}"),
+        ),
+        Classes::None => STYLESHEET.to_owned(),
+    };
+
+    if !config.theme.is_empty() {
+        css.push_str("\n/* Theme overrides */\n\n:root {\n");
+        write_properties(&mut css, &config.theme, 1);
+        css.push_str("}\n");
+    }
+
+    if !config.dark_theme.is_empty() {
+        css.push_str("\n/* Dark theme overrides */\n\n");
+        css.push_str("@media (prefers-color-scheme: dark) {\n\t:root {\n");
+        write_properties(&mut css, &config.dark_theme, 2);
+        css.push_str("\t}\n}\n");
+    }
+
+    css
+}
+
+fn write_properties(css: &mut String, properties: &[(String, String)], indent: usize) {
+    for (name, value) in properties {
+        let indent = "\t".repeat(indent);
+        let name = name.replace('_', "-");
+
+        writeln!(css, "{indent}--sstat-{name}: {value};").unwrap();
+    }
+}
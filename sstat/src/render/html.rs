@@ -0,0 +1,1211 @@
+//! HTML output
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use serde_json::json;
+
+use super::mathml::write_mathml;
+use super::stream::{Sink, Stream};
+use super::{
+    dispatch, Admonition, Context, Inline, ListItem, ListKind, Media, Renderer, TablePart,
+};
+use crate::bibliography::{Reference, Segment};
+use crate::parse::*;
+use crate::pass::{
+    Align, Footnote, Kind, ListofEntry, Metadata, Piece, Placeholder, Template, TocEntry,
+};
+use crate::{Classes, Config, Document};
+
+/// The deepest heading level supported by HTML
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// Renders a page to HTML, streaming it into a [`fmt::Write`](std::fmt::Write)
+pub(crate) struct HtmlRenderer<'p, W> {
+    /// The parser of the page, used to find where in the source nodes are
+    parser: &'p Parser,
+    output: Stream<W>,
+    context: Context,
+    /// The nesting depth of the element currently being rendered
+    indent: usize,
+    /// The prefix of generated classes, or `None` if no classes are generated
+    class_prefix: Option<String>,
+    /// Whether titles end in a link to themselves
+    heading_anchors: bool,
+    /// Whether elements are annotated with their position in the source
+    debug: bool,
+    /// The span of the node currently being rendered, if any
+    span: Option<Span>,
+    /// The ranges of the output generated from each node and the span of
+    /// that node, if a source map is generated
+    mappings: Option<Vec<(Range<usize>, Span)>>,
+}
+
+impl<'p, W: Write> HtmlRenderer<'p, W> {
+    /// Render a page, as a complete HTML document in its layout unless the
+    /// [`Config`] asks for a fragment, returning the source map of the page
+    /// if the [`Config`] asks for one
+    pub(crate) fn render(
+        page: &Page,
+        parser: &'p Parser,
+        config: &Config,
+        output: W,
+    ) -> Result<Option<String>, std::fmt::Error> {
+        let mut renderer = Self::new(parser, output);
+        renderer.class_prefix = match &config.classes {
+            Classes::Prefixed(prefix) => Some(prefix.clone()),
+            Classes::None => None,
+        };
+        renderer.heading_anchors = page.metadata.heading_anchors;
+        renderer.debug = config.debug;
+        renderer.mappings = config.source_map.then(Vec::new);
+
+        match config.document {
+            Document::Full => renderer.render_document(page, config),
+            Document::Fragment => renderer.render_doc(&page.doc),
+        }
+        if !renderer.output.ends_with_newline() {
+            renderer.output.push('\n');
+        }
+
+        let source_map = renderer.mappings.take().map(|mut mappings| {
+            // Outer elements come before the elements inside them
+            mappings.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+            renderer.source_map(&mappings)
+        });
+
+        renderer.output.finish()?;
+
+        Ok(source_map)
+    }
+
+    fn new(parser: &'p Parser, output: W) -> Self {
+        Self {
+            parser,
+            output: Stream::new(output),
+            context: Context::default(),
+            indent: 0,
+            class_prefix: None,
+            heading_anchors: false,
+            debug: false,
+            span: None,
+            mappings: None,
+        }
+    }
+
+    /// A JSON source map from ranges of bytes in the output to the source
+    /// code they were generated from
+    fn source_map(&self, mappings: &[(Range<usize>, Span)]) -> String {
+        let mappings: Vec<_> = mappings
+            .iter()
+            .map(|(range, span)| {
+                let location = self.parser.location(*span);
+
+                json!({
+                    "html": [range.start, range.end],
+                    "file": location.file_path.display().to_string(),
+                    "span": [location.span.start, location.span.end],
+                    "start": { "line": location.start.line, "column": location.start.column },
+                    "end": { "line": location.end.line, "column": location.end.column },
+                })
+            })
+            .collect();
+
+        json!({ "version": 1, "mappings": mappings }).to_string()
+    }
+
+    /// Render a page into the templates of its layout, each inserted into
+    /// the body of the next
+    fn render_document(&mut self, page: &Page, config: &Config) {
+        let layout = &page.layout;
+
+        if layout.builtin {
+            self.open_document(&page.metadata);
+        }
+        self.render_layout(&layout.templates, page, config);
+        if layout.builtin {
+            self.close_document();
+        }
+    }
+
+    /// Render a page into the last of some templates, which the others are
+    /// inserted into in turn
+    fn render_layout(&mut self, templates: &[Template], page: &Page, config: &Config) {
+        match templates.split_last() {
+            Some((template, inner)) => self.fill_template(template, inner, page, config),
+            None => self.render_doc(&page.doc),
+        }
+    }
+
+    /// Replace every placeholder in a layout template, rendering the
+    /// templates inserted into it as its body
+    fn fill_template(
+        &mut self,
+        template: &Template,
+        inner: &[Template],
+        page: &Page,
+        config: &Config,
+    ) {
+        let metadata = &page.metadata;
+        let mut placed = false;
+
+        for piece in template {
+            let placeholder = match piece {
+                Piece::Text(text) => {
+                    self.output.push_str(text);
+                    continue;
+                }
+                Piece::Placeholder(placeholder) => placeholder,
+            };
+
+            // Generated elements are indented like the placeholder, but the
+            // body is not, as it may contain preformatted text
+            let indent = match self.output.blank_line() {
+                Some(line) => format!("\n{line}"),
+                None => "\n".to_owned(),
+            };
+
+            let field = match placeholder {
+                Placeholder::Body => {
+                    // Only the first copy of the body is mapped to the source
+                    let mappings = match placed {
+                        true => self.mappings.take(),
+                        false => None,
+                    };
+                    let indent = std::mem::take(&mut self.indent);
+
+                    self.render_layout(inner, page, config);
+
+                    self.indent = indent;
+                    if mappings.is_some() {
+                        self.mappings = mappings;
+                    }
+                    placed = true;
+                    continue;
+                }
+                Placeholder::Head => {
+                    let head = self.render_separately(|r| r.write_head(metadata));
+                    self.output
+                        .push_str(&head.trim_start().replace('\n', &indent));
+                    continue;
+                }
+                Placeholder::Toc => {
+                    let toc = self.render_separately(|r| r.render_toc(&metadata.toc));
+                    self.output.push_str(&toc.replace('\n', &indent));
+                    continue;
+                }
+                Placeholder::Nav => {
+                    let nav = self.render_separately(|r| r.render_nav(&config.nav));
+                    self.output.push_str(&nav.replace('\n', &indent));
+                    continue;
+                }
+                Placeholder::Title => &metadata.title,
+                Placeholder::Author => &metadata.author,
+                Placeholder::Description => &metadata.description,
+                Placeholder::Lang => &metadata.language,
+            };
+            escape_into(&mut self.output, field.as_deref().unwrap_or_default());
+        }
+    }
+
+    /// Render something on its own at the top level, returning it instead of
+    /// adding it to the output
+    fn render_separately(&self, render: impl FnOnce(&mut HtmlRenderer<'p, String>)) -> String {
+        let mut renderer = HtmlRenderer::new(self.parser, String::new());
+        renderer.class_prefix = self.class_prefix.clone();
+        renderer.heading_anchors = self.heading_anchors;
+        renderer.debug = self.debug;
+
+        render(&mut renderer);
+
+        renderer
+            .output
+            .finish()
+            .expect("writing to a string never fails")
+    }
+
+    /// Write everything before the `<main>` element of a complete document,
+    /// including a `<head>` describing the page
+    fn open_document(&mut self, metadata: &Metadata) {
+        self.output.push_str("<!DOCTYPE html>\n<html");
+        if let Some(language) = &metadata.language {
+            self.write_attribute("lang", language);
+        }
+        self.output.push_str(">\n<head>");
+        self.indent += 1;
+        self.write_head(metadata);
+        self.indent -= 1;
+        self.output.push_str("\n</head>\n<body>");
+        self.indent += 1;
+        self.newline();
+    }
+
+    /// Write the elements of the `<head>` of a page, each on its own line
+    fn write_head(&mut self, metadata: &Metadata) {
+        self.newline();
+        self.output.push_str("<meta charset=\"utf-8\">");
+        self.newline();
+        self.output
+            .push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">");
+
+        if let Some(title) = &metadata.title {
+            self.newline();
+            self.output.push_str("<title>");
+            escape_into(&mut self.output, title);
+            self.close_tag("title");
+        }
+
+        let named = [
+            ("author", metadata.author.as_deref()),
+            ("description", metadata.description.as_deref()),
+        ];
+        self.write_meta("name", &named);
+
+        for stylesheet in &metadata.stylesheets {
+            self.newline();
+            self.output.push_str("<link rel=\"stylesheet\"");
+            self.write_attribute("href", stylesheet);
+            self.output.push('>');
+        }
+
+        if let Some(title) = &metadata.title {
+            self.write_social_metadata(title, metadata);
+        }
+    }
+
+    /// Write Open Graph and Twitter card `<meta>` elements, and a schema.org
+    /// `Article` as JSON-LD, so that links to the page get a preview
+    fn write_social_metadata(&mut self, title: &str, metadata: &Metadata) {
+        let description = metadata.description.as_deref();
+        let cover = metadata.cover.as_deref();
+
+        self.write_meta(
+            "property",
+            &[
+                ("og:type", Some("article")),
+                ("og:title", Some(title)),
+                ("og:description", description),
+                ("og:image", cover),
+                ("article:published_time", metadata.date.as_deref()),
+            ],
+        );
+
+        let card = match cover {
+            Some(_) => "summary_large_image",
+            None => "summary",
+        };
+        self.write_meta(
+            "name",
+            &[
+                ("twitter:card", Some(card)),
+                ("twitter:title", Some(title)),
+                ("twitter:description", description),
+                ("twitter:image", cover),
+            ],
+        );
+
+        let mut article = json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            "headline": title,
+        });
+        if let Some(description) = description {
+            article["description"] = json!(description);
+        }
+        if let Some(author) = &metadata.author {
+            article["author"] = json!({ "@type": "Person", "name": author });
+        }
+        if let Some(date) = &metadata.date {
+            article["datePublished"] = json!(date);
+        }
+        if let Some(cover) = cover {
+            article["image"] = json!(cover);
+        }
+
+        // `<` only appears in strings, where escaping it keeps `</script>`
+        // from ending the element early
+        let article = article.to_string().replace('<', "\\u003c");
+
+        self.newline();
+        write!(
+            self.output,
+            "<script type=\"application/ld+json\">{article}</script>"
+        )
+        .unwrap();
+    }
+
+    /// Write a `<meta>` element for every piece of metadata that is known, eg.
+    /// `<meta name="author" content="...">` for the attribute `name`
+    fn write_meta(&mut self, attribute: &str, metadata: &[(&str, Option<&str>)]) {
+        for (name, content) in metadata {
+            if let Some(content) = content {
+                self.newline();
+                self.output.push_str("<meta");
+                self.write_attribute(attribute, name);
+                self.write_attribute("content", content);
+                self.output.push('>');
+            }
+        }
+    }
+
+    /// Write everything after the `<main>` element of a complete document
+    fn close_document(&mut self) {
+        self.indent -= 1;
+        self.output.push_str("\n</body>\n</html>");
+    }
+
+    /// Render the `<main>` element of a page
+    fn render_doc(&mut self, doc: &DocNode) {
+        // The span of the doc node includes whatever comes before it
+        let span = Span::new(doc.lparen.span.start, doc.span.end);
+
+        self.trace(span, |r| {
+            r.open_tag("main", "main", &doc.attributes);
+            r.render_children(&doc.nodes);
+            r.close_tag("main");
+        });
+    }
+
+    /// Render something generated from the source code in a span, recording
+    /// where its output came from
+    fn trace(&mut self, span: Span, render: impl FnOnce(&mut Self)) {
+        let outer = self.span.replace(span);
+        let start = self.output.len();
+
+        render(self);
+
+        self.span = outer;
+        let end = self.output.len();
+        if let Some(mappings) = self.mappings.as_mut().filter(|_| start < end) {
+            mappings.push((start..end, span));
+        }
+    }
+
+    /// Render an element along with its children
+    fn render_element(&mut self, tag: &str, class: &str, attributes: &[Attribute], inner: &[Node]) {
+        self.open_tag(tag, class, attributes);
+        self.render_children(inner);
+        self.close_tag(tag);
+    }
+
+    /// Render an element, passing on some of its attributes as HTML
+    /// attributes of the same name
+    fn render_element_with(
+        &mut self,
+        tag: &str,
+        class: &str,
+        attributes: &[Attribute],
+        passed: &[&str],
+        inner: &[Node],
+    ) {
+        let extra: Vec<_> = passed
+            .iter()
+            .filter_map(|name| Some((*name, attribute(attributes, name)?)))
+            .collect();
+
+        self.open_tag_with(tag, class, attributes, &extra);
+        self.render_children(inner);
+        self.close_tag(tag);
+    }
+
+    /// Write the opening tag of a `figure` element wrapping a numbered node,
+    /// giving it a generated id unless the node has one of its own
+    ///
+    /// Only `figure_attributes` are applied to the `figure` element itself
+    fn open_figure(
+        &mut self,
+        class: &str,
+        attributes: &[Attribute],
+        figure_attributes: &[Attribute],
+        kind: Kind,
+        number: usize,
+    ) {
+        let id = kind.default_id(self.id_prefix(), number);
+        let extra: &[(&str, &str)] = match attribute(attributes, "id") {
+            Some(_) => &[],
+            None => &[("id", &id)],
+        };
+
+        self.open_tag_with("figure", class, figure_attributes, extra);
+    }
+
+    fn render_source(&mut self, src: &str, mime: Option<&str>) {
+        self.output.push_str("<source");
+        self.write_class("source");
+        self.write_source_position();
+        self.write_attribute("src", src);
+        if let Some(mime) = mime {
+            self.write_attribute("type", mime);
+        }
+        self.output.push('>');
+    }
+
+    /// Render the caption of a numbered node, prefixed by its label
+    fn render_caption(
+        &mut self,
+        tag: &str,
+        class: &str,
+        attributes: &[Attribute],
+        kind: Kind,
+        number: usize,
+    ) {
+        self.open_tag(tag, class, &[]);
+        self.open_tag("span", "caption-label", &[]);
+        write!(self.output, "{}:", kind.label(number)).unwrap();
+        self.close_tag("span");
+        self.output.push(' ');
+        escape_into(
+            &mut self.output,
+            attribute(attributes, "caption").unwrap_or_default(),
+        );
+        self.close_tag(tag);
+    }
+
+    /// Render a single entry of a `listof` node
+    fn render_listof_entry(&mut self, entry: &ListofEntry) {
+        self.open_tag("li", "listof-entry", &[]);
+        self.open_tag_with("a", "ref", &[], &[("href", &format!("#{}", entry.id))]);
+        escape_into(&mut self.output, &entry.label);
+        self.output.push_str("</a>: ");
+        escape_into(&mut self.output, &entry.caption);
+        self.output.push_str("</li>");
+    }
+
+    /// Render a single entry of a bibliography
+    fn render_reference(&mut self, reference: &Reference) {
+        self.output.push_str("<li");
+        let id = reference_id(self.id_prefix(), &reference.entry.key);
+        self.write_attribute("id", &id);
+        self.write_class("reference");
+        self.write_source_position();
+        self.output.push('>');
+
+        if let Some(label) = &reference.label {
+            self.open_tag("span", "reference-label", &[]);
+            escape_into(&mut self.output, label);
+            self.output.push_str("</span> ");
+        }
+
+        for segment in &reference.segments {
+            match segment {
+                Segment::Text(text) => escape_into(&mut self.output, text),
+                Segment::Title(title) => {
+                    self.output.push_str("<cite>");
+                    escape_into(&mut self.output, title);
+                    self.output.push_str("</cite>");
+                }
+                Segment::Link(url) => {
+                    self.output.push_str("<a");
+                    self.write_attribute("href", url);
+                    self.output.push('>');
+                    escape_into(&mut self.output, url);
+                    self.output.push_str("</a>");
+                }
+            }
+        }
+
+        self.output.push_str("</li>");
+    }
+
+    /// Render a single footnote of a `fnotes` node, starting with its number
+    fn render_footnote(&mut self, footnote: &Footnote) {
+        let id = footnote_id(self.id_prefix(), footnote.number);
+
+        self.open_tag_with("div", "footnote-definition", &[], &[("id", &id)]);
+        self.open_tag("sup", "footnote-definition-id", &[]);
+        write!(self.output, "{}", footnote.number).unwrap();
+        self.close_tag("sup");
+        self.output.push(' ');
+        self.render_children(&footnote.inner);
+        self.close_tag("div");
+    }
+
+    /// Render a `title` or `header` as an `h1-6` after how deeply it is
+    /// nested, followed by an anchor linking to it if enabled
+    fn render_heading(
+        &mut self,
+        class: &str,
+        attributes: &[Attribute],
+        inner: &[Node],
+        id: Option<&str>,
+    ) {
+        let level = self.context.heading_level().min(MAX_HEADING_LEVEL);
+        let tag = format!("h{level}");
+
+        let generated: &[_] = match id {
+            Some(id) if attribute(attributes, "id").is_none() => &[("id", id)],
+            _ => &[],
+        };
+        self.open_tag_with(&tag, class, attributes, generated);
+        self.render_children(inner);
+
+        if let Some(id) = id.filter(|_| self.heading_anchors) {
+            let href = format!("#{id}");
+
+            self.output.push(' ');
+            self.open_tag_with("a", "heading-anchor", &[], &[("href", &href)]);
+            self.output.push('¶');
+            self.close_tag("a");
+        }
+        self.close_tag(&tag);
+    }
+
+    /// Render a table of contents, nesting the titles of sections inside the
+    /// titles of the sections containing them
+    fn render_toc(&mut self, toc: &[TocEntry]) {
+        if toc.is_empty() {
+            return;
+        }
+
+        self.open_tag("nav", "toc", &[]);
+        self.indent += 1;
+        self.newline();
+        self.render_toc_list(toc);
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("nav");
+    }
+
+    fn render_toc_list(&mut self, entries: &[TocEntry]) {
+        self.open_tag("ol", "toc-list", &[]);
+        self.indent += 1;
+
+        let mut rest = entries;
+        while let Some((entry, after)) = rest.split_first() {
+            // Every following entry in a deeper section is nested in this one
+            let len = after
+                .iter()
+                .position(|e| e.depth <= entry.depth)
+                .unwrap_or(after.len());
+            let (nested, after) = after.split_at(len);
+
+            self.newline();
+            self.open_tag("li", "toc-entry", &[]);
+            match &entry.id {
+                Some(id) => {
+                    let href = format!("#{id}");
+                    self.open_tag_with("a", "toc-link", &[], &[("href", &href)]);
+                    escape_into(&mut self.output, &entry.title);
+                    self.close_tag("a");
+                }
+                None => escape_into(&mut self.output, &entry.title),
+            }
+            if !nested.is_empty() {
+                self.indent += 1;
+                self.newline();
+                self.render_toc_list(nested);
+                self.indent -= 1;
+                self.newline();
+            }
+            self.close_tag("li");
+
+            rest = after;
+        }
+
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("ol");
+    }
+
+    /// Render the site navigation, as labels and the URLs they link to
+    fn render_nav(&mut self, nav: &[(String, String)]) {
+        if nav.is_empty() {
+            return;
+        }
+
+        self.open_tag("nav", "nav", &[]);
+        self.indent += 1;
+        self.newline();
+        self.open_tag("ul", "nav-list", &[]);
+        self.indent += 1;
+        for (label, url) in nav {
+            self.newline();
+            self.open_tag("li", "nav-entry", &[]);
+            self.open_tag_with("a", "nav-link", &[], &[("href", url)]);
+            escape_into(&mut self.output, label);
+            self.close_tag("a");
+            self.close_tag("li");
+        }
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("ul");
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("nav");
+    }
+
+    /// Write the opening tag of an element, including its `id`, `class` and
+    /// `style` attributes
+    fn open_tag(&mut self, tag: &str, class: &str, attributes: &[Attribute]) {
+        self.open_tag_with(tag, class, attributes, &[]);
+    }
+
+    /// Write the opening tag of an element, followed by some additional HTML
+    /// attributes
+    fn open_tag_with(
+        &mut self,
+        tag: &str,
+        class: &str,
+        attributes: &[Attribute],
+        extra: &[(&str, &str)],
+    ) {
+        write!(self.output, "<{tag}").unwrap();
+
+        if let Some(id) = attribute(attributes, "id") {
+            self.write_attribute("id", id);
+        }
+
+        let classes: Vec<_> = self
+            .class_name(class)
+            .into_iter()
+            .chain(attributes_named(attributes, "class").map(str::to_owned))
+            .collect();
+        if !classes.is_empty() {
+            self.write_attribute("class", &classes.join(" "));
+        }
+
+        // A generated style comes first, so that the user's style overrides it
+        let styles: Vec<_> = extra
+            .iter()
+            .filter(|(name, _)| *name == "style")
+            .map(|(_, value)| *value)
+            .chain(attribute(attributes, "style"))
+            .collect();
+        if !styles.is_empty() {
+            self.write_attribute("style", &styles.join("; "));
+        }
+
+        for (name, value) in extra.iter().filter(|(name, _)| *name != "style") {
+            // Boolean attributes are written without a value
+            match value.is_empty() {
+                true => write!(self.output, " {name}").unwrap(),
+                false => self.write_attribute(name, value),
+            }
+        }
+        self.write_source_position();
+
+        self.output.push('>');
+    }
+
+    /// The generated class of an element, eg. `__sstat-paragraph` for a
+    /// paragraph
+    fn class_name(&self, class: &str) -> Option<String> {
+        self.class_prefix
+            .as_ref()
+            .map(|prefix| format!("{prefix}{class}"))
+    }
+
+    /// The prefix of generated ids, the same as that of generated classes,
+    /// or empty if no classes are generated
+    fn id_prefix(&self) -> &str {
+        self.class_prefix.as_deref().unwrap_or_default()
+    }
+
+    /// Write the generated `class` attribute of an element that has no
+    /// attributes of its own
+    fn write_class(&mut self, class: &str) {
+        if let Some(class) = self.class_name(class) {
+            self.write_attribute("class", &class);
+        }
+    }
+
+    /// Write the position in the source of the node currently being
+    /// rendered, eg. `data-sstat-src="page.sstat:12:5"`, in debug mode
+    fn write_source_position(&mut self) {
+        if let Some(span) = self.span.filter(|_| self.debug) {
+            let location = self.parser.location(span);
+            self.write_attribute("data-sstat-src", &location.to_string());
+        }
+    }
+
+    fn close_tag(&mut self, tag: &str) {
+        write!(self.output, "</{tag}>").unwrap();
+    }
+
+    fn write_attribute(&mut self, name: &str, value: &str) {
+        write!(self.output, " {name}=\"").unwrap();
+        escape_into(&mut self.output, value);
+        self.output.push('"');
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+
+        for _ in 0..self.indent {
+            self.output.push('\t');
+        }
+    }
+}
+
+impl<'p> HtmlRenderer<'p, String> {
+    /// Render a single node without generated classes, for formats that fall
+    /// back to HTML for nodes they cannot represent
+    pub(crate) fn render_fallback(parser: &'p Parser, context: Context, node: &Node) -> String {
+        let mut renderer = Self::new(parser, String::new());
+        renderer.context = context;
+
+        renderer.render_node(node);
+
+        renderer
+            .output
+            .finish()
+            .expect("writing to a string never fails")
+    }
+}
+
+impl<W: Write> Renderer for HtmlRenderer<'_, W> {
+    fn context(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    fn render_node(&mut self, node: &Node) {
+        self.trace(node.span(), |r| dispatch(r, node));
+    }
+
+    /// Render the children of an element, putting every child on its own line
+    /// if any of them is block content
+    fn render_children(&mut self, nodes: &[Node]) {
+        if !nodes.iter().any(|n| n.is_block()) {
+            for node in nodes {
+                self.render_node(node);
+            }
+
+            return;
+        }
+
+        self.indent += 1;
+        for node in nodes {
+            self.newline();
+            self.render_node(node);
+        }
+        self.indent -= 1;
+
+        self.newline();
+    }
+
+    fn text(&mut self, text: &str) {
+        escape_into(&mut self.output, text);
+    }
+
+    fn section(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        self.render_element("section", "section", attributes, inner);
+    }
+
+    fn title(&mut self, attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+        self.render_heading("title", attributes, inner, id);
+    }
+
+    fn header(&mut self, attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+        self.render_heading("header", attributes, inner, id);
+    }
+
+    fn paragraph(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        self.render_element("p", "paragraph", attributes, inner);
+    }
+
+    fn inline(&mut self, kind: Inline, attributes: &[Attribute], inner: &[Node]) {
+        let (tag, class, passed): (_, _, &[_]) = match kind {
+            Inline::Bold => ("b", "bold", &[]),
+            Inline::Italic => ("i", "italic", &[]),
+            Inline::Monospace => ("code", "monospace", &[]),
+            Inline::Underline => ("u", "underline", &[]),
+            Inline::Strikethrough => ("s", "strikethrough", &[]),
+            Inline::Mark => ("mark", "mark", &[]),
+            Inline::Subscript => ("sub", "subscript", &[]),
+            Inline::Superscript => ("sup", "superscript", &[]),
+            Inline::Keyboard => ("kbd", "keyboard", &[]),
+            Inline::Abbreviation => ("abbr", "abbreviation", &["title"]),
+            Inline::Quote => ("q", "quote", &["cite"]),
+            Inline::Time => ("time", "time", &["datetime"]),
+            Inline::Small => ("small", "small", &[]),
+            Inline::Deleted => ("del", "deleted", &["cite", "datetime"]),
+            Inline::Inserted => ("ins", "inserted", &["cite", "datetime"]),
+            Inline::Span => ("span", "span", &[]),
+        };
+
+        self.render_element_with(tag, class, attributes, passed, inner);
+    }
+
+    /// Render an admonition, as a collapsible `details` element if it has a
+    /// `[collapsible]` attribute
+    fn admonition(&mut self, kind: Admonition, attributes: &[Attribute], inner: &[Node]) {
+        let name = kind.name();
+        let class = format!("admonition-{name}");
+        let (tag, label_tag, extra) = match flag(attributes, "collapsible") {
+            true => ("details", "summary", open_flag(attributes)),
+            false => ("div", "div", &[] as &[_]),
+        };
+
+        self.open_tag_with(tag, &class, attributes, extra);
+        self.indent += 1;
+        self.newline();
+        self.open_tag(label_tag, "admonition-label", &[]);
+        self.output.push_str(name);
+        self.close_tag(label_tag);
+        self.newline();
+        self.render_element("div", "admonition-content", &[], inner);
+        self.indent -= 1;
+        self.newline();
+        self.close_tag(tag);
+    }
+
+    fn details(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        self.open_tag_with("details", "details", attributes, open_flag(attributes));
+        self.indent += 1;
+        if let Some(summary) = attribute(attributes, "summary") {
+            self.newline();
+            self.open_tag("summary", "details-summary", &[]);
+            escape_into(&mut self.output, summary);
+            self.close_tag("summary");
+        }
+        self.newline();
+        self.render_element("div", "details-content", &[], inner);
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("details");
+    }
+
+    fn division(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        self.render_element("div", "div", attributes, inner);
+    }
+
+    fn list(&mut self, kind: ListKind, attributes: &[Attribute], inner: &[Node]) {
+        let (tag, class) = match kind {
+            ListKind::Unordered => ("ul", "unordered-list"),
+            ListKind::Ordered => ("ol", "ordered-list"),
+            ListKind::Description => ("dl", "description-list"),
+        };
+
+        self.render_element(tag, class, attributes, inner);
+    }
+
+    fn list_item(&mut self, kind: ListItem, attributes: &[Attribute], inner: &[Node]) {
+        let (tag, class) = match kind {
+            ListItem::Item => ("li", "list-item"),
+            ListItem::Term => ("dt", "description-term"),
+            ListItem::Description => ("dd", "description-description"),
+        };
+
+        self.render_element(tag, class, attributes, inner);
+    }
+
+    fn image(&mut self, attributes: &[Attribute], number: usize) {
+        self.open_figure("figure", attributes, attributes, Kind::Figure, number);
+        self.indent += 1;
+        self.newline();
+        self.output.push_str("<img");
+        self.write_class("image");
+        self.write_source_position();
+        self.write_attribute("src", attribute(attributes, "src").unwrap_or_default());
+        self.write_attribute("alt", attribute(attributes, "alt").unwrap_or_default());
+        self.output.push('>');
+        self.newline();
+        self.render_caption(
+            "figcaption",
+            "figure-caption",
+            attributes,
+            Kind::Figure,
+            number,
+        );
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("figure");
+    }
+
+    /// Render a video or audio clip in a figure, with a `source` element for
+    /// its own `[src ...]` followed by one for each of its `source` children
+    fn media(&mut self, kind: Media, attributes: &[Attribute], inner: &[Node], mime: Option<&str>) {
+        let name = kind.name();
+        self.open_tag("figure", &format!("{name}-figure"), attributes);
+        self.indent += 1;
+        self.newline();
+        write!(self.output, "<{name}").unwrap();
+        self.write_class(name);
+        self.write_source_position();
+        self.output.push_str(" controls");
+        if let Some(poster) = attribute(attributes, "poster").filter(|_| kind == Media::Video) {
+            self.write_attribute("poster", poster);
+        }
+        self.output.push('>');
+
+        self.indent += 1;
+        if let Some(src) = attribute(attributes, "src") {
+            self.newline();
+            self.render_source(src, attribute(attributes, "type").or(mime));
+        }
+        for node in inner {
+            if let Node::Source {
+                attributes, mime, ..
+            } = node
+            {
+                self.newline();
+                self.trace(node.span(), |r| {
+                    r.render_source(
+                        attribute(attributes, "src").unwrap_or_default(),
+                        attribute(attributes, "type").or(*mime),
+                    );
+                });
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.close_tag(name);
+
+        if let Some(caption) = attribute(attributes, "caption") {
+            self.newline();
+            self.open_tag("figcaption", &format!("{name}-caption"), &[]);
+            escape_into(&mut self.output, caption);
+            self.close_tag("figcaption");
+        }
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("figure");
+    }
+
+    fn table(&mut self, attributes: &[Attribute], inner: &[Node], number: Option<usize>) {
+        self.open_tag("table", "table", attributes);
+        self.indent += 1;
+        if let Some(number) = number {
+            self.newline();
+            self.render_caption("caption", "table-caption", attributes, Kind::Table, number);
+        }
+        for node in inner {
+            self.newline();
+            self.render_node(node);
+        }
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("table");
+    }
+
+    fn table_part(&mut self, part: TablePart, attributes: &[Attribute], inner: &[Node]) {
+        let tag = match part {
+            TablePart::Head => "thead",
+            TablePart::Foot => "tfoot",
+            TablePart::Body => "tbody",
+        };
+
+        self.render_element(tag, table_class(part), attributes, inner);
+    }
+
+    fn table_row(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        let class = format!("{}-row", table_class(self.context.table_part()));
+        self.render_element("tr", &class, attributes, inner);
+    }
+
+    fn table_cell(&mut self, attributes: &[Attribute], inner: &[Node], align: Option<Align>) {
+        // Cells in the header and footer are header cells
+        let part = self.context.table_part();
+        let tag = match part {
+            TablePart::Body => "td",
+            TablePart::Head | TablePart::Foot => "th",
+        };
+        let class = format!("{}-cell", table_class(part));
+
+        let style = align.map(|a| format!("text-align: {}", a.css()));
+        let mut extra = vec![];
+        for name in ["colspan", "rowspan"] {
+            if let Some(value) = attribute(attributes, name) {
+                extra.push((name, value));
+            }
+        }
+        if let Some(style) = &style {
+            extra.push(("style", style.as_str()));
+        }
+
+        self.open_tag_with(tag, &class, attributes, &extra);
+        self.render_children(inner);
+        self.close_tag(tag);
+    }
+
+    fn codeblock(&mut self, attributes: &[Attribute], code: &str, number: Option<usize>) {
+        if let Some(number) = number {
+            self.open_figure("listing", attributes, &[], Kind::Listing, number);
+            self.indent += 1;
+            self.newline();
+        }
+
+        self.open_tag("pre", "codeblock", attributes);
+        escape_into(&mut self.output, code);
+        self.output.push_str("</pre>");
+
+        if let Some(number) = number {
+            self.newline();
+            self.render_caption(
+                "figcaption",
+                "listing-caption",
+                attributes,
+                Kind::Listing,
+                number,
+            );
+            self.indent -= 1;
+            self.newline();
+            self.close_tag("figure");
+        }
+    }
+
+    fn html(&mut self, html: &str) {
+        self.output.push_str(html);
+    }
+
+    fn math(&mut self, attributes: &[Attribute], formula: &MathExpr, _: &str, display: bool) {
+        match display {
+            true => {
+                self.open_tag_with("math", "math-block", attributes, &[("display", "block")]);
+            }
+            false => self.open_tag("math", "math", attributes),
+        }
+        write_mathml(&mut self.output, formula, display);
+        self.close_tag("math");
+    }
+
+    fn reference(&mut self, attributes: &[Attribute], inner: &[Node], label: Option<&str>) {
+        let href = format!("#{}", attribute(attributes, "ref_id").unwrap_or_default());
+
+        self.open_tag_with("a", "ref", attributes, &[("href", &href)]);
+        match label {
+            Some(label) => escape_into(&mut self.output, label),
+            None => self.render_children(inner),
+        }
+        self.close_tag("a");
+    }
+
+    fn listof(&mut self, attributes: &[Attribute], entries: &[ListofEntry]) {
+        self.open_tag("ul", "listof", attributes);
+        self.indent += 1;
+        for entry in entries {
+            self.newline();
+            self.render_listof_entry(entry);
+        }
+        self.indent -= 1;
+        if !entries.is_empty() {
+            self.newline();
+        }
+        self.close_tag("ul");
+    }
+
+    fn citation(&mut self, attributes: &[Attribute], label: &str) {
+        let key = attribute(attributes, "key").unwrap_or_default();
+        let href = format!("#{}", reference_id(self.id_prefix(), key));
+
+        self.open_tag_with("a", "citation", attributes, &[("href", &href)]);
+        escape_into(&mut self.output, label);
+        self.close_tag("a");
+    }
+
+    fn bibliography(&mut self, attributes: &[Attribute], references: &[Reference]) {
+        // Only numbered entries are listed in an ordered list
+        let tag = match references.iter().any(|r| r.label.is_some()) {
+            true => "ol",
+            false => "ul",
+        };
+
+        self.open_tag(tag, "bibliography", attributes);
+        self.indent += 1;
+        for reference in references {
+            self.newline();
+            self.render_reference(reference);
+        }
+        self.indent -= 1;
+        if !references.is_empty() {
+            self.newline();
+        }
+        self.close_tag(tag);
+    }
+
+    fn footnote_reference(&mut self, attributes: &[Attribute], number: usize, _: Option<&[Node]>) {
+        self.open_tag("sup", "footnote-reference", attributes);
+        let href = format!("#{}", footnote_id(self.id_prefix(), number));
+        self.output.push_str("<a");
+        self.write_attribute("href", &href);
+        write!(self.output, ">{number}</a>").unwrap();
+        self.close_tag("sup");
+    }
+
+    fn footnotes(&mut self, attributes: &[Attribute], footnotes: &[Footnote]) {
+        self.open_tag("div", "footnotes", attributes);
+        self.indent += 1;
+        self.newline();
+        self.open_tag("div", "footnotes-header", &[]);
+        self.output.push_str("Footnotes");
+        self.close_tag("div");
+        self.newline();
+        self.open_tag("div", "footnotes-definitions", &[]);
+        self.indent += 1;
+        for footnote in footnotes {
+            self.newline();
+            self.render_footnote(footnote);
+        }
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("div");
+        self.indent -= 1;
+        self.newline();
+        self.close_tag("div");
+    }
+}
+
+/// The class of a part of a table, which its rows and cells are prefixed by
+fn table_class(part: TablePart) -> &'static str {
+    match part {
+        TablePart::Body => "table",
+        TablePart::Head => "table-head",
+        TablePart::Foot => "table-foot",
+    }
+}
+
+/// The trimmed values of all attributes with a given name
+fn attributes_named<'a, 's: 'a>(
+    attributes: &'a [Attribute<'s>],
+    name: &'a str,
+) -> impl Iterator<Item = &'s str> + 'a {
+    attributes
+        .iter()
+        .filter(move |a| a.attribute_name.name == name)
+        .map(|a| a.attribute_value.text.trim())
+}
+
+/// The trimmed value of the last attribute with a given name
+fn attribute<'s>(attributes: &[Attribute<'s>], name: &str) -> Option<&'s str> {
+    attributes_named(attributes, name).last()
+}
+
+/// Whether a boolean attribute is set
+fn flag(attributes: &[Attribute], name: &str) -> bool {
+    attribute(attributes, name)
+        .and_then(parse_flag)
+        .unwrap_or(false)
+}
+
+/// The HTML `open` attribute of a `details` element, if it should be open
+fn open_flag(attributes: &[Attribute]) -> &'static [(&'static str, &'static str)] {
+    match flag(attributes, "open") {
+        true => &[("open", "")],
+        false => &[],
+    }
+}
+
+/// The HTML id of a bibliography entry
+fn reference_id(prefix: &str, key: &str) -> String {
+    format!("{prefix}reference-{key}")
+}
+
+/// The HTML id of the definition of a footnote
+fn footnote_id(prefix: &str, number: usize) -> String {
+    format!("{prefix}footnote-definition-{number}")
+}
+
+/// Write text to the output, escaping all characters with a special meaning
+/// in HTML
+pub(super) fn escape_into(output: &mut impl Sink, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            c => output.push(c),
+        }
+    }
+}
@@ -0,0 +1,806 @@
+//! LaTeX rendering, producing a standalone document for print
+
+use std::fmt::Write;
+
+use super::{
+    dispatch, Admonition, Context, Inline, ListItem, ListKind, Media, Renderer, Sink, Stream,
+    TablePart,
+};
+use crate::bibliography::{Reference, Segment};
+use crate::parse::*;
+use crate::pass::{Align, Footnote, Kind, ListofEntry, Metadata};
+use crate::{Config, ParseErrorType};
+
+/// The sectioning commands of nested sections, starting with sections at the
+/// top level
+const SECTIONING_COMMANDS: &[&str] = &[
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "subparagraph",
+];
+
+/// Everything before the metadata of a document
+const PREAMBLE: &str = r"\documentclass{article}
+
+\usepackage[T1]{fontenc}
+\usepackage[utf8]{inputenc}
+\usepackage{lmodern}
+\usepackage{amsmath}
+\usepackage{graphicx}
+\usepackage{float}
+\usepackage{multirow}
+\usepackage{xcolor}
+\usepackage[normalem]{ulem}
+\usepackage{hyperref}
+
+\newfloat{listing}{htbp}{lol}
+\floatname{listing}{Listing}
+";
+
+/// Renders a page to a standalone LaTeX document, streaming it into a
+/// [`fmt::Write`](std::fmt::Write)
+pub(crate) struct LatexRenderer<'p, W: Write> {
+    parser: &'p Parser,
+    output: Stream<W>,
+    context: Context,
+    /// Whether nothing was written in the innermost environment yet, so that
+    /// the next block needs no blank line before it
+    fresh: bool,
+    /// Whether inline content is being written into a block, like the text
+    /// of a paragraph
+    inline: bool,
+    /// Whether a description term was just written, so that the description
+    /// after it continues its `\item`
+    term: bool,
+    /// The prefix of generated labels, the same as that of generated HTML ids
+    id_prefix: String,
+}
+
+impl<'p, W: Write> LatexRenderer<'p, W> {
+    /// Render a page as a complete document, with a title made from its
+    /// metadata
+    pub(crate) fn render(
+        page: &Page,
+        parser: &'p Parser,
+        config: &Config,
+        output: W,
+    ) -> std::fmt::Result {
+        let mut renderer = Self {
+            parser,
+            output: Stream::new(output),
+            context: Context::default(),
+            fresh: true,
+            inline: false,
+            term: false,
+            id_prefix: config.classes.id_prefix().to_owned(),
+        };
+
+        renderer.output.push_str(PREAMBLE);
+        renderer.write_metadata(&page.metadata);
+        renderer.output.push_str("\n\\begin{document}\n");
+        if page.metadata.title.is_some() {
+            renderer.output.push_str("\n\\maketitle\n");
+        }
+        renderer.output.push('\n');
+
+        renderer.render_children(&page.doc.nodes);
+        if !renderer.fresh {
+            renderer.output.push_str("\n\n");
+        }
+        renderer.output.push_str("\\end{document}\n");
+
+        renderer.output.finish().map(|_| ())
+    }
+
+    /// Write the title, author and date of the document
+    ///
+    /// An empty date keeps LaTeX from using the date the document is built
+    /// on.
+    fn write_metadata(&mut self, metadata: &Metadata) {
+        let fields = [
+            ("title", &metadata.title),
+            ("author", &metadata.author),
+            ("date", &metadata.date),
+        ];
+
+        self.output.push('\n');
+        for (command, value) in fields {
+            write!(self.output, "\\{command}{{").unwrap();
+            escape_into(&mut self.output, value.as_deref().unwrap_or_default());
+            self.output.push_str("}\n");
+        }
+    }
+
+    /// Start a block, separated from the one before it by a blank line
+    fn start_block(&mut self) {
+        if !self.fresh {
+            self.output.push_str("\n\n");
+        }
+        self.fresh = false;
+        self.inline = false;
+    }
+
+    /// Start a paragraph for inline content, unless inline content is already
+    /// being written
+    fn start_inline(&mut self) {
+        if !self.inline {
+            self.start_block();
+            self.inline = true;
+        }
+    }
+
+    /// Write a line of its own, without a blank line before it
+    fn line(&mut self, line: &str) {
+        if !self.fresh {
+            self.output.push('\n');
+        }
+        self.output.push_str(line);
+        self.fresh = false;
+        self.inline = false;
+    }
+
+    /// Render inline content as a block, like a paragraph
+    fn render_inline_block(&mut self, inner: &[Node]) {
+        self.start_block();
+        self.inline = true;
+        self.render_children(inner);
+        self.inline = false;
+    }
+
+    /// Render a heading as a sectioning command, labelled by its id
+    ///
+    /// Unnumbered commands, like `\section*`, get an anchor of their own for
+    /// the label to point at.
+    fn render_heading(&mut self, command: &str, inner: &[Node], id: Option<&str>) {
+        self.start_block();
+        write!(self.output, "\\{command}{{").unwrap();
+        self.inline = true;
+        self.render_children(inner);
+        self.inline = false;
+        self.output.push('}');
+        if let Some(id) = id {
+            if command.ends_with('*') {
+                self.output.push_str("\\phantomsection");
+            }
+            self.output.push_str("\\label{");
+            label_into(&mut self.output, id);
+            self.output.push('}');
+        }
+    }
+
+    /// Render inline content as the argument of a command, like `\emph`
+    fn render_command(&mut self, command: &str, inner: &[Node]) {
+        self.start_inline();
+        write!(self.output, "\\{command}{{").unwrap();
+        self.render_children(inner);
+        self.output.push('}');
+    }
+
+    /// Start an environment as a block, with its options written right after
+    /// `\begin{...}`
+    fn begin(&mut self, environment: &str, options: &str) {
+        self.start_block();
+        writeln!(self.output, "\\begin{{{environment}}}{options}").unwrap();
+        self.fresh = true;
+    }
+
+    fn end(&mut self, environment: &str) {
+        if !self.fresh {
+            self.output.push('\n');
+        }
+        write!(self.output, "\\end{{{environment}}}").unwrap();
+        self.fresh = false;
+        self.inline = false;
+    }
+
+    /// Write the caption and label of a numbered node, inside its float
+    fn write_caption(&mut self, attributes: &[Attribute], kind: Kind, number: usize) {
+        let caption = attribute(attributes, "caption").unwrap_or_default();
+        let id = attribute(attributes, "id")
+            .map(str::to_owned)
+            .unwrap_or_else(|| kind.default_id(&self.id_prefix, number));
+
+        let mut line = String::from("\\caption{");
+        escape_into(&mut line, caption);
+        line.push_str("}\\label{");
+        label_into(&mut line, &id);
+        line.push('}');
+        self.line(&line);
+    }
+
+    /// Render the rows of a table as a `tabular`, with a rule after its
+    /// `thead` and before its `tfoot`
+    fn render_tabular(&mut self, inner: &[Node]) {
+        let mut parts = vec![];
+        let mut body = vec![];
+        for node in inner {
+            match node {
+                Node::Thead { inner, .. } => parts.push((TablePart::Head, rows(inner))),
+                Node::Tfoot { inner, .. } => parts.push((TablePart::Foot, rows(inner))),
+                node => body.extend(rows(std::slice::from_ref(node))),
+            }
+        }
+        // The body comes between the header and the footer
+        let at = parts
+            .iter()
+            .position(|(part, _)| *part == TablePart::Foot)
+            .unwrap_or(parts.len());
+        parts.insert(at, (TablePart::Body, body));
+
+        // Every row has the same width, which the first one shows
+        let first = parts.iter().find_map(|(_, rows)| rows.first());
+        let mut columns = String::new();
+        for cell in first.into_iter().flatten() {
+            if let Node::Tcell {
+                attributes, align, ..
+            } = cell
+            {
+                for _ in 0..span(attributes, "colspan") {
+                    columns.push(column_type(*align));
+                }
+            }
+        }
+
+        self.line(&format!("\\begin{{tabular}}{{{columns}}}\n\\hline"));
+        for (part, rows) in &parts {
+            if rows.is_empty() {
+                continue;
+            }
+            if *part == TablePart::Foot {
+                self.output.push_str("\n\\hline");
+            }
+
+            // The number of rows each column is still covered by a cell
+            // above spanning several rows
+            let mut covered = vec![0; columns.len()];
+            for row in rows {
+                self.output.push('\n');
+                self.render_tabular_row(row, &mut covered);
+            }
+
+            if *part == TablePart::Head {
+                self.output.push_str("\n\\hline");
+            }
+        }
+        self.output.push_str("\n\\hline\n\\end{tabular}");
+    }
+
+    fn render_tabular_row(&mut self, cells: &[&Node], covered: &mut [usize]) {
+        let mut cells = cells.iter();
+        let mut column = 0;
+
+        while column < covered.len() {
+            if column > 0 {
+                self.output.push_str(" & ");
+            }
+
+            if covered[column] > 0 {
+                covered[column] -= 1;
+                column += 1;
+                continue;
+            }
+            let Some(Node::Tcell {
+                attributes,
+                inner,
+                align,
+                ..
+            }) = cells.next()
+            else {
+                break;
+            };
+
+            let colspan = span(attributes, "colspan");
+            let rowspan = span(attributes, "rowspan");
+            if colspan > 1 {
+                let column = column_type(*align);
+                write!(self.output, "\\multicolumn{{{colspan}}}{{{column}}}{{").unwrap();
+            }
+            if rowspan > 1 {
+                write!(self.output, "\\multirow{{{rowspan}}}{{*}}{{").unwrap();
+            }
+
+            self.inline = true;
+            self.render_children(inner);
+            self.inline = false;
+
+            if rowspan > 1 {
+                self.output.push('}');
+            }
+            if colspan > 1 {
+                self.output.push('}');
+            }
+
+            for rows in &mut covered[column..column + colspan] {
+                *rows = rowspan - 1;
+            }
+            // The other columns of the cell are part of it
+            column += colspan;
+        }
+
+        self.output.push_str(" \\\\");
+    }
+
+    /// Render a list of items, each written by `item` after its `\item`
+    fn render_list<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        if items.is_empty() {
+            return;
+        }
+
+        self.begin("itemize", "");
+        for (i, entry) in items.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.output.push_str("\\item");
+            item(self, entry);
+        }
+        self.fresh = false;
+        self.end("itemize");
+    }
+}
+
+impl<W: Write> Renderer for LatexRenderer<'_, W> {
+    fn context(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    fn render_node(&mut self, node: &Node) {
+        let representable = match node {
+            // Raw HTML has no meaning in print
+            Node::Html { .. } => false,
+            // A `verbatim` environment ends at the first `\end{verbatim}`,
+            // even in the middle of the code
+            Node::Codeblock { text, .. } => !text.text.contains("\\end{verbatim}"),
+            // File names cannot escape these, even when detokenized
+            Node::Image { .. } => !node
+                .attribute("src")
+                .is_some_and(|src| src.contains(['%', '#', '{', '}', '\\'])),
+            _ => true,
+        };
+
+        match representable {
+            true => dispatch(self, node),
+            false => self.parser.warn(
+                node.span(),
+                ParseErrorType::Unrepresentable {
+                    name: node.name().to_owned(),
+                    format: "LaTeX".to_owned(),
+                },
+            ),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.start_inline();
+        escape_into(&mut self.output, text);
+    }
+
+    /// Render a title as the sectioning command of its section, labelled by
+    /// its id
+    ///
+    /// Titles outside of any section are the title of the document, which
+    /// `\maketitle` shows instead.
+    fn title(&mut self, _attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+        let depth = self.context.heading_level() - 1;
+        let Some(index) = depth.checked_sub(1) else {
+            return;
+        };
+        let command = SECTIONING_COMMANDS[index.min(SECTIONING_COMMANDS.len() - 1)];
+
+        self.render_heading(command, inner, id);
+    }
+
+    /// Render a header as the unnumbered sectioning command of its section,
+    /// so that it is left out of the table of contents
+    ///
+    /// Headers outside of any section are shown like those of top-level
+    /// sections.
+    fn header(&mut self, _attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+        let index = (self.context.heading_level() - 1).saturating_sub(1);
+        let command = SECTIONING_COMMANDS[index.min(SECTIONING_COMMANDS.len() - 1)];
+
+        self.render_heading(&format!("{command}*"), inner, id);
+    }
+
+    fn paragraph(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_inline_block(inner);
+    }
+
+    fn inline(&mut self, kind: Inline, _attributes: &[Attribute], inner: &[Node]) {
+        let command = match kind {
+            Inline::Bold => "textbf",
+            Inline::Italic => "emph",
+            Inline::Monospace | Inline::Keyboard => "texttt",
+            Inline::Underline | Inline::Inserted => "uline",
+            Inline::Strikethrough | Inline::Deleted => "sout",
+            Inline::Subscript => "textsubscript",
+            Inline::Superscript => "textsuperscript",
+            Inline::Mark => {
+                self.start_inline();
+                self.output.push_str("\\colorbox{yellow}{");
+                self.render_children(inner);
+                self.output.push('}');
+                return;
+            }
+            Inline::Small => {
+                self.start_inline();
+                self.output.push_str("{\\small ");
+                self.render_children(inner);
+                self.output.push('}');
+                return;
+            }
+            Inline::Quote => {
+                self.start_inline();
+                self.output.push_str("``");
+                self.render_children(inner);
+                self.output.push_str("''");
+                return;
+            }
+            // Abbreviations and times read the same in print
+            Inline::Abbreviation | Inline::Time | Inline::Span => {
+                return self.render_children(inner);
+            }
+        };
+
+        self.render_command(command, inner);
+    }
+
+    /// Render an admonition as a quote starting with its kind, like
+    /// `\textbf{Note:}`
+    fn admonition(&mut self, kind: Admonition, _attributes: &[Attribute], inner: &[Node]) {
+        let name = kind.name();
+
+        // The quote is still fresh after this, so that its first block
+        // starts right after the kind
+        self.begin("quote", "");
+        write!(
+            self.output,
+            "\\textbf{{{}{}:}} ",
+            name[..1].to_uppercase(),
+            &name[1..]
+        )
+        .unwrap();
+        self.render_children(inner);
+        self.end("quote");
+    }
+
+    /// Render a `details` element with its summary as a bold paragraph, as
+    /// nothing is hidden in print
+    fn details(&mut self, attributes: &[Attribute], inner: &[Node]) {
+        if let Some(summary) = attribute(attributes, "summary") {
+            self.start_block();
+            self.output.push_str("\\textbf{");
+            escape_into(&mut self.output, summary);
+            self.output.push('}');
+        }
+        self.render_children(inner);
+    }
+
+    /// Render a list as an `itemize`, `enumerate` or `description`, unless
+    /// it has no items, which LaTeX does not allow
+    fn list(&mut self, kind: ListKind, _attributes: &[Attribute], inner: &[Node]) {
+        if inner.iter().all(|node| matches!(node, Node::Text { .. })) {
+            return;
+        }
+        let environment = match kind {
+            ListKind::Unordered => "itemize",
+            ListKind::Ordered => "enumerate",
+            ListKind::Description => "description",
+        };
+
+        // Lists inside an item start on the line after its text
+        match self.inline {
+            true => self.output.push('\n'),
+            false => self.start_block(),
+        }
+        writeln!(self.output, "\\begin{{{environment}}}").unwrap();
+        self.fresh = true;
+        self.inline = false;
+        self.render_children(inner);
+        self.end(environment);
+    }
+
+    /// Render a list item after its `\item`, or a description term as the
+    /// label of the `\item` its description continues
+    fn list_item(&mut self, kind: ListItem, _attributes: &[Attribute], inner: &[Node]) {
+        let term = std::mem::take(&mut self.term);
+        if kind == ListItem::Description && term {
+            self.output.push(' ');
+        } else {
+            if !self.fresh {
+                self.output.push('\n');
+            }
+            self.output.push_str("\\item");
+            if kind != ListItem::Term {
+                self.output.push(' ');
+            }
+        }
+
+        if kind == ListItem::Term {
+            // Braces keep a `]` in the term from ending the label
+            self.output.push_str("[{");
+            self.inline = true;
+            self.render_children(inner);
+            self.output.push_str("}]");
+            self.inline = false;
+            self.fresh = false;
+            self.term = true;
+            return;
+        }
+
+        self.fresh = true;
+        self.inline = false;
+        self.render_children(inner);
+        self.inline = false;
+        self.fresh = false;
+    }
+
+    fn image(&mut self, attributes: &[Attribute], number: usize) {
+        let src = attribute(attributes, "src").unwrap_or_default();
+
+        self.begin("figure", "[htbp]");
+        self.line("\\centering");
+        // Other special characters are taken literally once detokenized
+        let src = src.trim();
+        let src = match src.contains(['$', '&', '^', '_', '~', ' ']) {
+            true => format!("\\detokenize{{{src}}}"),
+            false => src.to_owned(),
+        };
+        self.line(&format!("\\includegraphics[width=\\linewidth]{{{src}}}"));
+        self.write_caption(attributes, Kind::Figure, number);
+        self.end("figure");
+    }
+
+    /// Render a video or audio clip as a link to it, as it cannot be played
+    /// in print
+    fn media(&mut self, _kind: Media, attributes: &[Attribute], _: &[Node], _: Option<&str>) {
+        let src = attribute(attributes, "src").unwrap_or_default();
+
+        self.start_block();
+        url_into(&mut self.output, src);
+    }
+
+    fn table(&mut self, attributes: &[Attribute], inner: &[Node], number: Option<usize>) {
+        match number {
+            Some(number) => {
+                self.begin("table", "[htbp]");
+                self.line("\\centering");
+                self.write_caption(attributes, Kind::Table, number);
+                self.render_tabular(inner);
+                self.end("table");
+            }
+            None => {
+                self.begin("center", "");
+                self.render_tabular(inner);
+                self.end("center");
+            }
+        }
+    }
+
+    fn codeblock(&mut self, attributes: &[Attribute], code: &str, number: Option<usize>) {
+        if number.is_some() {
+            self.begin("listing", "[htbp]");
+        }
+
+        self.start_block();
+        write!(
+            self.output,
+            "\\begin{{verbatim}}\n{}\n\\end{{verbatim}}",
+            code.trim_end()
+        )
+        .unwrap();
+
+        if let Some(number) = number {
+            self.write_caption(attributes, Kind::Listing, number);
+            self.end("listing");
+        }
+    }
+
+    fn html(&mut self, _html: &str) {
+        unreachable!("html nodes are left out")
+    }
+
+    /// Render a formula from its source, whose notation is a subset of
+    /// LaTeX
+    fn math(&mut self, _attributes: &[Attribute], _: &MathExpr, source: &str, display: bool) {
+        // Parentheses are only escaped to balance the node
+        let source = source.trim().replace("\\(", "(").replace("\\)", ")");
+
+        match display {
+            true => {
+                self.start_block();
+                write!(self.output, "\\[ {source} \\]").unwrap();
+            }
+            false => {
+                self.start_inline();
+                write!(self.output, "${source}$").unwrap();
+            }
+        }
+    }
+
+    /// Render a reference as `\ref`, after the kind of the node it refers to,
+    /// or as a link if it has text of its own
+    fn reference(&mut self, attributes: &[Attribute], inner: &[Node], label: Option<&str>) {
+        let id = attribute(attributes, "ref_id").unwrap_or_default();
+
+        self.start_inline();
+        match label.and_then(|l| l.rsplit_once(' ')) {
+            Some((kind, _)) => {
+                escape_into(&mut self.output, kind);
+                self.output.push_str("~\\ref{");
+                label_into(&mut self.output, id);
+                self.output.push('}');
+            }
+            None => {
+                self.output.push_str("\\hyperref[");
+                label_into(&mut self.output, id);
+                self.output.push_str("]{");
+                match label {
+                    Some(label) => escape_into(&mut self.output, label),
+                    None => self.render_children(inner),
+                }
+                self.output.push('}');
+            }
+        }
+    }
+
+    fn listof(&mut self, _attributes: &[Attribute], entries: &[ListofEntry]) {
+        self.render_list(entries, |renderer, entry| {
+            renderer.output.push_str(" \\hyperref[");
+            label_into(&mut renderer.output, &entry.id);
+            renderer.output.push_str("]{");
+            escape_into(&mut renderer.output, &entry.label);
+            renderer.output.push_str("}: ");
+            escape_into(&mut renderer.output, &entry.caption);
+        });
+    }
+
+    fn citation(&mut self, _attributes: &[Attribute], label: &str) {
+        self.start_inline();
+        escape_into(&mut self.output, label);
+    }
+
+    fn bibliography(&mut self, _attributes: &[Attribute], references: &[Reference]) {
+        self.render_list(references, |renderer, reference| {
+            if let Some(label) = &reference.label {
+                renderer.output.push_str("[{");
+                escape_into(&mut renderer.output, label);
+                renderer.output.push_str("}]");
+            }
+            renderer.output.push(' ');
+
+            for segment in &reference.segments {
+                match segment {
+                    Segment::Text(text) => escape_into(&mut renderer.output, text),
+                    Segment::Title(title) => {
+                        renderer.output.push_str("\\emph{");
+                        escape_into(&mut renderer.output, title);
+                        renderer.output.push('}');
+                    }
+                    Segment::Link(url) => url_into(&mut renderer.output, url),
+                }
+            }
+        });
+    }
+    /// Render the first reference to a footnote as a `\footnote` with its
+    /// number, and later ones as a `\footnotemark` pointing at it
+    fn footnote_reference(
+        &mut self,
+        _attributes: &[Attribute],
+        number: usize,
+        note: Option<&[Node]>,
+    ) {
+        self.start_inline();
+        match note {
+            Some(note) => {
+                write!(self.output, "\\footnote[{number}]{{").unwrap();
+                self.render_children(note);
+                self.output.push('}');
+            }
+            None => write!(self.output, "\\footnotemark[{number}]").unwrap(),
+        }
+    }
+
+    /// Footnotes are shown at the bottom of the page they are referenced on,
+    /// so lists of them are left out
+    fn footnotes(&mut self, _attributes: &[Attribute], _footnotes: &[Footnote]) {}
+}
+
+/// The cells of every row among some nodes
+fn rows<'a, 's>(nodes: &'a [Node<'s>]) -> Vec<Vec<&'a Node<'s>>> {
+    nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Trow { inner, .. } => Some(inner.iter().collect()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The type of a `tabular` column with an alignment
+fn column_type(align: Option<Align>) -> char {
+    match align {
+        None | Some(Align::Left) => 'l',
+        Some(Align::Center) => 'c',
+        Some(Align::Right) => 'r',
+    }
+}
+
+/// The trimmed value of the last attribute with a given name
+fn attribute<'s>(attributes: &[Attribute<'s>], name: &str) -> Option<&'s str> {
+    attributes
+        .iter()
+        .rfind(|a| a.attribute_name.name == name)
+        .map(|a| a.attribute_value.text.trim())
+}
+
+/// The number of rows or columns a cell spans, checked when tables are laid
+/// out
+fn span(attributes: &[Attribute], name: &str) -> usize {
+    attribute(attributes, name).map_or(1, |value| value.parse().unwrap_or(1))
+}
+
+/// Write text to the output, escaping all characters with a special meaning
+/// in LaTeX
+fn escape_into(output: &mut impl Sink, text: &str) {
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                output.push('\\');
+                output.push(c);
+            }
+            '\\' => output.push_str("\\textbackslash{}"),
+            '^' => output.push_str("\\textasciicircum{}"),
+            '~' => output.push_str("\\textasciitilde{}"),
+            '<' => output.push_str("\\textless{}"),
+            '>' => output.push_str("\\textgreater{}"),
+            '\u{a0}' => output.push('~'),
+            // Two hyphens would be joined into a dash
+            '-' if chars.peek() == Some(&'-') => output.push_str("-{}"),
+            c => output.push(c),
+        }
+    }
+}
+
+/// Write an id as the name of a label, which can only safely contain some
+/// characters
+///
+/// Every other byte is written as `+` and its value in hexadecimal, so that
+/// different ids never share a label.
+fn label_into(output: &mut impl Sink, id: &str) {
+    for byte in id.trim().bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b':' | b'/' => {
+                output.push(byte as char)
+            }
+            _ => output.push_str(&format!("+{byte:02X}")),
+        }
+    }
+}
+
+/// Write a URL as a `\url`, escaping `%` and `#` and percent-encoding the
+/// characters it cannot contain at all
+fn url_into(output: &mut impl Sink, url: &str) {
+    output.push_str("\\url{");
+    for c in url.trim().chars() {
+        match c {
+            '%' | '#' => {
+                output.push('\\');
+                output.push(c);
+            }
+            '{' | '}' | '\\' | '^' | '~' => output.push_str(&format!("\\%{:02X}", c as u8)),
+            c if c.is_whitespace() => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    output.push_str(&format!("\\%{byte:02X}"));
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('}');
+}
@@ -0,0 +1,596 @@
+//! Markdown rendering, following CommonMark with the GitHub Flavored Markdown
+//! extensions for tables, strikethrough and alerts
+
+use std::fmt::Write;
+
+use super::{
+    dispatch, Admonition, Context, HtmlRenderer, Inline, ListItem, ListKind, Media, Renderer, Sink,
+    Stream,
+};
+use crate::bibliography::{Reference, Segment};
+use crate::parse::*;
+use crate::pass::{Align, Footnote, Kind, ListofEntry};
+use crate::ParseErrorType;
+
+/// The deepest heading level supported by Markdown
+const MAX_HEADING_LEVEL: usize = 6;
+
+/// Renders a page to Markdown, streaming it into a [`fmt::Write`](std::fmt::Write)
+///
+/// Nodes that Markdown cannot represent are rendered as inline HTML instead,
+/// with a warning.
+pub(crate) struct MarkdownRenderer<'p, W: Write> {
+    parser: &'p Parser,
+    output: Stream<W>,
+    context: Context,
+    /// What every line starts with, `> ` for each blockquote and spaces for
+    /// each list item the current line is in
+    prefix: String,
+    /// Whether nothing was written in the innermost block container yet, so
+    /// that the next block needs no blank line before it
+    fresh: bool,
+    /// Whether inline content is being written into a block, like the text
+    /// of a paragraph
+    inline: bool,
+    /// Whether nothing was written on the current line of inline content yet,
+    /// so that text starting with a block marker like `#` must escape it
+    line_start: bool,
+}
+
+impl<'p, W: Write> MarkdownRenderer<'p, W> {
+    /// Render the document of a page
+    pub(crate) fn render(page: &Page, parser: &'p Parser, output: W) -> std::fmt::Result {
+        let mut renderer = Self {
+            parser,
+            output: Stream::new(output),
+            context: Context::default(),
+            prefix: String::new(),
+            fresh: true,
+            inline: false,
+            line_start: false,
+        };
+
+        renderer.render_children(&page.doc.nodes);
+        if !renderer.output.ends_with_newline() {
+            renderer.output.push('\n');
+        }
+
+        renderer.output.finish().map(|_| ())
+    }
+
+    /// Start a new line in the current block container
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.output.push_str(&self.prefix);
+    }
+
+    /// Start a block, separated from the one before it by a blank line
+    fn start_block(&mut self) {
+        if !self.fresh {
+            self.output.push('\n');
+            self.output.push_str(self.prefix.trim_end());
+            self.newline();
+        }
+        self.fresh = false;
+        self.inline = false;
+    }
+
+    /// Start a paragraph for inline content, unless inline content is already
+    /// being written
+    fn start_inline(&mut self) {
+        if !self.inline {
+            self.start_block();
+            self.inline = true;
+            self.line_start = true;
+        }
+    }
+
+    /// Render inline content as a block, like a paragraph or a heading
+    /// starting with `marker`
+    fn render_inline_block(&mut self, marker: &str, inner: &[Node]) {
+        self.start_block();
+        self.output.push_str(marker);
+        self.inline = true;
+        self.line_start = marker.is_empty();
+        self.render_children(inner);
+        self.inline = false;
+    }
+
+    /// Render inline content between two delimiters, like `**` for bold text
+    fn render_delimited(&mut self, delimiter: &str, inner: &[Node]) {
+        self.start_inline();
+        self.output.push_str(delimiter);
+        self.line_start = false;
+        self.render_children(inner);
+        self.output.push_str(delimiter);
+    }
+
+    /// Write text on the current line, escaping it
+    fn write_text(&mut self, text: &str) {
+        escape_into(&mut self.output, text, self.line_start);
+        self.line_start &= text.is_empty();
+    }
+
+    /// Write text spanning several lines, keeping each line inside the
+    /// current blockquotes
+    fn write_lines(&mut self, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+            self.output.push_str(line);
+        }
+        self.line_start = false;
+    }
+
+    /// Write the caption of a numbered node as a paragraph of its own
+    fn write_caption(&mut self, attributes: &[Attribute], kind: Kind, number: usize) {
+        let caption = attributes
+            .iter()
+            .rfind(|a| a.attribute_name.name == "caption")
+            .map_or("", |a| a.attribute_value.text.trim());
+
+        self.start_block();
+        write!(self.output, "*{}:* ", kind.label(number)).unwrap();
+        self.write_text(caption);
+    }
+
+    /// Write a link destination, in angle brackets if it would otherwise end
+    /// the link early
+    fn write_url(&mut self, url: &str) {
+        match url.contains([' ', '(', ')', '<', '>']) {
+            true => {
+                self.output.push('<');
+                self.output
+                    .push_str(&url.replace('<', "%3C").replace('>', "%3E"));
+                self.output.push('>');
+            }
+            false => self.output.push_str(url),
+        }
+    }
+
+    /// Render a node as HTML, warning that it cannot be represented in
+    /// Markdown
+    fn render_fallback(&mut self, node: &Node, attribute: Option<&Attribute>) {
+        let span = attribute.map_or(node.span(), |a| a.span);
+        self.parser.warn(
+            span,
+            ParseErrorType::HtmlFallback {
+                name: node.name().to_owned(),
+                attribute: attribute.map(|a| a.attribute_name.name.to_owned()),
+                format: "Markdown".to_owned(),
+            },
+        );
+
+        let html = HtmlRenderer::render_fallback(self.parser, self.context, node);
+        match node.is_block() {
+            true => self.start_block(),
+            false => self.start_inline(),
+        }
+        self.write_lines(html.trim_end());
+    }
+
+    /// Render a table as a GFM table, whose first row is its header
+    ///
+    /// Tables without a `thead` get an empty header, as GFM tables always
+    /// have one.
+    fn render_table_rows(&mut self, inner: &[Node]) {
+        let mut head = vec![];
+        let mut body = vec![];
+        for node in inner {
+            match node {
+                Node::Thead { inner, .. } => head.extend(inner.iter().filter_map(cells)),
+                Node::Tfoot { inner, .. } => body.extend(inner.iter().filter_map(cells)),
+                node => body.extend(cells(node)),
+            }
+        }
+
+        let columns = head.iter().chain(&body).map(|r| r.len()).max().unwrap_or(0);
+        let aligns: Vec<_> = (0..columns)
+            .map(|i| {
+                head.iter()
+                    .chain(&body)
+                    .find_map(|row| row.get(i))
+                    .and_then(|cell| match cell {
+                        Node::Tcell { align, .. } => *align,
+                        _ => None,
+                    })
+            })
+            .collect();
+
+        self.start_block();
+        self.render_table_row(head.first().map_or(&[], |r| r.as_slice()), columns);
+        self.newline();
+        self.output.push('|');
+        for align in aligns {
+            self.output.push_str(match align {
+                None => " --- |",
+                Some(Align::Left) => " :-- |",
+                Some(Align::Center) => " :-: |",
+                Some(Align::Right) => " --: |",
+            });
+        }
+        for row in &body {
+            self.newline();
+            self.render_table_row(row, columns);
+        }
+    }
+
+    fn render_table_row(&mut self, cells: &[&Node], columns: usize) {
+        self.output.push('|');
+        for i in 0..columns {
+            self.output.push(' ');
+            if let Some(Node::Tcell { inner, .. }) = cells.get(i) {
+                self.inline = true;
+                self.line_start = false;
+                self.render_children(inner);
+                self.inline = false;
+            }
+            self.output.push_str(" |");
+        }
+    }
+}
+
+impl<W: Write> Renderer for MarkdownRenderer<'_, W> {
+    fn context(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    fn render_node(&mut self, node: &Node) {
+        // Classes and styles only exist in HTML
+        let attribute = node
+            .attributes()
+            .iter()
+            .rfind(|a| matches!(a.attribute_name.name, "class" | "style"));
+
+        if attribute.is_some() || !is_representable(node) {
+            self.render_fallback(node, attribute);
+        } else {
+            dispatch(self, node);
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        self.start_inline();
+        self.write_text(text);
+    }
+
+    fn title(&mut self, _attributes: &[Attribute], inner: &[Node], _id: Option<&str>) {
+        let level = self.context.heading_level().min(MAX_HEADING_LEVEL);
+        let marker = format!("{} ", "#".repeat(level));
+
+        self.render_inline_block(&marker, inner);
+    }
+
+    fn paragraph(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_inline_block("", inner);
+    }
+
+    fn inline(&mut self, kind: Inline, _attributes: &[Attribute], inner: &[Node]) {
+        match kind {
+            Inline::Bold => self.render_delimited("**", inner),
+            Inline::Italic => self.render_delimited("*", inner),
+            Inline::Strikethrough | Inline::Deleted => self.render_delimited("~~", inner),
+            Inline::Span => self.render_children(inner),
+            Inline::Monospace => {
+                let code: String = inner.iter().filter_map(text).collect();
+
+                // The delimiter must be longer than any run of backticks in
+                // the code
+                let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let delimiter = "`".repeat(longest + 1);
+                let padding = match code.starts_with('`') || code.ends_with('`') {
+                    true => " ",
+                    false => "",
+                };
+
+                self.start_inline();
+                write!(
+                    self.output,
+                    "{delimiter}{padding}{code}{padding}{delimiter}"
+                )
+                .unwrap();
+                self.line_start = false;
+            }
+            _ => unreachable!("{kind:?} nodes are rendered as HTML"),
+        }
+    }
+
+    /// Render an admonition as a GFM alert, a blockquote starting with its
+    /// kind, like `> [!NOTE]`
+    fn admonition(&mut self, kind: Admonition, _attributes: &[Attribute], inner: &[Node]) {
+        self.start_block();
+        write!(self.output, "> [!{}]", kind.name().to_uppercase()).unwrap();
+        self.prefix.push_str("> ");
+        self.newline();
+
+        self.fresh = true;
+        self.render_children(inner);
+        self.inline = false;
+        self.fresh = false;
+
+        self.prefix.truncate(self.prefix.len() - 2);
+    }
+
+    /// Render a list with every item on a line of its own
+    ///
+    /// A list right after the text of a list item starts on the next line,
+    /// so that the outer list stays tight.
+    fn list(&mut self, _kind: ListKind, _attributes: &[Attribute], inner: &[Node]) {
+        match self.inline {
+            true => self.newline(),
+            false => self.start_block(),
+        }
+        self.inline = false;
+        self.fresh = true;
+        self.render_children(inner);
+        self.inline = false;
+        self.fresh = false;
+    }
+
+    /// Render a list item after its marker, indenting every line after its
+    /// first one to the content of the item
+    ///
+    /// Ordered lists number their items from their first marker, so every
+    /// item is marked `1.`.
+    fn list_item(&mut self, kind: ListItem, _attributes: &[Attribute], inner: &[Node]) {
+        let marker = match self.context.list() {
+            Some(ListKind::Unordered) => "- ",
+            Some(ListKind::Ordered) => "1. ",
+            _ => unreachable!("{kind:?} nodes are rendered as HTML"),
+        };
+
+        if !self.fresh {
+            self.newline();
+        }
+        self.output.push_str(marker);
+        self.prefix.push_str(&" ".repeat(marker.len()));
+
+        self.fresh = true;
+        self.render_children(inner);
+        self.inline = false;
+        self.fresh = false;
+
+        self.prefix.truncate(self.prefix.len() - marker.len());
+    }
+
+    fn image(&mut self, attributes: &[Attribute], number: usize) {
+        let value = |name| {
+            attributes
+                .iter()
+                .rfind(|a| a.attribute_name.name == name)
+                .map_or("", |a| a.attribute_value.text.trim())
+        };
+
+        self.start_block();
+        self.output.push_str("![");
+        self.write_text(value("alt"));
+        self.output.push_str("](");
+        self.write_url(value("src"));
+        self.output.push(')');
+        self.write_caption(attributes, Kind::Figure, number);
+    }
+
+    fn media(&mut self, kind: Media, _: &[Attribute], _: &[Node], _: Option<&str>) {
+        unreachable!("{} nodes are rendered as HTML", kind.name())
+    }
+
+    fn table(&mut self, attributes: &[Attribute], inner: &[Node], number: Option<usize>) {
+        if let Some(number) = number {
+            self.write_caption(attributes, Kind::Table, number);
+        }
+        self.render_table_rows(inner);
+    }
+
+    fn codeblock(&mut self, attributes: &[Attribute], code: &str, number: Option<usize>) {
+        let code = code.trim_end();
+        let language = attributes
+            .iter()
+            .rfind(|a| a.attribute_name.name == "lang")
+            .map_or("", |a| a.attribute_value.text.trim());
+
+        // The fence must be longer than any run of backticks in the code
+        let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+        let fence = "`".repeat(longest.max(2) + 1);
+
+        self.start_block();
+        write!(self.output, "{fence}{language}").unwrap();
+        for line in code.lines() {
+            self.newline();
+            self.output.push_str(line);
+        }
+        self.newline();
+        self.output.push_str(&fence);
+
+        if let Some(number) = number {
+            self.write_caption(attributes, Kind::Listing, number);
+        }
+    }
+
+    fn html(&mut self, html: &str) {
+        self.start_inline();
+        self.write_lines(html);
+    }
+
+    fn math(&mut self, _: &[Attribute], _: &MathExpr, _: &str, _: bool) {
+        unreachable!("math is rendered as HTML")
+    }
+
+    fn reference(&mut self, attributes: &[Attribute], inner: &[Node], label: Option<&str>) {
+        let id = attributes
+            .iter()
+            .rfind(|a| a.attribute_name.name == "ref_id")
+            .map_or("", |a| a.attribute_value.text.trim());
+
+        self.start_inline();
+        self.output.push('[');
+        self.line_start = false;
+        match label {
+            Some(label) => self.write_text(label),
+            None => self.render_children(inner),
+        }
+        self.output.push_str("](#");
+        self.write_url(id);
+        self.output.push(')');
+    }
+
+    fn listof(&mut self, _attributes: &[Attribute], entries: &[ListofEntry]) {
+        for (i, entry) in entries.iter().enumerate() {
+            match i {
+                0 => self.start_block(),
+                _ => self.newline(),
+            }
+            self.output.push_str("- [");
+            self.write_text(&entry.label);
+            self.output.push_str("](#");
+            self.write_url(&entry.id);
+            self.output.push_str("): ");
+            self.write_text(&entry.caption);
+        }
+    }
+
+    fn citation(&mut self, _attributes: &[Attribute], label: &str) {
+        self.start_inline();
+        self.write_text(label);
+    }
+
+    fn bibliography(&mut self, _attributes: &[Attribute], references: &[Reference]) {
+        for (i, reference) in references.iter().enumerate() {
+            match i {
+                0 => self.start_block(),
+                _ => self.newline(),
+            }
+            self.output.push_str("- ");
+            if let Some(label) = &reference.label {
+                self.write_text(label);
+                self.output.push(' ');
+            }
+
+            for segment in &reference.segments {
+                match segment {
+                    Segment::Text(text) => self.write_text(text),
+                    Segment::Title(title) => {
+                        self.output.push('*');
+                        self.write_text(title);
+                        self.output.push('*');
+                    }
+                    Segment::Link(url) => write!(self.output, "<{url}>").unwrap(),
+                }
+            }
+        }
+    }
+
+    fn footnote_reference(&mut self, _: &[Attribute], number: usize, _: Option<&[Node]>) {
+        self.start_inline();
+        write!(self.output, "[^{number}]").unwrap();
+        self.line_start = false;
+    }
+
+    /// Render footnotes as GFM footnote definitions, like `[^1]: text`
+    fn footnotes(&mut self, _attributes: &[Attribute], footnotes: &[Footnote]) {
+        for footnote in footnotes {
+            self.start_block();
+            write!(self.output, "[^{}]: ", footnote.number).unwrap();
+            self.inline = true;
+            self.line_start = false;
+            self.render_children(&footnote.inner);
+            self.inline = false;
+        }
+    }
+}
+
+/// Whether a node has an equivalent in Markdown
+fn is_representable(node: &Node) -> bool {
+    match node {
+        Node::U { .. }
+        | Node::Mark { .. }
+        | Node::Sub { .. }
+        | Node::Sup { .. }
+        | Node::Kbd { .. }
+        | Node::Abbr { .. }
+        | Node::Q { .. }
+        | Node::Time { .. }
+        | Node::Small { .. }
+        | Node::Ins { .. }
+        | Node::Details { .. }
+        | Node::Div { .. }
+        | Node::Dlist { .. }
+        | Node::Video { .. }
+        | Node::Audio { .. }
+        | Node::Math { .. }
+        | Node::Mathblock { .. } => false,
+        // Code spans can only contain text
+        Node::Mono { inner, .. } => inner.iter().all(|n| text(n).is_some()),
+        // GFM tables have a single header row, and cells that span a single
+        // row and column
+        Node::Table { inner, .. } => inner.iter().all(|part| match part {
+            Node::Thead { inner, .. } => {
+                inner.len() <= 1 && inner.iter().all(|row| is_simple_row(row))
+            }
+            Node::Tfoot { inner, .. } => inner.iter().all(is_simple_row),
+            row => is_simple_row(row),
+        }),
+        _ => true,
+    }
+}
+
+/// Whether no cell of a table row spans several rows or columns
+fn is_simple_row(row: &Node) -> bool {
+    cells(row).is_some_and(|cells| {
+        cells
+            .iter()
+            .all(|cell| cell.attribute("colspan").is_none() && cell.attribute("rowspan").is_none())
+    })
+}
+
+/// The cells of a table row
+fn cells<'a, 's>(row: &'a Node<'s>) -> Option<Vec<&'a Node<'s>>> {
+    match row {
+        Node::Trow { inner, .. } => Some(inner.iter().collect()),
+        _ => None,
+    }
+}
+
+/// The text of a text node
+fn text<'s>(node: &Node<'s>) -> Option<&'s str> {
+    match node {
+        Node::Text { inner } => Some(inner.text),
+        _ => None,
+    }
+}
+
+/// Write text to the output, escaping all characters with a special meaning
+/// in Markdown
+///
+/// At the start of a line, characters that would start a block, like `#` or
+/// `-`, are escaped as well.
+fn escape_into(output: &mut impl Sink, text: &str, line_start: bool) {
+    if line_start {
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &text[digits..];
+
+        if digits == 0 && rest.starts_with(['#', '-', '+', '>', '=']) {
+            output.push('\\');
+        } else if digits > 0 && rest.starts_with(['.', ')']) {
+            output.push_str(&text[..digits]);
+            output.push('\\');
+            return escape_into(output, rest, false);
+        }
+    }
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '|' | '~' => output.push('\\'),
+            // Only `&` starting something that looks like an entity is special
+            '&' if chars
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '#') =>
+            {
+                output.push('\\');
+            }
+            _ => (),
+        }
+        output.push(c);
+    }
+}
@@ -0,0 +1,142 @@
+//! MathML output for formulas
+
+use super::html::escape_into;
+use super::stream::Sink;
+use crate::parse::MathExpr;
+
+/// Write a formula as the content of a `math` element
+///
+/// In `display` mode, the scripts of large operators and some functions are
+/// placed above and below them
+pub(super) fn write_mathml(output: &mut impl Sink, formula: &MathExpr, display: bool) {
+    // `math` elements behave like an `mrow` already
+    match formula {
+        MathExpr::Row(items) => {
+            for item in items {
+                write_expr(output, item, display);
+            }
+        }
+        formula => write_expr(output, formula, display),
+    }
+}
+
+fn write_expr(output: &mut impl Sink, expr: &MathExpr, display: bool) {
+    match expr {
+        MathExpr::Row(items) => {
+            output.push_str("<mrow>");
+            for item in items {
+                write_expr(output, item, display);
+            }
+            output.push_str("</mrow>");
+        }
+        MathExpr::Identifier { name, upright } => {
+            output.push_str(match upright {
+                true => "<mi mathvariant=\"normal\">",
+                false => "<mi>",
+            });
+            escape_into(output, name.encode_utf8(&mut [0; 4]));
+            output.push_str("</mi>");
+        }
+        MathExpr::Function { name, .. } => {
+            // Only single-letter identifiers are italic by default
+            output.push_str(match name.chars().count() {
+                1 => "<mi mathvariant=\"normal\">",
+                _ => "<mi>",
+            });
+            escape_into(output, name);
+            output.push_str("</mi>");
+        }
+        MathExpr::Number(number) => write_token(output, "mn", number),
+        MathExpr::Operator(op) | MathExpr::LargeOperator { op, .. } => {
+            write_token(output, "mo", op.encode_utf8(&mut [0; 4]));
+        }
+        MathExpr::Text(text) => write_token(output, "mtext", text),
+        MathExpr::Space(width) => {
+            output.push_str("<mspace width=\"");
+            output.push_str(width);
+            output.push_str("\"></mspace>");
+        }
+        MathExpr::Fraction {
+            numerator,
+            denominator,
+        } => {
+            output.push_str("<mfrac>");
+            write_expr(output, numerator, display);
+            write_expr(output, denominator, display);
+            output.push_str("</mfrac>");
+        }
+        MathExpr::Root {
+            radicand,
+            index: None,
+        } => {
+            output.push_str("<msqrt>");
+            write_expr(output, radicand, display);
+            output.push_str("</msqrt>");
+        }
+        MathExpr::Root {
+            radicand,
+            index: Some(index),
+        } => {
+            output.push_str("<mroot>");
+            write_expr(output, radicand, display);
+            write_expr(output, index, display);
+            output.push_str("</mroot>");
+        }
+        MathExpr::Scripts { base, sub, sup } => {
+            let limits = display && base.has_limits();
+            let tag = match (sub, sup, limits) {
+                (Some(_), Some(_), false) => "msubsup",
+                (Some(_), None, false) => "msub",
+                (None, Some(_), false) => "msup",
+                (Some(_), Some(_), true) => "munderover",
+                (Some(_), None, true) => "munder",
+                (None, Some(_), true) => "mover",
+                (None, None, _) => return write_expr(output, base, display),
+            };
+
+            output.push('<');
+            output.push_str(tag);
+            output.push('>');
+            write_expr(output, base, display);
+            for script in [sub, sup].into_iter().flatten() {
+                write_expr(output, script, display);
+            }
+            output.push_str("</");
+            output.push_str(tag);
+            output.push('>');
+        }
+        MathExpr::Accent { base, accent } => {
+            output.push_str("<mover accent=\"true\">");
+            write_expr(output, base, display);
+            write_token(output, "mo", accent.encode_utf8(&mut [0; 4]));
+            output.push_str("</mover>");
+        }
+        MathExpr::Fenced { open, inner, close } => {
+            output.push_str("<mrow>");
+            if let Some(open) = open {
+                write_fence(output, *open);
+            }
+            write_expr(output, inner, display);
+            if let Some(close) = close {
+                write_fence(output, *close);
+            }
+            output.push_str("</mrow>");
+        }
+    }
+}
+
+fn write_token(output: &mut impl Sink, tag: &str, content: &str) {
+    output.push('<');
+    output.push_str(tag);
+    output.push('>');
+    escape_into(output, content);
+    output.push_str("</");
+    output.push_str(tag);
+    output.push('>');
+}
+
+fn write_fence(output: &mut impl Sink, fence: char) {
+    output.push_str("<mo fence=\"true\" stretchy=\"true\">");
+    escape_into(output, fence.encode_utf8(&mut [0; 4]));
+    output.push_str("</mo>");
+}
@@ -0,0 +1,37 @@
+//! Output generation for transpiled pages
+
+use std::fmt;
+
+mod css;
+mod html;
+mod latex;
+mod markdown;
+mod mathml;
+mod renderer;
+mod stream;
+
+pub(crate) use css::*;
+pub(crate) use html::*;
+pub(crate) use latex::*;
+pub(crate) use markdown::*;
+pub(crate) use renderer::*;
+pub(crate) use stream::*;
+
+use crate::parse::{Page, Parser};
+use crate::{Config, Format};
+
+/// Render a page to the format of a [`Config`], returning its source map if
+/// one was asked for
+pub(crate) fn render(
+    page: &Page,
+    parser: &Parser,
+    config: &Config,
+    output: impl fmt::Write,
+) -> Result<Option<String>, fmt::Error> {
+    match config.format {
+        Format::Html => HtmlRenderer::render(page, parser, config, output),
+        // Source maps are only generated for HTML
+        Format::Markdown => MarkdownRenderer::render(page, parser, output).map(|()| None),
+        Format::Latex => LatexRenderer::render(page, parser, config, output).map(|()| None),
+    }
+}
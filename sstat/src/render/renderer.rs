@@ -0,0 +1,535 @@
+//! The interface shared by every output format
+
+use crate::bibliography::Reference;
+use crate::parse::*;
+use crate::pass::{Align, Footnote, ListofEntry};
+
+/// An output format pages can be rendered to
+///
+/// Renderers only see pages after every semantic pass has run, so numbers,
+/// labels, citations and ids are already resolved. Each kind of node has a
+/// hook, called by [`Renderer::render_node`] with the parts of the node that
+/// matter for rendering it. Hooks of nodes containing other nodes render
+/// those by default, so a format only needs to handle what it can represent.
+pub(crate) trait Renderer {
+    /// The state shared by every hook
+    fn context(&mut self) -> &mut Context;
+
+    /// Render a single node using the hook of its kind
+    fn render_node(&mut self, node: &Node) {
+        dispatch(self, node);
+    }
+
+    /// Render a list of nodes in order
+    fn render_children(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.render_node(node);
+        }
+    }
+
+    /// Text, which must be escaped as needed by the format
+    fn text(&mut self, text: &str);
+
+    /// A `sec`, while [`Context::heading_level`] is that of its title
+    fn section(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `title`, along with its explicit or generated id, if it has one
+    ///
+    /// Its heading level is [`Context::heading_level`].
+    fn title(&mut self, _attributes: &[Attribute], inner: &[Node], _id: Option<&str>) {
+        self.render_children(inner);
+    }
+
+    /// A `header`, which is a title left out of the table of contents, along
+    /// with its explicit or generated id, if it has one
+    fn header(&mut self, attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+        self.title(attributes, inner, id);
+    }
+
+    /// A `p`
+    fn paragraph(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A node that changes how the text in it looks or what it means, like
+    /// `b` or `abbr`
+    fn inline(&mut self, _kind: Inline, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A callout, like `note` or `warning`
+    fn admonition(&mut self, _kind: Admonition, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `details`, whose summary is its `[summary ...]` attribute
+    fn details(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `div`
+    fn division(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `list`, `ordlist` or `dlist`
+    fn list(&mut self, _kind: ListKind, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `li`, `dterm` or `ddesc`, while [`Context::list`] is the list it is
+    /// in
+    fn list_item(&mut self, _kind: ListItem, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// An `image`, along with its figure number
+    fn image(&mut self, attributes: &[Attribute], number: usize);
+
+    /// A `video` or `audio` clip, along with the MIME type inferred from its
+    /// `[src ...]`
+    ///
+    /// Its children are `source` nodes, which have no hook of their own.
+    fn media(&mut self, kind: Media, attributes: &[Attribute], inner: &[Node], mime: Option<&str>);
+
+    /// A `table`, along with its table number if it has a caption
+    fn table(&mut self, _attributes: &[Attribute], inner: &[Node], _number: Option<usize>) {
+        self.render_children(inner);
+    }
+
+    /// A `thead` or `tfoot`, while [`Context::table_part`] is that part
+    fn table_part(&mut self, _part: TablePart, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `trow`
+    fn table_row(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+        self.render_children(inner);
+    }
+
+    /// A `tcell`, along with the alignment of its column
+    fn table_cell(&mut self, _attributes: &[Attribute], inner: &[Node], _align: Option<Align>) {
+        self.render_children(inner);
+    }
+
+    /// A `codeblock`, along with its listing number if it has a caption
+    fn codeblock(&mut self, attributes: &[Attribute], code: &str, number: Option<usize>);
+
+    /// An `html` node, whose content is HTML
+    fn html(&mut self, html: &str);
+
+    /// A `math` or `mathblock` formula, along with its source in LaTeX
+    /// notation
+    fn math(&mut self, attributes: &[Attribute], formula: &MathExpr, source: &str, display: bool);
+
+    /// A `ref`, along with the label of the node it refers to if it has no
+    /// text of its own
+    fn reference(&mut self, attributes: &[Attribute], inner: &[Node], label: Option<&str>);
+
+    /// A `listof`, along with every node it lists
+    fn listof(&mut self, attributes: &[Attribute], entries: &[ListofEntry]);
+
+    /// A `cite`, along with its label, like `[1]`
+    fn citation(&mut self, attributes: &[Attribute], label: &str);
+
+    /// A `bibliography`, along with every entry it lists
+    fn bibliography(&mut self, attributes: &[Attribute], references: &[Reference]);
+
+    /// A `fnote` or `fnoteref`, along with the number of its footnote and
+    /// the content of that footnote if this is its first reference
+    fn footnote_reference(
+        &mut self,
+        attributes: &[Attribute],
+        number: usize,
+        note: Option<&[Node]>,
+    );
+
+    /// A `fnotes`, along with every footnote it lists
+    fn footnotes(&mut self, attributes: &[Attribute], footnotes: &[Footnote]);
+}
+
+/// The state shared by every hook of a [`Renderer`], kept up to date by
+/// [`Renderer::render_node`]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Context {
+    section_depth: usize,
+    table_part: TablePart,
+    list: Option<ListKind>,
+}
+
+impl Context {
+    /// The level of headings in the section being rendered, starting at 1
+    /// outside of any section
+    pub(crate) fn heading_level(&self) -> usize {
+        self.section_depth + 1
+    }
+
+    /// The part of a table the node being rendered is in
+    pub(crate) fn table_part(&self) -> TablePart {
+        self.table_part
+    }
+
+    /// The innermost list the node being rendered is in, if any
+    pub(crate) fn list(&self) -> Option<ListKind> {
+        self.list
+    }
+}
+
+/// A node that changes how the text in it looks or what it means
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Inline {
+    Bold,
+    Italic,
+    Monospace,
+    Underline,
+    Strikethrough,
+    Mark,
+    Subscript,
+    Superscript,
+    Keyboard,
+    Abbreviation,
+    Quote,
+    Time,
+    Small,
+    Deleted,
+    Inserted,
+    Span,
+}
+
+/// A callout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Admonition {
+    Important,
+    Note,
+    Tip,
+    Caution,
+    Warning,
+}
+
+impl Admonition {
+    /// The name of the node
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Important => "important",
+            Self::Note => "note",
+            Self::Tip => "tip",
+            Self::Caution => "caution",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// A video or audio clip
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Media {
+    Video,
+    Audio,
+}
+
+impl Media {
+    /// The name of the node
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Audio => "audio",
+        }
+    }
+}
+
+/// A list
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ListKind {
+    Unordered,
+    Ordered,
+    Description,
+}
+
+/// An item of a list
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ListItem {
+    /// A `li`
+    Item,
+    /// A `dterm`
+    Term,
+    /// A `ddesc`
+    Description,
+}
+
+/// A part of a table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TablePart {
+    #[default]
+    Body,
+    Head,
+    Foot,
+}
+
+/// Call the hook of a node, keeping the [`Context`] up to date
+///
+/// This is what [`Renderer::render_node`] does by default, for renderers that
+/// do more around every node.
+pub(crate) fn dispatch<R: Renderer + ?Sized>(renderer: &mut R, node: &Node) {
+    match node {
+        Node::Text { inner } => renderer.text(inner.text),
+        Node::Sec {
+            attributes, inner, ..
+        } => {
+            renderer.context().section_depth += 1;
+            renderer.section(attributes, inner);
+            renderer.context().section_depth -= 1;
+        }
+        Node::Title {
+            attributes,
+            inner,
+            slug,
+            ..
+        }
+        | Node::Header {
+            attributes,
+            inner,
+            slug,
+            ..
+        } => {
+            let id = attributes
+                .iter()
+                .rfind(|a| a.attribute_name.name == "id")
+                .map(|a| a.attribute_value.text)
+                .or(slug.as_deref());
+
+            match node {
+                Node::Header { .. } => renderer.header(attributes, inner, id),
+                _ => renderer.title(attributes, inner, id),
+            }
+        }
+        Node::P {
+            attributes, inner, ..
+        } => renderer.paragraph(attributes, inner),
+        Node::B {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Bold, attributes, inner),
+        Node::I {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Italic, attributes, inner),
+        Node::Mono {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Monospace, attributes, inner),
+        Node::U {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Underline, attributes, inner),
+        Node::S {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Strikethrough, attributes, inner),
+        Node::Mark {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Mark, attributes, inner),
+        Node::Sub {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Subscript, attributes, inner),
+        Node::Sup {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Superscript, attributes, inner),
+        Node::Kbd {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Keyboard, attributes, inner),
+        Node::Abbr {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Abbreviation, attributes, inner),
+        Node::Q {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Quote, attributes, inner),
+        Node::Time {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Time, attributes, inner),
+        Node::Small {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Small, attributes, inner),
+        Node::Del {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Deleted, attributes, inner),
+        Node::Ins {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Inserted, attributes, inner),
+        Node::Span {
+            attributes, inner, ..
+        } => renderer.inline(Inline::Span, attributes, inner),
+        Node::Important {
+            attributes, inner, ..
+        } => renderer.admonition(Admonition::Important, attributes, inner),
+        Node::Note {
+            attributes, inner, ..
+        } => renderer.admonition(Admonition::Note, attributes, inner),
+        Node::Tip {
+            attributes, inner, ..
+        } => renderer.admonition(Admonition::Tip, attributes, inner),
+        Node::Caution {
+            attributes, inner, ..
+        } => renderer.admonition(Admonition::Caution, attributes, inner),
+        Node::Warning {
+            attributes, inner, ..
+        } => renderer.admonition(Admonition::Warning, attributes, inner),
+        Node::Details {
+            attributes, inner, ..
+        } => renderer.details(attributes, inner),
+        Node::Div {
+            attributes, inner, ..
+        } => renderer.division(attributes, inner),
+        Node::List {
+            attributes, inner, ..
+        } => render_list(renderer, ListKind::Unordered, attributes, inner),
+        Node::Ordlist {
+            attributes, inner, ..
+        } => render_list(renderer, ListKind::Ordered, attributes, inner),
+        Node::Dlist {
+            attributes, inner, ..
+        } => render_list(renderer, ListKind::Description, attributes, inner),
+        Node::Li {
+            attributes, inner, ..
+        } => renderer.list_item(ListItem::Item, attributes, inner),
+        Node::Dterm {
+            attributes, inner, ..
+        } => renderer.list_item(ListItem::Term, attributes, inner),
+        Node::Ddesc {
+            attributes, inner, ..
+        } => renderer.list_item(ListItem::Description, attributes, inner),
+        Node::Image {
+            attributes, number, ..
+        } => {
+            let number = number.expect("images are numbered before rendering");
+            renderer.image(attributes, number);
+        }
+        Node::Video {
+            attributes,
+            inner,
+            mime,
+            ..
+        } => renderer.media(Media::Video, attributes, inner, *mime),
+        Node::Audio {
+            attributes,
+            inner,
+            mime,
+            ..
+        } => renderer.media(Media::Audio, attributes, inner, *mime),
+        // Sources are rendered by their video or audio clip
+        Node::Source { .. } => (),
+        Node::Table {
+            attributes,
+            inner,
+            number,
+            ..
+        } => renderer.table(attributes, inner, *number),
+        Node::Thead {
+            attributes, inner, ..
+        } => render_table_part(renderer, TablePart::Head, attributes, inner),
+        Node::Tfoot {
+            attributes, inner, ..
+        } => render_table_part(renderer, TablePart::Foot, attributes, inner),
+        Node::Trow {
+            attributes, inner, ..
+        } => renderer.table_row(attributes, inner),
+        Node::Tcell {
+            attributes,
+            inner,
+            align,
+            ..
+        } => renderer.table_cell(attributes, inner, *align),
+        Node::Codeblock {
+            attributes,
+            text,
+            number,
+            ..
+        } => renderer.codeblock(attributes, text.text, *number),
+        Node::Html { text, .. } => renderer.html(text.text),
+        Node::Math {
+            attributes,
+            text,
+            formula,
+            ..
+        } => renderer.math(attributes, formula, text.text, false),
+        Node::Mathblock {
+            attributes,
+            text,
+            formula,
+            ..
+        } => renderer.math(attributes, formula, text.text, true),
+        Node::Ref {
+            attributes,
+            inner,
+            label,
+            ..
+        } => renderer.reference(attributes, inner, label.as_deref()),
+        Node::Listof {
+            attributes,
+            entries,
+            ..
+        } => renderer.listof(attributes, entries),
+        Node::Cite {
+            attributes, label, ..
+        } => {
+            let label = label
+                .as_deref()
+                .expect("citations are resolved before rendering");
+            renderer.citation(attributes, label);
+        }
+        Node::Bibliography {
+            attributes,
+            references,
+            ..
+        } => renderer.bibliography(attributes, references),
+        Node::Fnote {
+            attributes,
+            inner,
+            number,
+            ..
+        } => {
+            let number = number.expect("footnotes are numbered before rendering");
+            renderer.footnote_reference(attributes, number, Some(inner));
+        }
+        Node::Fnoteref {
+            attributes,
+            number,
+            note,
+            ..
+        } => {
+            let number = number.expect("footnotes are numbered before rendering");
+            renderer.footnote_reference(attributes, number, note.as_deref());
+        }
+        Node::Fnotes {
+            attributes,
+            footnotes,
+            ..
+        } => renderer.footnotes(attributes, footnotes),
+        Node::Var { .. }
+        | Node::Defnode { .. }
+        | Node::Slot { .. }
+        | Node::If { .. }
+        | Node::Else { .. }
+        | Node::Fnotedef { .. }
+        | Node::Component { .. } => {
+            unreachable!("`{}` nodes are removed before rendering", node.name())
+        }
+    }
+}
+
+fn render_table_part<R: Renderer + ?Sized>(
+    renderer: &mut R,
+    part: TablePart,
+    attributes: &[Attribute],
+    inner: &[Node],
+) {
+    let outer = std::mem::replace(&mut renderer.context().table_part, part);
+    renderer.table_part(part, attributes, inner);
+    renderer.context().table_part = outer;
+}
+
+fn render_list<R: Renderer + ?Sized>(
+    renderer: &mut R,
+    kind: ListKind,
+    attributes: &[Attribute],
+    inner: &[Node],
+) {
+    let outer = renderer.context().list.replace(kind);
+    renderer.list(kind, attributes, inner);
+    renderer.context().list = outer;
+}
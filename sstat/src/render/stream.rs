@@ -0,0 +1,132 @@
+//! Sinks rendered output is streamed into
+
+use std::fmt;
+use std::io;
+
+/// Something rendered output can be pushed onto
+///
+/// Pushing never fails, so that renderers need not handle errors after every
+/// write. A [`Stream`] keeps the first error of its writer until the end
+/// instead.
+pub(crate) trait Sink {
+    fn push_str(&mut self, text: &str);
+
+    fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+}
+
+impl Sink for String {
+    fn push_str(&mut self, text: &str) {
+        String::push_str(self, text);
+    }
+
+    fn push(&mut self, c: char) {
+        String::push(self, c);
+    }
+}
+
+/// Output written to a [`fmt::Write`] as it is rendered, keeping track of how
+/// much was written and of the line being written
+pub(crate) struct Stream<W> {
+    writer: W,
+    /// The number of bytes written so far
+    len: usize,
+    /// The whitespace the current line consists of, if it is blank so far
+    blank_line: Option<String>,
+    /// The first error of the writer, after which nothing more is written
+    result: fmt::Result,
+}
+
+impl<W: fmt::Write> Stream<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer,
+            len: 0,
+            blank_line: Some(String::new()),
+            result: Ok(()),
+        }
+    }
+
+    /// The number of bytes written so far
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The whitespace the current line consists of, or `None` if it has
+    /// anything else on it
+    pub(crate) fn blank_line(&self) -> Option<&str> {
+        self.blank_line.as_deref()
+    }
+
+    /// Whether the output so far ends in a line break
+    pub(crate) fn ends_with_newline(&self) -> bool {
+        self.len > 0 && self.blank_line() == Some("")
+    }
+
+    /// The writer, or the first error it returned
+    pub(crate) fn finish(self) -> Result<W, fmt::Error> {
+        self.result.map(|()| self.writer)
+    }
+}
+
+impl<W: fmt::Write> Sink for Stream<W> {
+    fn push_str(&mut self, text: &str) {
+        if self.result.is_ok() {
+            self.result = self.writer.write_str(text);
+        }
+        self.len += text.len();
+
+        // Only the last line of the text matters
+        let (line, reset) = match text.rfind('\n') {
+            Some(i) => (&text[i + 1..], true),
+            None => (text, false),
+        };
+        if reset {
+            self.blank_line = Some(String::new());
+        }
+        if let Some(blank_line) = &mut self.blank_line {
+            match line.trim().is_empty() {
+                true => blank_line.push_str(line),
+                false => self.blank_line = None,
+            }
+        }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for Stream<W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.push_str(text);
+
+        Ok(())
+    }
+}
+
+/// Adapts an [`io::Write`] to a [`fmt::Write`], keeping the I/O error that
+/// made a write fail
+pub(crate) struct IoWriter<W> {
+    writer: W,
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.writer.write_all(text.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,69 @@
+//! Ids generated from the text of titles
+
+use std::collections::HashSet;
+
+/// The slug of a piece of text, as used for the id of a title
+///
+/// Letters are lowercased, runs of whitespace and dashes become a single `-`,
+/// and punctuation is dropped, so that `What's new in 2.0?` becomes
+/// `whats-new-in-20`. Letters outside ASCII are kept as they are, apart from
+/// being lowercased. Text without any letters or digits becomes `section`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    let mut separated = false;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if separated && !slug.is_empty() {
+                slug.push('-');
+            }
+            separated = false;
+
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' {
+            separated = true;
+        }
+    }
+
+    match slug.is_empty() {
+        true => "section".to_owned(),
+        false => slug,
+    }
+}
+
+/// Generates slugs that are unique within a page, given the titles of the
+/// page in document order
+///
+/// The first title with a given slug gets it as is, later ones get `-2`,
+/// `-3`, and so on appended, skipping any that are already taken.
+#[derive(Clone, Debug, Default)]
+pub struct Slugger {
+    taken: HashSet<String>,
+}
+
+impl Slugger {
+    /// Create a generator without any ids taken
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark an id as taken, eg. because it was given explicitly
+    pub fn reserve(&mut self, id: &str) {
+        self.taken.insert(id.to_owned());
+    }
+
+    /// The unique slug of the next title, which is then taken
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+
+        let mut slug = base.clone();
+        let mut n = 1;
+        while self.taken.contains(&slug) {
+            n += 1;
+            slug = format!("{base}-{n}");
+        }
+
+        self.taken.insert(slug.clone());
+        slug
+    }
+}
@@ -1,16 +1,99 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod test {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
 
     use codespan_reporting::files::SimpleFile;
 
+    use crate::bibliography::Reference;
     use crate::parse::*;
+    use crate::pass::{Align, Footnote, Layout, ListofEntry, Metadata};
+    use crate::render::{Admonition, Context, Media, Renderer};
+    use crate::{
+        transpile, transpile_into, transpile_to_writer, Classes, Config, Document, Format,
+        ParseErrorType, Severity, Typography,
+    };
+
+    /// The attribute values and text of some nodes, in document order
+    fn flatten(nodes: &[Node]) -> Vec<String> {
+        let mut flat = vec![];
+
+        for node in nodes {
+            for attribute in node.attributes() {
+                flat.push(format!(
+                    "[{} {}]",
+                    attribute.attribute_name.name, attribute.attribute_value.text
+                ));
+            }
+
+            match node {
+                Node::Text { inner } if !inner.text.trim().is_empty() => {
+                    flat.push(inner.text.trim().to_owned())
+                }
+                Node::Sec { inner, .. }
+                | Node::Title { inner, .. }
+                | Node::P { inner, .. }
+                | Node::Defnode { inner, .. }
+                | Node::Component { inner, .. } => flat.extend(flatten(inner)),
+                _ => (),
+            }
+        }
+
+        flat
+    }
 
     fn dummy_parser(src: &str) -> Parser {
         let file = SimpleFile::new("filepath".to_owned(), src.to_owned());
         Parser::new(file)
     }
 
+    /// The default configuration, except that pages are rendered as
+    /// fragments, so that only their `<main>` element is compared
+    fn fragment_config() -> Config {
+        Config {
+            document: Document::Fragment,
+            ..Config::default()
+        }
+    }
+
+    /// Transpile a page with the default configuration, as a fragment
+    fn render(src: &str) -> String {
+        transpile("filepath".to_owned(), src.to_owned(), &fragment_config())
+            .unwrap()
+            .content
+    }
+
+    /// A temporary directory used by a single test, named after the test and
+    /// the process so that concurrent runs never share it, and removed when
+    /// dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+
+            Self(dir)
+        }
+    }
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
     #[test]
 	#[rustfmt::skip]
 	fn parse_page() {
@@ -66,6 +149,9 @@ mod test {
 					rparen: RParen { span: Span { start: 52, end: 53 } },
 					span: Span { start: 33, end: 53 }
 				},
+				library: vec![],
+				metadata: Metadata::default(),
+				layout: Layout::default(),
 				span: Span { start: 0, end: 53 },
 			}
 		);
@@ -148,4 +234,2620 @@ mod test {
         );
         assert_eq!(span, Span { start: 0, end: 20 });
     }
+
+    #[test]
+    #[rustfmt::skip]
+    fn parse_var() {
+        let input = "(var [id v] product) rest";
+
+        let parser = dummy_parser(input);
+        let result = parser.parse_node()(input, 0);
+        assert_matches!(result, Ok(_));
+
+        let (rest, (var, span)) = result.unwrap();
+
+        assert_eq!(rest, " rest");
+        assert_eq!(
+            var,
+            Node::Var {
+                lparen: LParen { span: Span { start: 0, end: 1 } },
+                var: Var { span: Span { start: 1, end: 4 } },
+                attributes: vec![
+                    Attribute {
+                        lbracket: LBracket { span: Span { start: 5, end: 6 } },
+                        attribute_name: Identifier { name: "id", span: Span { start: 6, end: 8 } },
+                        attribute_value: Text { text: "v", span: Span { start: 9, end: 10 } },
+                        rbracket: RBracket { span: Span { start: 10, end: 11 } },
+                        span: Span { start: 5, end: 11 },
+                    }
+                ],
+                name: Identifier { name: "product", span: Span { start: 12, end: 19 } },
+                rparen: RParen { span: Span { start: 19, end: 20 } },
+                span: Span { start: 0, end: 20 },
+            }
+        );
+        assert_eq!(span, Span { start: 0, end: 20 });
+    }
+
+    #[test]
+    fn resolve_variables() {
+        let input = "
+            [let product S-stat]
+
+            (doc [let version 1.0]
+                (sec [let product sstatc] (var product) (var version) (var site))
+                (p (var product))
+            )
+        ";
+
+        let mut config = Config::default();
+        config
+            .variables
+            .insert("site".to_owned(), "example.com".to_owned());
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.resolve_variables(&mut page, &config).unwrap();
+
+        let texts = |node: &Node| -> Vec<String> {
+            match node {
+                Node::Sec { inner, .. } | Node::P { inner, .. } => inner
+                    .iter()
+                    .filter_map(|n| match n {
+                        Node::Text { inner } => Some(inner.text.trim().to_owned()),
+                        _ => None,
+                    })
+                    .filter(|t| !t.is_empty())
+                    .collect(),
+                _ => panic!("expected a sec or p node"),
+            }
+        };
+
+        assert_eq!(
+            texts(&page.doc.nodes[0]),
+            vec!["sstatc", "1.0", "example.com"]
+        );
+        assert_eq!(texts(&page.doc.nodes[1]), vec!["S-stat"]);
+    }
+
+    #[test]
+    fn render_variable_before_text() {
+        let input = "[let x Foo]\n(doc A (var x) text\n\n(var x) text)";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">A Foo text</p>\n\
+             \t<p class=\"__sstat-paragraph\">Foo text</p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn resolve_undefined_variable() {
+        let input = "[let product S-stat] (doc (sec [let other x]) (p (var prodcut)))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        let result = parser.resolve_variables(&mut page, &Config::default());
+        assert_matches!(result, Err(_));
+
+        let error = result.unwrap_err();
+        assert_matches!(error.ty, ParseErrorType::UndefinedVariable { name } if name == "prodcut");
+        assert_eq!(error.span, Span::new(54, 61));
+        assert_eq!(error.context, vec!["did you mean 'product'?"]);
+    }
+
+    #[test]
+    fn parse_unknown_node() {
+        let input = "(doc (sce))";
+
+        let parser = dummy_parser(input);
+        let result = parser.parse();
+        assert_matches!(result, Err(_));
+
+        let error = result.unwrap_err();
+        assert_matches!(error.ty, ParseErrorType::UnknownNode { name } if name == "sce");
+        assert_eq!(error.span, Span::new(6, 9));
+        assert_eq!(error.context, vec!["did you mean 'sec'?"]);
+    }
+
+    #[test]
+    fn parse_config() {
+        let input = "
+            ;; site-wide variables
+            [let product S-stat]
+            [let version 0.1.0]
+        ";
+
+        let config = Config::parse("sstat.cfg".to_owned(), input.to_owned()).unwrap();
+
+        assert_eq!(config.variables.len(), 2);
+        assert_eq!(config.variables["product"], "S-stat");
+        assert_eq!(config.variables["version"], "0.1.0");
+    }
+
+    #[test]
+    fn expand_components() {
+        let input = "
+            (doc
+                (defnode card [params title]
+                    (sec [id card] (title (var title)) (slot) (var title))
+                )
+
+                (card [title first] some (p content))
+                (card [title second])
+            )
+        ";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.expand_components(&mut page).unwrap();
+
+        assert_eq!(
+            flatten(&page.doc.nodes),
+            vec![
+                "[id card-1-card]",
+                "first",
+                "some",
+                "content",
+                "first",
+                "[id card-2-card]",
+                "second",
+                "second",
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_component_references() {
+        // References to nodes in the component are made unique along with
+        // their IDs, while references to the rest of the page are kept
+        let input = "
+            (doc
+                (defnode seefig (p see (ref [ref_id fig-arch])))
+                (defnode figure
+                    (image [id fig] [src a.webp] [alt a] [caption a])
+                    (p (ref [ref_id fig]))
+                )
+
+                (image [id fig-arch] [src arch.webp] [alt diagram] [caption Architecture])
+                (seefig)
+                (figure)
+            )
+        ";
+
+        let output = render(input);
+        assert!(output.contains("see <a class=\"__sstat-ref\" href=\"#fig-arch\">Figure 1</a>"));
+        assert!(output.contains("<figure id=\"figure-1-fig\" class=\"__sstat-figure\">"));
+        assert!(output.contains("<a class=\"__sstat-ref\" href=\"#figure-1-fig\">Figure 2</a>"));
+    }
+
+    #[test]
+    fn expand_component_missing_parameter() {
+        let input = "(doc (defnode card [params title kind] (slot)) (card [title x]))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        let result = parser.expand_components(&mut page);
+        assert_matches!(result, Err(_));
+
+        let error = result.unwrap_err();
+        assert_matches!(error.ty, ParseErrorType::MissingParameter { name } if name == "kind");
+        assert_eq!(error.span, Span::new(48, 52));
+        assert_eq!(error.related.len(), 1);
+        assert_eq!(error.related[0].span, Span::new(14, 18));
+    }
+
+    #[test]
+    fn expand_component_error_locations() {
+        let input = "(doc (defnode inner (p (var nope))) (defnode outer (inner)) (outer))";
+        let span_of = |s: &str| {
+            let start = input.find(s).unwrap();
+            Span::new(start, start + s.len())
+        };
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &fragment_config());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UndefinedVariable { name } if name == "nope");
+        assert_eq!(
+            error.related.iter().map(|r| r.span).collect::<Vec<_>>(),
+            vec![span_of("(inner)"), span_of("(outer)")]
+        );
+    }
+
+    #[test]
+    fn expand_component_warning_locations() {
+        // Each use of a component gets a copy of its own, so diagnostics
+        // point at the use they come from instead of the first one
+        let input = "(doc (defnode card (sec x)) (card) (p (b (card))))";
+        let span_of = |s: &str| {
+            let start = input.find(s).unwrap();
+            Span::new(start, start + s.len())
+        };
+        let second_use = {
+            let start = input.rfind("(card)").unwrap();
+            Span::new(start, start + "(card)".len())
+        };
+
+        let output =
+            transpile("filepath".to_owned(), input.to_owned(), &fragment_config()).unwrap();
+        let [warning] = &output.warnings[..] else {
+            panic!("expected a single warning");
+        };
+        assert_matches!(&warning.ty, ParseErrorType::BlockInInline { block, .. } if block == "sec");
+        assert_eq!(warning.span, span_of("(sec x)"));
+        assert_eq!(
+            warning.related.iter().map(|r| r.span).collect::<Vec<_>>(),
+            vec![second_use]
+        );
+    }
+
+    #[test]
+    fn expand_stray_slot() {
+        let input = "(doc (p (slot)))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        let result = parser.expand_components(&mut page);
+        assert_matches!(result, Err(_));
+        assert_matches!(result.unwrap_err().ty, ParseErrorType::StraySlot);
+    }
+
+    #[test]
+    fn parse_component_before_definition() {
+        let input = "(doc (card) (defnode card))";
+
+        let parser = dummy_parser(input);
+        let result = parser.parse();
+        assert_matches!(result, Err(_));
+        assert_matches!(result.unwrap_err().ty, ParseErrorType::UnknownNode { name } if name == "card");
+    }
+
+    #[test]
+    fn import_components() {
+        let dir = TempDir::new("sstat-import-components");
+        std::fs::write(
+            dir.join("components.sstat"),
+            "(defnode hint [params label] (p (var label): (slot)))",
+        )
+        .unwrap();
+
+        let src = "[import components.sstat]\n(doc (hint [label Note] hello) (hint hi))";
+        let file = SimpleFile::new(dir.join("page.sstat").display().to_string(), src.to_owned());
+        let parser = Parser::new(file);
+        let mut page = parser.parse().unwrap();
+
+        let result = parser.expand_components(&mut page);
+        assert_matches!(result, Err(_));
+
+        // The error is in the page, but the definition is in the library
+        let error = result.unwrap_err();
+        assert_matches!(error.ty, ParseErrorType::MissingParameter { name } if name == "label");
+        assert!(error.source.name().ends_with("page.sstat"));
+        assert_eq!(error.span, Span::new(58, 62));
+        assert!(error.related[0].source.name().ends_with("components.sstat"));
+        assert_eq!(error.related[0].span, Span::new(9, 13));
+
+        let parser = Parser::new(SimpleFile::new(
+            dir.join("page.sstat").display().to_string(),
+            src.replace("(hint hi)", "").to_owned(),
+        ));
+        let mut page = parser.parse().unwrap();
+        parser.expand_components(&mut page).unwrap();
+
+        assert_eq!(flatten(&page.doc.nodes), vec!["Note", ":", "hello"]);
+    }
+
+    #[test]
+    fn evaluate_conditionals() {
+        let input = "
+            (doc
+                (if [profile internal] internal)
+                (else public)
+
+                (if [profile public] [draft] public draft)
+                (else (p not a public draft))
+
+                (sec (if [draft] draft))
+            )
+        ";
+
+        let mut config = Config::default();
+        config
+            .flags
+            .insert("profile".to_owned(), "internal".to_owned());
+        config.flags.insert("draft".to_owned(), "".to_owned());
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.evaluate_conditionals(&mut page, &config).unwrap();
+
+        assert_eq!(
+            flatten(&page.doc.nodes),
+            vec!["internal", "not a public draft", "draft"]
+        );
+    }
+
+    #[test]
+    fn evaluate_stray_else() {
+        let input = "(doc (if [profile internal] a) b (else c))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        let result = parser.evaluate_conditionals(&mut page, &Config::default());
+        assert_matches!(result, Err(_));
+
+        let error = result.unwrap_err();
+        assert_matches!(error.ty, ParseErrorType::StrayElse);
+        assert_eq!(error.span, Span::new(33, 41));
+    }
+
+    #[test]
+    fn parse_config_flags() {
+        let input = "[set profile internal] [set draft]";
+
+        let config = Config::parse("sstat.cfg".to_owned(), input.to_owned()).unwrap();
+
+        assert_eq!(config.flags["profile"], "internal");
+        assert_eq!(config.flags["draft"], "");
+    }
+
+    #[test]
+    fn wrap_paragraphs() {
+        let input = "(doc first (b line)\n  \n second (sec (title t) third) (p fourth))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.wrap_paragraphs(&mut page);
+
+        let nodes = &page.doc.nodes;
+        assert_eq!(nodes.len(), 4);
+        assert_matches!(&nodes[0], Node::P { inner, .. } if inner.len() == 2);
+        assert_matches!(&nodes[1], Node::P { inner, .. } if inner.len() == 1);
+        assert_matches!(&nodes[2], Node::Sec { inner, .. } if matches!(inner[1], Node::P { .. }));
+        assert_matches!(&nodes[3], Node::P { .. });
+
+        assert_eq!(nodes[0].span(), Span::new(5, 19));
+        assert_eq!(
+            flatten(nodes),
+            vec!["first", "second", "t", "third", "fourth"]
+        );
+        assert!(parser.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn warn_block_in_inline() {
+        let input = "(doc (p a (sec b)))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.wrap_paragraphs(&mut page);
+
+        let warnings = parser.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, Severity::Warning);
+        assert_matches!(
+            &warnings[0].ty,
+            ParseErrorType::BlockInInline { block, inline } if block == "sec" && inline == "p"
+        );
+        assert_eq!(warnings[0].span, Span::new(10, 17));
+    }
+
+    #[test]
+    fn normalize_whitespace() {
+        let input = "(doc (p\n\tsome   text\n\twith (b bold)\n\twords\n) (p (i a) (b  b ) c))";
+
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        parser.wrap_paragraphs(&mut page);
+        parser.normalize_whitespace(&mut page);
+
+        let Node::P { inner, .. } = &page.doc.nodes[0] else {
+            panic!("expected a paragraph");
+        };
+        assert_matches!(&inner[0], Node::Text { inner } if inner.text == "some text with");
+        assert_matches!(&inner[1], Node::Text { inner } if inner.text == " ");
+        assert_matches!(&inner[3], Node::Text { inner } if inner.text == " words");
+
+        let Node::P { inner, .. } = &page.doc.nodes[1] else {
+            panic!("expected a paragraph");
+        };
+        assert_matches!(&inner[1], Node::Text { inner } if inner.text == " ");
+        assert_matches!(&inner[2], Node::B { inner, .. } if inner[0] == Node::Text {
+            inner: Text { text: "b", span: Span::new(58, 60) },
+        });
+        assert_matches!(&inner[3], Node::Text { inner } if inner.text == " c");
+    }
+
+    #[test]
+    fn render_doc_examples() {
+        assert_eq!(
+            render("(doc\n\tfoo\n)"),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">foo</p>\n\
+             </main>\n"
+        );
+
+        assert_eq!(
+            render(
+                "(doc\n\
+                 \t(title this will be an h1)\n\n\
+                 \t(sec\n\
+                 \t\t(title this an h2)\n\n\
+                 \t\t(sec\n\
+                 \t\t\t(title and this an h3)\n\
+                 \t\t)\n\
+                 \t)\n\
+                 )"
+            ),
+            "<main class=\"__sstat-main\">\n\
+             \t<h1 class=\"__sstat-title\" id=\"this-will-be-an-h1\">this will be an h1</h1>\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 class=\"__sstat-title\" id=\"this-an-h2\">this an h2</h2>\n\
+             \t\t<section class=\"__sstat-section\">\n\
+             \t\t\t<h3 class=\"__sstat-title\" id=\"and-this-an-h3\">and this an h3</h3>\n\
+             \t\t</section>\n\
+             \t</section>\n\
+             </main>\n"
+        );
+
+        assert_eq!(
+            render(
+                "(doc (p\n\
+                 \ta paragraph with (b (i bold, italic text)) and inline (mono monospace) text\n\
+                 ))"
+            ),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">a paragraph with <b class=\"__sstat-bold\">\
+             <i class=\"__sstat-italic\">bold, italic text</i></b> and inline \
+             <code class=\"__sstat-monospace\">monospace</code> text</p>\n\
+             </main>\n"
+        );
+
+        assert_eq!(
+            render(
+                "(doc (p\n\
+                 \ta paragraph with (u underlined), (s strikethrough), and (mark highlighted)\n\
+                 \ttext.\n\
+                 ))"
+            ),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">a paragraph with \
+             <u class=\"__sstat-underline\">underlined</u>, \
+             <s class=\"__sstat-strikethrough\">strikethrough</s>, and \
+             <mark class=\"__sstat-mark\">highlighted</mark> text.</p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn render_raw_nodes() {
+        assert_eq!(
+            render("(doc (codeblock\n\tfor i in range(10):\n\t\tprint(i)\n))"),
+            "<main class=\"__sstat-main\">\n\
+             \t<pre class=\"__sstat-codeblock\">for i in range(10):\n\
+             \t\tprint(i)\n\
+             </pre>\n\
+             </main>\n"
+        );
+
+        assert_eq!(
+            render("(doc (p a (html <br>)  b))"),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">a <br> b</p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn process_escapes() {
+        assert_eq!(
+            render(r"(doc (p a \(b\) c \\))"),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">a (b) c \\</p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn apply_typography() {
+        let input = r#"[typography smart]
+
+        (doc (p "Quoted" -- it's 10 kg... (mono "as is") and 'this' --- \(see "below"\)))"#;
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">“Quoted” – it’s 10\u{a0}kg… \
+             <code class=\"__sstat-monospace\">&quot;as is&quot;</code> and ‘this’ — \
+             (see “below”)</p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn apply_typography_from_config() {
+        let input = "(doc (p jdu v lese s kamarádem, a to 5 km))";
+
+        let config = Config {
+            typography: Typography::Smart,
+            language: Some("cs-CZ".to_owned()),
+            ..Config::default()
+        };
+        let html = transpile("filepath".to_owned(), input.to_owned(), &config)
+            .unwrap()
+            .content;
+        assert!(html.contains("jdu v\u{a0}lese s\u{a0}kamarádem, a\u{a0}to 5\u{a0}km"));
+
+        // Pages can opt out again
+        let input = format!("[typography plain] {input}");
+        let html = transpile("filepath".to_owned(), input, &config)
+            .unwrap()
+            .content;
+        assert!(html.contains("jdu v lese s kamarádem, a to 5 km"));
+    }
+
+    #[test]
+    fn invalid_typography() {
+        let input = "[typography smrt] (doc)";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidAttributeValue { name, value }
+                if name == "typography" && value == "smrt"
+        );
+        assert_eq!(error.span, Span::new(12, 16));
+        assert_eq!(error.context, vec!["did you mean 'smart'?"]);
+    }
+
+    #[test]
+    fn render_math() {
+        assert_eq!(
+            render(r"(doc (p energy (math E = mc^2)) (mathblock \sum_{i=1}^n i = \frac{n(n+1)}{2}))"),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">energy <math class=\"__sstat-math\">\
+             <mi>E</mi><mo>=</mo><mi>m</mi><msup><mi>c</mi><mn>2</mn></msup></math></p>\n\
+             \t<math class=\"__sstat-math-block\" display=\"block\">\
+             <munderover><mo>∑</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover>\
+             <mi>i</mi><mo>=</mo><mfrac><mrow><mi>n</mi><mo>(</mo><mi>n</mi><mo>+</mo><mn>1</mn>\
+             <mo>)</mo></mrow><mn>2</mn></mfrac></math>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_formula() {
+        let input = r"(doc (math \frac{a}{b} + \alpah))";
+
+        let parser = dummy_parser(input);
+        let error = parser.parse().unwrap_err();
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidFormula { reason } if reason == r"unknown command '\alpah'"
+        );
+        assert_eq!(error.span, Span::new(25, 31));
+        assert_eq!(error.context, vec![r"did you mean '\alpha'?"]);
+
+        let input = "(doc (math x^{2 + y))";
+
+        let parser = dummy_parser(input);
+        let error = parser.parse().unwrap_err();
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidFormula { reason } if reason == "unclosed '{'"
+        );
+        assert_eq!(error.span, Span::new(13, 14));
+    }
+
+    /// A temporary directory containing the bibliography files used by tests
+    fn bibliography_dir(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        std::fs::write(
+            dir.join("refs.bib"),
+            "@string{aw = \"Addison-Wesley\"}\n\
+             @book{knuth84,\n\
+             \tauthor = {Donald E. Knuth},\n\
+             \ttitle = {The {\\TeX}book},\n\
+             \tpublisher = aw,\n\
+             \tyear = 1984,\n\
+             }\n\
+             @article{lamport94,\n\
+             \tauthor = \"Leslie Lamport and {Barnes and Noble}\",\n\
+             \ttitle = {Document Preparation},\n\
+             \tjournal = {Software -- Practice},\n\
+             \tyear = {1994},\n\
+             \tdoi = {10.1000/xyz}\n\
+             }\n\
+             @misc{trap,\n\
+             \ttitle = {Trap},\n\
+             \turl = {javascript:alert(1)},\n\
+             }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("refs.json"),
+            r#"[
+                {"id": "knuth84", "author": [{"family": "Knuth", "given": "Donald E."}],
+                 "title": "The TeXbook", "issued": {"date-parts": [[1984]]}},
+                {"id": "dijkstra68", "author": [{"family": "Dijkstra", "given": "Edsger W."}],
+                 "title": "Go To Statement Considered Harmful", "issued": {"raw": "1968-03"}}
+            ]"#,
+        )
+        .unwrap();
+
+        dir
+    }
+
+    /// Transpile a page in a directory that also contains some bibliography
+    /// files
+    fn transpile_with_bibliography(name: &str, src: &str) -> Result<crate::Output, crate::Error> {
+        let dir = bibliography_dir(name);
+
+        transpile(
+            dir.join("page.sstat").display().to_string(),
+            src.to_owned(),
+            &fragment_config(),
+        )
+    }
+
+    #[test]
+    fn render_numeric_citations() {
+        let input = "[bibliography refs.bib]\n\
+                     (doc (p see (cite [key lamport94]), (cite [key knuth84]) and (cite [key lamport94])) (bibliography))";
+
+        let html = transpile_with_bibliography("sstat-numeric-citations", input)
+            .unwrap()
+            .content;
+        assert_eq!(
+            html,
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">see \
+             <a class=\"__sstat-citation\" href=\"#__sstat-reference-lamport94\">[1]</a>, \
+             <a class=\"__sstat-citation\" href=\"#__sstat-reference-knuth84\">[2]</a> and \
+             <a class=\"__sstat-citation\" href=\"#__sstat-reference-lamport94\">[1]</a></p>\n\
+             \t<ol class=\"__sstat-bibliography\">\n\
+             \t\t<li id=\"__sstat-reference-lamport94\" class=\"__sstat-reference\">\
+             <span class=\"__sstat-reference-label\">[1]</span> L. Lamport and Barnes and Noble. \
+             <cite>Document Preparation</cite>. Software – Practice, 1994. \
+             <a href=\"https://doi.org/10.1000/xyz\">https://doi.org/10.1000/xyz</a></li>\n\
+             \t\t<li id=\"__sstat-reference-knuth84\" class=\"__sstat-reference\">\
+             <span class=\"__sstat-reference-label\">[2]</span> D. E. Knuth. \
+             <cite>The TeXbook</cite>. Addison-Wesley, 1984.</li>\n\
+             \t</ol>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn safe_mode_bibliography() {
+        let dir = bibliography_dir("sstat-safe-bibliography");
+        let config = Config {
+            safe: true,
+            document: Document::Fragment,
+            ..Config::default()
+        };
+        let input = "[bibliography refs.bib]\n\
+                     (doc (p (cite [key trap]) (cite [key lamport94])) (bibliography))";
+
+        let output = transpile(
+            dir.join("page.sstat").display().to_string(),
+            input.to_owned(),
+            &config,
+        )
+        .unwrap();
+        assert!(!output.content.contains("javascript:"));
+        assert!(output
+            .content
+            .contains("<a href=\"https://doi.org/10.1000/xyz\">"));
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [ParseErrorType::UnsafeUrl { url }] if url == "javascript:alert(1)"
+        );
+    }
+
+    #[test]
+    fn render_author_year_citations() {
+        let input = "[bibliography refs.json]\n[citations author-year]\n\
+                     (doc (p see (cite [key knuth84]) and (cite [key dijkstra68])) (bibliography))";
+
+        let html = transpile_with_bibliography("sstat-author-year-citations", input)
+            .unwrap()
+            .content;
+        assert!(html.contains(
+            "<a class=\"__sstat-citation\" href=\"#__sstat-reference-knuth84\">(Knuth, 1984)</a>"
+        ));
+
+        // Entries are listed alphabetically, rather than in citation order
+        assert!(html.contains(
+            "\t<ul class=\"__sstat-bibliography\">\n\
+             \t\t<li id=\"__sstat-reference-dijkstra68\" class=\"__sstat-reference\">\
+             Dijkstra, E. W. (1968). <cite>Go To Statement Considered Harmful</cite>.</li>\n\
+             \t\t<li id=\"__sstat-reference-knuth84\" class=\"__sstat-reference\">\
+             Knuth, D. E. (1984). <cite>The TeXbook</cite>.</li>\n\
+             \t</ul>"
+        ));
+    }
+
+    #[test]
+    fn unknown_citation() {
+        let input = "[bibliography refs.bib]\n(doc (p see (cite [key knuth85])))";
+
+        let result = transpile_with_bibliography("sstat-unknown-citation", input);
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UnknownCitation { key } if key == "knuth85");
+        assert!(error.source.name().ends_with("page.sstat"));
+        assert_eq!(error.span, Span::new(36, 56));
+        assert_eq!(error.context, vec!["did you mean 'knuth84'?"]);
+
+        let input = "(doc (cite [key knuth84]))";
+
+        let result = transpile_with_bibliography("sstat-unknown-citation", input);
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UnknownCitation { key } if key == "knuth84");
+        assert_eq!(error.span, Span::new(5, 25));
+    }
+
+    #[test]
+    fn invalid_bibliography() {
+        let dir = TempDir::new("sstat-invalid-bibliography");
+        std::fs::write(
+            dir.join("refs.bib"),
+            "@book{knuth84,\n\ttitle = publisher,\n}\n",
+        )
+        .unwrap();
+
+        let src = "[bibliography refs.bib]\n(doc)";
+        let result = transpile(
+            dir.join("page.sstat").display().to_string(),
+            src.to_owned(),
+            &fragment_config(),
+        );
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+
+        // The error points into the bibliography file
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidBibliography { reason } if reason == "undefined string 'publisher'"
+        );
+        assert!(error.source.name().ends_with("refs.bib"));
+        assert_eq!(error.span, Span::new(24, 33));
+    }
+
+    #[test]
+    fn render_numbered_nodes() {
+        let input = "
+            (doc
+                (p see (ref [ref_id arch]) and (ref [ref_id hello] the listing))
+                (image [id arch] [src arch.webp] [alt diagram] [caption The architecture])
+                (table [caption Prices]
+                    (thead (trow (tcell Product) (tcell Price)))
+                    (trow (tcell Eggs) (tcell 10))
+                )
+                (table (trow (tcell uncaptioned)))
+                (codeblock [id hello] [caption Hello] hi)
+                (listof [kind table])
+            )
+        ";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">see \
+             <a class=\"__sstat-ref\" href=\"#arch\">Figure 1</a> and \
+             <a class=\"__sstat-ref\" href=\"#hello\">the listing</a></p>\n\
+             \t<figure id=\"arch\" class=\"__sstat-figure\">\n\
+             \t\t<img class=\"__sstat-image\" src=\"arch.webp\" alt=\"diagram\">\n\
+             \t\t<figcaption class=\"__sstat-figure-caption\">\
+             <span class=\"__sstat-caption-label\">Figure 1:</span> The architecture</figcaption>\n\
+             \t</figure>\n\
+             \t<table class=\"__sstat-table\">\n\
+             \t\t<caption class=\"__sstat-table-caption\">\
+             <span class=\"__sstat-caption-label\">Table 1:</span> Prices</caption>\n\
+             \t\t<thead class=\"__sstat-table-head\">\n\
+             \t\t\t<tr class=\"__sstat-table-head-row\">\n\
+             \t\t\t\t<th class=\"__sstat-table-head-cell\">Product</th>\n\
+             \t\t\t\t<th class=\"__sstat-table-head-cell\">Price</th>\n\
+             \t\t\t</tr>\n\
+             \t\t</thead>\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\">Eggs</td>\n\
+             \t\t\t<td class=\"__sstat-table-cell\">10</td>\n\
+             \t\t</tr>\n\
+             \t</table>\n\
+             \t<table class=\"__sstat-table\">\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\">uncaptioned</td>\n\
+             \t\t</tr>\n\
+             \t</table>\n\
+             \t<figure class=\"__sstat-listing\">\n\
+             \t\t<pre id=\"hello\" class=\"__sstat-codeblock\">hi</pre>\n\
+             \t\t<figcaption class=\"__sstat-listing-caption\">\
+             <span class=\"__sstat-caption-label\">Listing 1:</span> Hello</figcaption>\n\
+             \t</figure>\n\
+             \t<ul class=\"__sstat-listof\">\n\
+             \t\t<li class=\"__sstat-listof-entry\">\
+             <a class=\"__sstat-ref\" href=\"#__sstat-table-1\">Table 1</a>: Prices</li>\n\
+             \t</ul>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_references() {
+        let input =
+            "(doc (image [id arch] [src a.webp] [alt a] [caption a]) (p (ref [ref_id acrh])))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UnknownReference { id } if id == "acrh");
+        assert_eq!(error.span, Span::new(59, 78));
+        assert_eq!(error.context[0], "did you mean 'arch'?");
+
+        let input = "(doc (image [src a.webp] [alt a]))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::MissingAttribute { node, name } if node == "image" && name == "caption"
+        );
+
+        let input = "(doc (listof [kind figures]))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidAttributeValue { name, value } if name == "kind" && value == "figures"
+        );
+        assert_eq!(error.context, vec!["did you mean 'figure'?"]);
+    }
+
+    #[test]
+    fn render_details() {
+        assert_eq!(
+            render("(doc (note hello) (warning [collapsible] careful) (details [summary More] [open] hidden))"),
+            "<main class=\"__sstat-main\">\n\
+             \t<div class=\"__sstat-admonition-note\">\n\
+             \t\t<div class=\"__sstat-admonition-label\">note</div>\n\
+             \t\t<div class=\"__sstat-admonition-content\">\n\
+             \t\t\t<p class=\"__sstat-paragraph\">hello</p>\n\
+             \t\t</div>\n\
+             \t</div>\n\
+             \t<details class=\"__sstat-admonition-warning\">\n\
+             \t\t<summary class=\"__sstat-admonition-label\">warning</summary>\n\
+             \t\t<div class=\"__sstat-admonition-content\">\n\
+             \t\t\t<p class=\"__sstat-paragraph\">careful</p>\n\
+             \t\t</div>\n\
+             \t</details>\n\
+             \t<details class=\"__sstat-details\" open>\n\
+             \t\t<summary class=\"__sstat-details-summary\">More</summary>\n\
+             \t\t<div class=\"__sstat-details-content\">\n\
+             \t\t\t<p class=\"__sstat-paragraph\">hidden</p>\n\
+             \t\t</div>\n\
+             \t</details>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn render_lists() {
+        assert_eq!(
+            render(
+                "(doc (list (li one) (li two (ordlist (li nested))))
+                    (dlist (dterm term) (ddesc (p description))))"
+            ),
+            "<main class=\"__sstat-main\">\n\
+             \t<ul class=\"__sstat-unordered-list\">\n\
+             \t\t<li class=\"__sstat-list-item\">one</li>\n\
+             \t\t<li class=\"__sstat-list-item\">\n\
+             \t\t\ttwo\n\
+             \t\t\t<ol class=\"__sstat-ordered-list\">\n\
+             \t\t\t\t<li class=\"__sstat-list-item\">nested</li>\n\
+             \t\t\t</ol>\n\
+             \t\t</li>\n\
+             \t</ul>\n\
+             \t<dl class=\"__sstat-description-list\">\n\
+             \t\t<dt class=\"__sstat-description-term\">term</dt>\n\
+             \t\t<dd class=\"__sstat-description-description\">\n\
+             \t\t\t<p class=\"__sstat-paragraph\">description</p>\n\
+             \t\t</dd>\n\
+             \t</dl>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_lists() {
+        let input = "(doc (list (li one) two))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::UnexpectedChild { parent, child } if parent == "list" && child == "text"
+        );
+        assert_eq!(error.context, vec!["'list' can only contain 'li' nodes"]);
+
+        let input = "(doc (dlist (dterm a) (li b)))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::UnexpectedChild { parent, child } if parent == "dlist" && child == "li"
+        );
+        assert_eq!(error.span, Span::new(22, 28));
+
+        let input = "(doc (p (ddesc a)))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::UnexpectedChild { parent, child } if parent == "p" && child == "ddesc"
+        );
+        assert_eq!(error.context, vec!["'ddesc' can only be inside 'dlist'"]);
+    }
+
+    #[test]
+    fn render_footnotes() {
+        let input = "(doc
+            (sec (p a (fnoteref [ref_id x]) b (fnote inline) c (fnoteref [ref_id x]))
+                (fnotedef [id x] defined (b here))
+                (fnotes))
+            (fnotes)
+            (p d (fnoteref [ref_id y]))
+            (fnotedef [id y] last)
+            (fnotedef [id unused] unused))";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<p class=\"__sstat-paragraph\">a <sup class=\"__sstat-footnote-reference\"><a href=\"#__sstat-footnote-definition-1\">1</a></sup> b <sup class=\"__sstat-footnote-reference\"><a href=\"#__sstat-footnote-definition-2\">2</a></sup> c <sup class=\"__sstat-footnote-reference\"><a href=\"#__sstat-footnote-definition-1\">1</a></sup></p>\n\
+             \t\t<div class=\"__sstat-footnotes\">\n\
+             \t\t\t<div class=\"__sstat-footnotes-header\">Footnotes</div>\n\
+             \t\t\t<div class=\"__sstat-footnotes-definitions\">\n\
+             \t\t\t\t<div class=\"__sstat-footnote-definition\" id=\"__sstat-footnote-definition-1\"><sup class=\"__sstat-footnote-definition-id\">1</sup> defined <b class=\"__sstat-bold\">here</b></div>\n\
+             \t\t\t\t<div class=\"__sstat-footnote-definition\" id=\"__sstat-footnote-definition-2\"><sup class=\"__sstat-footnote-definition-id\">2</sup> inline</div>\n\
+             \t\t\t</div>\n\
+             \t\t</div>\n\
+             \t</section>\n\
+             \t<p class=\"__sstat-paragraph\">d <sup class=\"__sstat-footnote-reference\"><a href=\"#__sstat-footnote-definition-3\">3</a></sup></p>\n\
+             \t<div class=\"__sstat-footnotes\">\n\
+             \t\t<div class=\"__sstat-footnotes-header\">Footnotes</div>\n\
+             \t\t<div class=\"__sstat-footnotes-definitions\">\n\
+             \t\t\t<div class=\"__sstat-footnote-definition\" id=\"__sstat-footnote-definition-3\"><sup class=\"__sstat-footnote-definition-id\">3</sup> last</div>\n\
+             \t\t</div>\n\
+             \t</div>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_footnotes() {
+        let input = "(doc (fnotedef [id note] a) (p (fnoteref [ref_id nite])))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UnknownFootnote { id } if id == "nite");
+        assert_eq!(error.span, Span::new(31, 55));
+        assert_eq!(error.context[0], "did you mean 'note'?");
+
+        let input = "(doc (fnotedef [id note] a) (fnotedef [id note] b))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::DuplicateFootnote { id } if id == "note");
+        assert_eq!(error.span, Span::new(28, 50));
+        assert_eq!(error.related.len(), 1);
+
+        let input = "(doc (p (fnoteref)))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::MissingAttribute { node, name } if node == "fnoteref" && name == "ref_id"
+        );
+    }
+
+    #[test]
+    fn invalid_flag() {
+        let input = "(doc (tip [collapsible ture] hi))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InvalidAttributeValue { name, value }
+                if name == "collapsible" && value == "ture"
+        );
+        assert_eq!(error.span, Span::new(23, 27));
+        assert_eq!(error.context, vec!["did you mean 'true'?"]);
+    }
+
+    #[test]
+    fn render_table_layout() {
+        let input = "
+            (doc (table [align l r c]
+                (trow (tcell [rowspan 2] A) (tcell [colspan 2] [style color: red] B))
+                (trow (tcell C) (tcell D))
+            ))
+        ";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<table class=\"__sstat-table\">\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: left\" rowspan=\"2\">A</td>\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: right; color: red\" colspan=\"2\">B</td>\n\
+             \t\t</tr>\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: right\">C</td>\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: center\">D</td>\n\
+             \t\t</tr>\n\
+             \t</table>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn inconsistent_row_width() {
+        let input = "(doc (table (trow (tcell [colspan 2] a)) (trow (tcell b))))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InconsistentRowWidth {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(error.span, Span::new(41, 57));
+    }
+
+    #[test]
+    fn invalid_cell_span() {
+        for (attribute, value) in [
+            ("colspan", "0"),
+            ("colspan", "1001"),
+            ("colspan", "99999999999999"),
+            ("rowspan", "65535"),
+            ("rowspan", "99999999999999999999999"),
+        ] {
+            let input = format!("(doc (table (trow (tcell [{attribute} {value}] a))))");
+
+            let result = transpile("filepath".to_owned(), input, &Config::default());
+            let Err(crate::Error::ParseError(error)) = result else {
+                panic!("expected a parse error for '{attribute}' {value}");
+            };
+            assert_matches!(
+                &error.ty,
+                ParseErrorType::InvalidAttributeValue { name, value: found }
+                    if name == attribute && found == value
+            );
+        }
+
+        let input = "(doc (table (trow (tcell [colspan 1000] a))))";
+        assert!(transpile("filepath".to_owned(), input.to_owned(), &Config::default()).is_ok());
+    }
+
+    /// Transpile a page in a directory that also contains a CSV file
+    fn transpile_with_csv(name: &str, csv: &str, src: &str) -> Result<crate::Output, crate::Error> {
+        let dir = TempDir::new(name);
+        std::fs::write(dir.join("data.csv"), csv).unwrap();
+
+        transpile(
+            dir.join("page.sstat").display().to_string(),
+            src.to_owned(),
+            &fragment_config(),
+        )
+    }
+
+    #[test]
+    fn render_csv_table() {
+        let output = transpile_with_csv(
+            "sstat-render-csv-table",
+            "Product,Price\r\nEggs,10\r\n\"Milk, \"\"fresh\"\"\",5\r\n",
+            "(doc (table [src data.csv] [header true] [align l r]\n\t(tfoot (trow (tcell Total) (tcell 15)))))",
+        )
+        .unwrap();
+
+        assert_eq!(
+            output.content,
+            "<main class=\"__sstat-main\">\n\
+             \t<table class=\"__sstat-table\">\n\
+             \t\t<thead class=\"__sstat-table-head\">\n\
+             \t\t\t<tr class=\"__sstat-table-head-row\">\n\
+             \t\t\t\t<th class=\"__sstat-table-head-cell\" style=\"text-align: left\">Product</th>\n\
+             \t\t\t\t<th class=\"__sstat-table-head-cell\" style=\"text-align: right\">Price</th>\n\
+             \t\t\t</tr>\n\
+             \t\t</thead>\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: left\">Eggs</td>\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: right\">10</td>\n\
+             \t\t</tr>\n\
+             \t\t<tr class=\"__sstat-table-row\">\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: left\">Milk, &quot;fresh&quot;</td>\n\
+             \t\t\t<td class=\"__sstat-table-cell\" style=\"text-align: right\">5</td>\n\
+             \t\t</tr>\n\
+             \t\t<tfoot class=\"__sstat-table-foot\">\n\
+             \t\t\t<tr class=\"__sstat-table-foot-row\">\n\
+             \t\t\t\t<th class=\"__sstat-table-foot-cell\" style=\"text-align: left\">Total</th>\n\
+             \t\t\t\t<th class=\"__sstat-table-foot-cell\" style=\"text-align: right\">15</th>\n\
+             \t\t\t</tr>\n\
+             \t\t</tfoot>\n\
+             \t</table>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn render_literal_csv_cells() {
+        // Imported cells are data, so they are neither unescaped nor typeset,
+        // unlike cells written in the page
+        let output = transpile_with_csv(
+            "sstat-render-literal-csv-cells",
+            "C:\\\\x\\\\y (\\(1\\)),\"say \"\"hi\"\" -- 'now'\"\r\n",
+            "[typography smart] (doc (table [src data.csv] (trow (tcell \\(\"hi\"\\)) (tcell -))))",
+        )
+        .unwrap();
+
+        assert!(output
+            .content
+            .contains("<td class=\"__sstat-table-cell\">C:\\\\x\\\\y (\\(1\\))</td>"));
+        assert!(output
+            .content
+            .contains("<td class=\"__sstat-table-cell\">say &quot;hi&quot; -- &#39;now&#39;</td>"));
+        assert!(output
+            .content
+            .contains("<td class=\"__sstat-table-cell\">(“hi”)</td>"));
+    }
+
+    #[test]
+    fn invalid_csv() {
+        let result = transpile_with_csv(
+            "sstat-invalid-csv",
+            "a,b\nc,\"d\ne,f,g\n",
+            "(doc (table [src data.csv]))",
+        );
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+
+        // The error points at the unclosed field in the CSV file
+        assert_matches!(&error.ty, ParseErrorType::InvalidCsv { reason } if reason == "unclosed '\"'");
+        assert!(error.source.name().ends_with("data.csv"));
+        assert_eq!(error.span, Span::new(6, 8));
+
+        let result = transpile_with_csv(
+            "sstat-csv-row-width",
+            "a,b\nc,d\ne,f,g\n",
+            "(doc (table [src data.csv]))",
+        );
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::InconsistentRowWidth {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert!(error.source.name().ends_with("data.csv"));
+        assert_eq!(error.span, Span::new(8, 13));
+    }
+
+    #[test]
+    fn render_media() {
+        let dir = TempDir::new("sstat-render-media");
+        std::fs::write(dir.join("demo.webm"), "").unwrap();
+
+        let src = "
+            (doc
+                (video [src demo.webm#t=5] [poster https://example.com/demo.jpg] [caption A demo]
+                    (source [src https://example.com/demo.MP4])
+                    (source [src demo.webm] [type video/webm; codecs=vp9]))
+                (audio [src https://example.com/theme.ogg]))
+        ";
+        let output = transpile(
+            dir.join("page.sstat").display().to_string(),
+            src.to_owned(),
+            &fragment_config(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            output.content,
+            "<main class=\"__sstat-main\">\n\
+             \t<figure class=\"__sstat-video-figure\">\n\
+             \t\t<video class=\"__sstat-video\" controls poster=\"https://example.com/demo.jpg\">\n\
+             \t\t\t<source class=\"__sstat-source\" src=\"demo.webm#t=5\" type=\"video/webm\">\n\
+             \t\t\t<source class=\"__sstat-source\" src=\"https://example.com/demo.MP4\" type=\"video/mp4\">\n\
+             \t\t\t<source class=\"__sstat-source\" src=\"demo.webm\" type=\"video/webm; codecs=vp9\">\n\
+             \t\t</video>\n\
+             \t\t<figcaption class=\"__sstat-video-caption\">A demo</figcaption>\n\
+             \t</figure>\n\
+             \t<figure class=\"__sstat-audio-figure\">\n\
+             \t\t<audio class=\"__sstat-audio\" controls>\n\
+             \t\t\t<source class=\"__sstat-source\" src=\"https://example.com/theme.ogg\" type=\"audio/ogg\">\n\
+             \t\t</audio>\n\
+             \t</figure>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_media() {
+        let input = "(doc (video [src missing.mp4]))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::MissingFile { path } if path == "missing.mp4");
+        assert_eq!(error.span, Span::new(17, 28));
+
+        // Paths from the root of the site are not relative to the page, so
+        // they are not checked, even in safe mode
+        let input = "(doc (video [src /media/clip.mp4] [poster /media/clip.jpg]))";
+        for safe in [false, true] {
+            let config = Config {
+                safe,
+                ..fragment_config()
+            };
+            assert!(transpile("filepath".to_owned(), input.to_owned(), &config).is_ok());
+        }
+
+        let input = "(doc (p (source [src https://example.com/a.mp3])))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::UnexpectedChild { parent, child } if parent == "p" && child == "source"
+        );
+        assert_eq!(error.span, Span::new(8, 48));
+    }
+
+    #[test]
+    fn render_semantic_inline_nodes() {
+        let input = "
+            (doc (p
+                press (kbd Ctrl) + (kbd C) to copy (abbr [title HyperText Markup Language] HTML),
+                (q [cite https://example.com] quoted) on (time [datetime 2026-10-17T14:30Z] Saturday),
+                (small fine print), (del [datetime 2026-10-17] old) (ins new)
+            ))
+        ";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">press <kbd class=\"__sstat-keyboard\">Ctrl</kbd> + \
+             <kbd class=\"__sstat-keyboard\">C</kbd> to copy \
+             <abbr class=\"__sstat-abbreviation\" title=\"HyperText Markup Language\">HTML</abbr>, \
+             <q class=\"__sstat-quote\" cite=\"https://example.com\">quoted</q> on \
+             <time class=\"__sstat-time\" datetime=\"2026-10-17T14:30Z\">Saturday</time>, \
+             <small class=\"__sstat-small\">fine print</small>, \
+             <del class=\"__sstat-deleted\" datetime=\"2026-10-17\">old</del> \
+             <ins class=\"__sstat-inserted\">new</ins></p>\n\
+             </main>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_semantic_inline_nodes() {
+        let input = "(doc (abbr HTML))";
+
+        let result = transpile("filepath".to_owned(), input.to_owned(), &Config::default());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(
+            &error.ty,
+            ParseErrorType::MissingAttribute { node, name } if node == "abbr" && name == "title"
+        );
+        assert_eq!(error.span, Span::new(5, 16));
+
+        for datetime in ["2026-13-01", "25:00", "2026-10-17T14", "17/10/2026"] {
+            let input = format!("(doc (ins [datetime {datetime}] new))");
+
+            let result = transpile("filepath".to_owned(), input, &Config::default());
+            let Err(crate::Error::ParseError(error)) = result else {
+                panic!("expected a parse error for '{datetime}'");
+            };
+            assert_matches!(
+                &error.ty,
+                ParseErrorType::InvalidAttributeValue { name, value }
+                    if name == "datetime" && value == datetime
+            );
+        }
+
+        for datetime in ["2026", "2026-10", "14:30:05.250", "2026-10-17 14:30+02:00"] {
+            let input = format!("(doc (time [datetime {datetime}] now))");
+            assert!(transpile("filepath".to_owned(), input, &Config::default()).is_ok());
+        }
+    }
+
+    #[test]
+    fn render_class_prefix() {
+        let input = "
+            (doc
+                (p [class lead] some (b bold) text)
+                (tip [collapsible] hi)
+                (image [src a.webp] [alt a] [caption An image])
+            )
+        ";
+
+        let prefixed = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[class_prefix docs-]\n[document fragment]".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(prefixed.classes, Classes::Prefixed("docs-".to_owned()));
+
+        let output = transpile("filepath".to_owned(), input.to_owned(), &prefixed).unwrap();
+        assert_eq!(
+            output.content,
+            "<main class=\"docs-main\">\n\
+             \t<p class=\"docs-paragraph lead\">some <b class=\"docs-bold\">bold</b> text</p>\n\
+             \t<details class=\"docs-admonition-tip\">\n\
+             \t\t<summary class=\"docs-admonition-label\">tip</summary>\n\
+             \t\t<div class=\"docs-admonition-content\">\n\
+             \t\t\t<p class=\"docs-paragraph\">hi</p>\n\
+             \t\t</div>\n\
+             \t</details>\n\
+             \t<figure class=\"docs-figure\" id=\"docs-figure-1\">\n\
+             \t\t<img class=\"docs-image\" src=\"a.webp\" alt=\"a\">\n\
+             \t\t<figcaption class=\"docs-figure-caption\">\
+             <span class=\"docs-caption-label\">Figure 1:</span> An image</figcaption>\n\
+             \t</figure>\n\
+             </main>\n"
+        );
+
+        let none = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[class_prefix none]\n[document fragment]".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(none.classes, Classes::None);
+
+        let output = transpile("filepath".to_owned(), input.to_owned(), &none).unwrap();
+        assert_eq!(
+            output.content,
+            "<main>\n\
+             \t<p class=\"lead\">some <b>bold</b> text</p>\n\
+             \t<details>\n\
+             \t\t<summary>tip</summary>\n\
+             \t\t<div>\n\
+             \t\t\t<p>hi</p>\n\
+             \t\t</div>\n\
+             \t</details>\n\
+             \t<figure id=\"figure-1\">\n\
+             \t\t<img src=\"a.webp\" alt=\"a\">\n\
+             \t\t<figcaption><span>Figure 1:</span> An image</figcaption>\n\
+             \t</figure>\n\
+             </main>\n"
+        );
+
+        // Slugs never take the ids generated for numbered nodes
+        let input = "(doc (sec (title Figure 1)) (p a (fnote b)) (fnotes) (image [src a.webp] [alt a] [caption c]))";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &none).unwrap();
+        assert!(output
+            .content
+            .contains("<h2 id=\"figure-1-2\">Figure 1</h2>"));
+        assert!(output
+            .content
+            .contains("<a href=\"#footnote-definition-1\">1</a>"));
+        assert!(output
+            .content
+            .contains("<div id=\"footnote-definition-1\">"));
+        assert!(output.content.contains("<figure id=\"figure-1\">"));
+    }
+
+    /// The names of all nodes in a tree, including the nodes themselves
+    fn collect_node_names<'s>(nodes: &mut [Node<'s>], names: &mut Vec<&'s str>) {
+        for node in nodes {
+            names.push(node.name());
+
+            if let Some(children) = node.children_mut() {
+                collect_node_names(children, names);
+            }
+        }
+    }
+
+    #[test]
+    fn stylesheet_covers_every_class() {
+        let input = "
+            [bibliography refs.bib]
+            [let version 1.0]
+            [layout page]
+
+            (doc
+                (defnode badge (b (slot)))
+                (sec (title Everything (badge new)) (header header)
+                    (p (b b) (i i) (mono mono) (u u) (s s) (mark mark) (sub sub) (sup sup)
+                        (kbd kbd) (abbr [title abbreviation] abbr) (q q)
+                        (time [datetime 2026-10-17] time) (small small) (del del) (ins ins)
+                        (math x^2) (var version) (ref [ref_id __sstat-figure-1])
+                        (cite [key knuth84]) (if [flag draft] draft) (else final)
+                        (span span) (fnote fnote) (fnoteref [ref_id note]))
+                    (fnotedef [id note] fnotedef)
+                    (fnotes)
+                    (html <hr>)
+                    (mathblock x^2)
+                    (important important) (note note) (tip [collapsible] tip)
+                    (caution caution) (warning warning)
+                    (details [summary more] details)
+                    (div div)
+                    (list (li li)) (ordlist (li li)) (dlist (dterm dterm) (ddesc ddesc))
+                    (image [src a.webp] [alt a] [caption image])
+                    (video [src https://example.com/a.webp] [caption video]
+                        (source [src https://example.com/a.mp4]))
+                    (audio [src https://example.com/a.mp3] [caption audio])
+                    (table [caption table]
+                        (thead (trow (tcell head)))
+                        (trow (tcell body))
+                        (tfoot (trow (tcell foot))))
+                    (codeblock [caption listing] code)
+                    (listof [kind figure])
+                    (bibliography)))
+        ";
+
+        // The page uses every built-in node, so that it renders every class
+        let parser = dummy_parser(input);
+        let mut page = parser.parse().unwrap();
+        let mut names = vec![];
+        collect_node_names(&mut page.doc.nodes, &mut names);
+        for name in builtin_node_names() {
+            assert!(names.contains(&name), "'{name}' is not used");
+        }
+
+        // The page is rendered in full, into a layout with a table of contents
+        // and site navigation, so that their classes are covered too
+        let dir = bibliography_dir("sstat-stylesheet");
+        let config = layout_config(
+            &dir,
+            &[(
+                "page",
+                "{{ extends default }}\n{{ nav }}\n{{ toc }}\n{{ body }}\n",
+            )],
+        );
+        let html = transpile(
+            dir.join("page.sstat").display().to_string(),
+            input.to_owned(),
+            &config,
+        )
+        .unwrap()
+        .content;
+        assert!(html.contains("<nav class=\"__sstat-nav\">"));
+        assert!(html.contains("<nav class=\"__sstat-toc\">"));
+        let css = crate::stylesheet(&Config::default());
+
+        let classes = html
+            .split("class=\"")
+            .skip(1)
+            .flat_map(|rest| rest.split('"').next().unwrap().split(' '));
+        for class in classes {
+            let selector = format!(".{class}");
+            let is_styled = css
+                .match_indices(&selector)
+                .any(|(idx, _)| css[idx + selector.len()..].starts_with([' ', ',', ':', '\n']));
+
+            assert!(is_styled, "'{class}' is not styled");
+        }
+    }
+
+    #[test]
+    fn stylesheet_from_config() {
+        let input = "
+            [class_prefix docs-]
+            [theme font_body Georgia, serif]
+            [dark_theme accent #3fc58d]
+        ";
+
+        let config = Config::parse("sstat.cfg".to_owned(), input.to_owned()).unwrap();
+        let css = crate::stylesheet(&config);
+
+        assert!(css.contains(".docs-paragraph {"));
+        assert!(!css.contains(".__sstat-"));
+        assert!(css.ends_with(
+            ":root {\n\
+             \t--sstat-font-body: Georgia, serif;\n\
+             }\n\
+             \n\
+             /* Dark theme overrides */\n\
+             \n\
+             @media (prefers-color-scheme: dark) {\n\
+             \t:root {\n\
+             \t\t--sstat-accent: #3fc58d;\n\
+             \t}\n\
+             }\n"
+        ));
+
+        let result = Config::parse("sstat.cfg".to_owned(), "[theme acent red]".to_owned());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::UnknownThemeProperty { name } if name == "acent");
+        assert_eq!(error.span, Span::new(7, 12));
+        assert_eq!(error.context, vec!["did you mean 'accent'?"]);
+
+        let result = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[theme text red; color: blue]".to_owned(),
+        );
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::InvalidAttributeValue { name, .. } if name == "text");
+    }
+
+    #[test]
+    fn render_document() {
+        let input = "
+            [author Ada & Bob]
+            [description A \"short\" page]
+            [stylesheet page.css]
+
+            (doc (sec (title The (i first) title) (sec (title The second title))))
+        ";
+
+        let config = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[lang en]\n[stylesheet /sstat.css]".to_owned(),
+        )
+        .unwrap();
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<!DOCTYPE html>\n\
+             <html lang=\"en\">\n\
+             <head>\n\
+             \t<meta charset=\"utf-8\">\n\
+             \t<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             \t<title>The first title</title>\n\
+             \t<meta name=\"author\" content=\"Ada &amp; Bob\">\n\
+             \t<meta name=\"description\" content=\"A &quot;short&quot; page\">\n\
+             \t<link rel=\"stylesheet\" href=\"/sstat.css\">\n\
+             \t<link rel=\"stylesheet\" href=\"page.css\">\n\
+             \t<meta property=\"og:type\" content=\"article\">\n\
+             \t<meta property=\"og:title\" content=\"The first title\">\n\
+             \t<meta property=\"og:description\" content=\"A &quot;short&quot; page\">\n\
+             \t<meta name=\"twitter:card\" content=\"summary\">\n\
+             \t<meta name=\"twitter:title\" content=\"The first title\">\n\
+             \t<meta name=\"twitter:description\" content=\"A &quot;short&quot; page\">\n\
+             \t<script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\
+             \"@type\":\"Article\",\"author\":{\"@type\":\"Person\",\"name\":\"Ada & Bob\"},\
+             \"description\":\"A \\\"short\\\" page\",\"headline\":\"The first title\"}</script>\n\
+             </head>\n\
+             <body>\n\
+             \t<main class=\"__sstat-main\">\n\
+             \t\t<section class=\"__sstat-section\">\n\
+             \t\t\t<h2 class=\"__sstat-title\" id=\"the-first-title\">The <i class=\"__sstat-italic\">first</i> title</h2>\n\
+             \t\t\t<section class=\"__sstat-section\">\n\
+             \t\t\t\t<h3 class=\"__sstat-title\" id=\"the-second-title\">The second title</h3>\n\
+             \t\t\t</section>\n\
+             \t\t</section>\n\
+             \t</main>\n\
+             </body>\n\
+             </html>\n"
+        );
+
+        // A title attribute takes precedence over title nodes
+        let input = "[title Custom] [lang cs] (doc (title ignored))";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert!(output.content.contains("<html lang=\"cs\">"));
+        assert!(output.content.contains("<title>Custom</title>"));
+
+        // Without any metadata, the head is minimal
+        let html = transpile(
+            "filepath".to_owned(),
+            "(doc)".to_owned(),
+            &Config::default(),
+        )
+        .unwrap()
+        .content;
+        assert_eq!(
+            html,
+            "<!DOCTYPE html>\n\
+             <html>\n\
+             <head>\n\
+             \t<meta charset=\"utf-8\">\n\
+             \t<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             </head>\n\
+             <body>\n\
+             \t<main class=\"__sstat-main\"></main>\n\
+             </body>\n\
+             </html>\n"
+        );
+    }
+
+    #[test]
+    fn invalid_document() {
+        let result = Config::parse("sstat.cfg".to_owned(), "[document fragmnt]".to_owned());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::InvalidAttributeValue { name, .. } if name == "document");
+        assert_eq!(error.span, Span::new(10, 17));
+        assert_eq!(error.context, vec!["did you mean 'fragment'?"]);
+    }
+
+    /// A configuration reading layouts from a directory, after writing the
+    /// given layout files into it
+    fn layout_config(dir: &TempDir, layouts: &[(&str, &str)]) -> Config {
+        for (name, source) in layouts {
+            std::fs::write(dir.join(format!("{name}.html")), source).unwrap();
+        }
+
+        Config {
+            layouts: Some(dir.to_path_buf()),
+            nav: vec![("Home".to_owned(), "/index.html".to_owned())],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn render_layouts() {
+        let dir = TempDir::new("sstat-layouts");
+        let config = layout_config(
+            &dir,
+            &[
+                (
+                    "base",
+                    "<html lang=\"{{ lang }}\">\n\
+                     <head>\n\
+                     \t{{head}}\n\
+                     </head>\n\
+                     <body>\n\
+                     \t{{ nav }}\n\
+                     {{ body }}\n\
+                     </body>\n\
+                     </html>\n",
+                ),
+                (
+                    "post",
+                    "\n{{ extends base }}\n\
+                     <article>\n\
+                     {{ toc }}\n\
+                     <p>By {{ author }}</p>\n\
+                     {{ body }}\n\
+                     </article>\n",
+                ),
+                ("card", "{{ extends default }}\n<div>{{ body }}</div>\n"),
+            ],
+        );
+
+        let input = "
+            [layout post]
+            [author Ada & Bob]
+            [lang en]
+
+            (doc (sec (title [id intro] Intro) (sec (title Deeper) (header Aside))) (sec (title Outro)))
+        ";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<html lang=\"en\">\n\
+             <head>\n\
+             \t<meta charset=\"utf-8\">\n\
+             \t<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             \t<title>Intro</title>\n\
+             \t<meta name=\"author\" content=\"Ada &amp; Bob\">\n\
+             \t<meta property=\"og:type\" content=\"article\">\n\
+             \t<meta property=\"og:title\" content=\"Intro\">\n\
+             \t<meta name=\"twitter:card\" content=\"summary\">\n\
+             \t<meta name=\"twitter:title\" content=\"Intro\">\n\
+             \t<script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\
+             \"@type\":\"Article\",\"author\":{\"@type\":\"Person\",\"name\":\"Ada & Bob\"},\
+             \"headline\":\"Intro\"}</script>\n\
+             </head>\n\
+             <body>\n\
+             \t<nav class=\"__sstat-nav\">\n\
+             \t\t<ul class=\"__sstat-nav-list\">\n\
+             \t\t\t<li class=\"__sstat-nav-entry\">\
+             <a class=\"__sstat-nav-link\" href=\"/index.html\">Home</a></li>\n\
+             \t\t</ul>\n\
+             \t</nav>\n\
+             <article>\n\
+             <nav class=\"__sstat-toc\">\n\
+             \t<ol class=\"__sstat-toc-list\">\n\
+             \t\t<li class=\"__sstat-toc-entry\">\
+             <a class=\"__sstat-toc-link\" href=\"#intro\">Intro</a>\n\
+             \t\t\t<ol class=\"__sstat-toc-list\">\n\
+             \t\t\t\t<li class=\"__sstat-toc-entry\"><a class=\"__sstat-toc-link\" href=\"#deeper\">Deeper</a></li>\n\
+             \t\t\t</ol>\n\
+             \t\t</li>\n\
+             \t\t<li class=\"__sstat-toc-entry\"><a class=\"__sstat-toc-link\" href=\"#outro\">Outro</a></li>\n\
+             \t</ol>\n\
+             </nav>\n\
+             <p>By Ada &amp; Bob</p>\n\
+             <main class=\"__sstat-main\">\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 id=\"intro\" class=\"__sstat-title\">Intro</h2>\n\
+             \t\t<section class=\"__sstat-section\">\n\
+             \t\t\t<h3 class=\"__sstat-title\" id=\"deeper\">Deeper</h3>\n\
+             \t\t\t<h3 class=\"__sstat-header\" id=\"aside\">Aside</h3>\n\
+             \t\t</section>\n\
+             \t</section>\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 class=\"__sstat-title\" id=\"outro\">Outro</h2>\n\
+             \t</section>\n\
+             </main>\n\
+             </article>\n\
+             </body>\n\
+             </html>\n"
+        );
+
+        // Layouts can extend the built-in default layout
+        let input = "[layout card] (doc hi)";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<!DOCTYPE html>\n\
+             <html>\n\
+             <head>\n\
+             \t<meta charset=\"utf-8\">\n\
+             \t<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             </head>\n\
+             <body>\n\
+             \t<div><main class=\"__sstat-main\">\n\
+             \t<p class=\"__sstat-paragraph\">hi</p>\n\
+             </main></div>\n\
+             </body>\n\
+             </html>\n"
+        );
+
+        // The default layout is built in, but can be overridden
+        let input = "[layout default] (doc)";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert!(output.content.starts_with("<!DOCTYPE html>\n"));
+
+        let dir = TempDir::new("sstat-layouts-default");
+        let config = layout_config(&dir, &[("default", "{{ body }}")]);
+        let output = transpile("filepath".to_owned(), "(doc)".to_owned(), &config).unwrap();
+        assert_eq!(output.content, "<main class=\"__sstat-main\"></main>\n");
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        let dir = TempDir::new("sstat-invalid-layouts");
+        let config = layout_config(
+            &dir,
+            &[
+                ("post", "{{ extends base }}"),
+                ("loop", "{{ extends cycle }}"),
+                ("cycle", "{{ extends loop }}"),
+                ("typo", "<p>{{ bdy }}</p>"),
+                ("late", "<p></p>{{ extends default }}"),
+                ("unclosed", "<p>{{ body</p>"),
+            ],
+        );
+
+        let error_for = |input: &str| {
+            let result = transpile("filepath".to_owned(), input.to_owned(), &config);
+            let Err(crate::Error::ParseError(error)) = result else {
+                panic!("expected a parse error");
+            };
+            error
+        };
+
+        let error = error_for("[layout psot] (doc)");
+        assert_matches!(&error.ty, ParseErrorType::UnknownLayout { name } if name == "psot");
+        assert_eq!(error.span, Span::new(8, 12));
+        assert_eq!(error.context[1], "did you mean 'post'?");
+
+        // Errors in layouts point into the layout files
+        let error = error_for("[layout post] (doc)");
+        assert_matches!(&error.ty, ParseErrorType::UnknownLayout { name } if name == "base");
+        assert!(error.source.name().ends_with("post.html"));
+        assert_eq!(error.span, Span::new(11, 15));
+
+        let error = error_for("[layout loop] (doc)");
+        assert_matches!(&error.ty, ParseErrorType::RecursiveLayout { name } if name == "loop");
+
+        let error = error_for("[layout typo] (doc)");
+        assert_matches!(&error.ty, ParseErrorType::UnknownPlaceholder { name } if name == "bdy");
+        assert_eq!(error.context, vec!["did you mean 'body'?"]);
+
+        let error = error_for("[layout late] (doc)");
+        assert_matches!(error.ty, ParseErrorType::MisplacedExtends);
+
+        let error = error_for("[layout unclosed] (doc)");
+        assert_matches!(&error.ty, ParseErrorType::UnexpectedEof { expected } if expected == "}}");
+    }
+
+    #[test]
+    fn render_social_metadata() {
+        let input = "
+            [title Hello </script>]
+            [date 2026-10-17T14:30Z]
+            [cover /images/cover.webp]
+
+            (doc)
+        ";
+
+        let config = Config {
+            base_url: Some("https://example.com/blog/".to_owned()),
+            ..Config::default()
+        };
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert!(output.warnings.is_empty());
+        assert!(output.content.contains(
+            "\t<meta property=\"og:type\" content=\"article\">\n\
+             \t<meta property=\"og:title\" content=\"Hello &lt;/script&gt;\">\n\
+             \t<meta property=\"og:image\" content=\"https://example.com/blog/images/cover.webp\">\n\
+             \t<meta property=\"article:published_time\" content=\"2026-10-17T14:30Z\">\n\
+             \t<meta name=\"twitter:card\" content=\"summary_large_image\">\n\
+             \t<meta name=\"twitter:title\" content=\"Hello &lt;/script&gt;\">\n\
+             \t<meta name=\"twitter:image\" content=\"https://example.com/blog/images/cover.webp\">\n\
+             \t<script type=\"application/ld+json\">{\"@context\":\"https://schema.org\",\
+             \"@type\":\"Article\",\"datePublished\":\"2026-10-17T14:30Z\",\
+             \"headline\":\"Hello \\u003c/script>\",\
+             \"image\":\"https://example.com/blog/images/cover.webp\"}</script>\n"
+        ));
+
+        // Without a base URL, covers stay relative
+        let output =
+            transpile("filepath".to_owned(), input.to_owned(), &Config::default()).unwrap();
+        assert!(output
+            .content
+            .contains("<meta property=\"og:image\" content=\"/images/cover.webp\">"));
+        assert_eq!(output.warnings.len(), 1);
+        assert_matches!(&output.warnings[0].ty, ParseErrorType::RelativeUrl { url } if url == "/images/cover.webp");
+
+        // Absolute covers are used as is
+        let input = "[title a] [cover https://cdn.example.com/a.png] (doc)";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert!(output
+            .content
+            .contains("<meta property=\"og:image\" content=\"https://cdn.example.com/a.png\">"));
+
+        let input = "[date 14:30] (doc)";
+        let result = transpile("filepath".to_owned(), input.to_owned(), &config);
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::InvalidAttributeValue { name, .. } if name == "date");
+        assert_eq!(error.span, Span::new(6, 11));
+    }
+
+    #[test]
+    fn slugify() {
+        assert_eq!(crate::slugify("Getting Started"), "getting-started");
+        assert_eq!(crate::slugify("What's new in 2.0?"), "whats-new-in-20");
+        assert_eq!(crate::slugify("  C++ -- and   Rust  "), "c-and-rust");
+        assert_eq!(
+            crate::slugify("Příliš Žluťoučký kůň"),
+            "příliš-žluťoučký-kůň"
+        );
+        assert_eq!(crate::slugify("snake_case\u{a0}names"), "snake_case-names");
+        assert_eq!(crate::slugify("?!"), "section");
+
+        let mut slugger = crate::Slugger::new();
+        slugger.reserve("intro-2");
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("intro"), "intro-3");
+        assert_eq!(slugger.slug("Intro!"), "intro-4");
+    }
+
+    #[test]
+    fn parse_non_ascii() {
+        // Tags must not split characters when they do not match
+        let output = render("(doc (title Héllo wörld) (sec (title éllo) (p é)))");
+        assert!(output.contains("<h1 class=\"__sstat-title\" id=\"héllo-wörld\">Héllo wörld</h1>"));
+        assert!(output.contains("<h2 class=\"__sstat-title\" id=\"éllo\">éllo</h2>"));
+        assert!(output.contains("<p class=\"__sstat-paragraph\">é</p>"));
+    }
+
+    #[test]
+    fn render_headers() {
+        let input =
+            "[heading_anchors] (doc (title Intro) (sec (header Intro) (header [id aside] Aside)))";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<h1 class=\"__sstat-title\" id=\"intro\">Intro \
+             <a class=\"__sstat-heading-anchor\" href=\"#intro\">¶</a></h1>\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 class=\"__sstat-header\" id=\"intro-2\">Intro \
+             <a class=\"__sstat-heading-anchor\" href=\"#intro-2\">¶</a></h2>\n\
+             \t\t<h2 id=\"aside\" class=\"__sstat-header\">Aside \
+             <a class=\"__sstat-heading-anchor\" href=\"#aside\">¶</a></h2>\n\
+             \t</section>\n\
+             </main>\n"
+        );
+
+        let config = Config {
+            format: Format::Latex,
+            ..Config::default()
+        };
+        let input = "(doc (header Top) (sec (title Intro) (header [id a b] Aside)))";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert!(output
+            .content
+            .contains("\\section*{Top}\\phantomsection\\label{top}\n\n\\section{Intro}"));
+        assert!(output
+            .content
+            .contains("\\section*{Aside}\\phantomsection\\label{a+20b}"));
+    }
+
+    #[test]
+    fn render_heading_anchors() {
+        let input = "
+            [heading_anchors]
+
+            (doc
+                (title Intro)
+                (sec (title Intro) (p [id intro-2] hi))
+                (sec (title [id custom] Custom)))
+        ";
+
+        assert_eq!(
+            render(input),
+            "<main class=\"__sstat-main\">\n\
+             \t<h1 class=\"__sstat-title\" id=\"intro\">Intro \
+             <a class=\"__sstat-heading-anchor\" href=\"#intro\">¶</a></h1>\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 class=\"__sstat-title\" id=\"intro-3\">Intro \
+             <a class=\"__sstat-heading-anchor\" href=\"#intro-3\">¶</a></h2>\n\
+             \t\t<p id=\"intro-2\" class=\"__sstat-paragraph\">hi</p>\n\
+             \t</section>\n\
+             \t<section class=\"__sstat-section\">\n\
+             \t\t<h2 id=\"custom\" class=\"__sstat-title\">Custom \
+             <a class=\"__sstat-heading-anchor\" href=\"#custom\">¶</a></h2>\n\
+             \t</section>\n\
+             </main>\n"
+        );
+
+        // Pages can opt out of anchors enabled by the configuration
+        let config = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[heading_anchors]\n[document fragment]".to_owned(),
+        )
+        .unwrap();
+        assert!(config.heading_anchors);
+
+        let input = "[heading_anchors false] (doc (title Intro))";
+        let output = transpile("filepath".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<main class=\"__sstat-main\">\n\
+             \t<h1 class=\"__sstat-title\" id=\"intro\">Intro</h1>\n\
+             </main>\n"
+        );
+
+        let input = "[heading_anchors yes] (doc)";
+        let result = transpile("filepath".to_owned(), input.to_owned(), &config);
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::InvalidAttributeValue { name, .. } if name == "heading_anchors");
+    }
+
+    #[test]
+    fn render_debug_positions() {
+        let input = "(doc\n  (p Some (b bold) text)\n  (image [src a.png] [alt A] [caption B]))";
+
+        let config = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[debug]\n[document fragment]\n[class_prefix none]".to_owned(),
+        )
+        .unwrap();
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<main data-sstat-src=\"page.sstat:1:1\">\n\
+             \t<p data-sstat-src=\"page.sstat:2:3\">Some <b data-sstat-src=\"page.sstat:2:11\">bold</b> text</p>\n\
+             \t<figure id=\"figure-1\" data-sstat-src=\"page.sstat:3:3\">\n\
+             \t\t<img data-sstat-src=\"page.sstat:3:3\" src=\"a.png\" alt=\"A\">\n\
+             \t\t<figcaption data-sstat-src=\"page.sstat:3:3\"><span data-sstat-src=\"page.sstat:3:3\">Figure 1:</span> B</figcaption>\n\
+             \t</figure>\n\
+             </main>\n"
+        );
+        assert_eq!(output.source_map, None);
+    }
+
+    #[test]
+    fn source_map() {
+        let input = "[title Mapped]\n(doc (sec (title Intro) (p Some (i text))))";
+
+        let config = Config {
+            source_map: true,
+            ..Config::default()
+        };
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+        let map: serde_json::Value = serde_json::from_str(&output.source_map.unwrap()).unwrap();
+        let mappings = map["mappings"].as_array().unwrap();
+
+        // Every node is mapped, including the text in it
+        assert_eq!(mappings.len(), 9);
+        for mapping in mappings {
+            let html = &output.content[mapping["html"][0].as_u64().unwrap() as usize
+                ..mapping["html"][1].as_u64().unwrap() as usize];
+            let source = &input[mapping["span"][0].as_u64().unwrap() as usize
+                ..mapping["span"][1].as_u64().unwrap() as usize];
+
+            assert_eq!(mapping["file"], "page.sstat");
+            assert_eq!(
+                html.starts_with('<'),
+                source.starts_with('('),
+                "{html:?} {source:?}"
+            );
+        }
+
+        // The `<main>` element is found inside the complete document
+        let main = &mappings[0];
+        let start = main["html"][0].as_u64().unwrap() as usize;
+        assert!(output.content[start..].starts_with("<main"));
+        assert_eq!(main["start"], serde_json::json!({ "line": 2, "column": 1 }));
+        assert_eq!(main["end"], serde_json::json!({ "line": 2, "column": 44 }));
+
+        let italic = mappings
+            .iter()
+            .find(|mapping| mapping["span"] == serde_json::json!([47, 55]))
+            .unwrap();
+        let start = italic["html"][0].as_u64().unwrap() as usize;
+        let end = italic["html"][1].as_u64().unwrap() as usize;
+        assert_eq!(
+            &output.content[start..end],
+            "<i class=\"__sstat-italic\">text</i>"
+        );
+    }
+
+    #[test]
+    fn stream_output() {
+        let input = "[title Streamed]\n(doc (sec (title Intro) (p Some (i text) & more)))";
+        let config = Config {
+            source_map: true,
+            ..Config::default()
+        };
+        let expected = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+
+        let mut html = String::new();
+        let output = transpile_into(
+            "page.sstat".to_owned(),
+            input.to_owned(),
+            &config,
+            &mut html,
+        )
+        .unwrap();
+        assert_eq!(html, expected.content);
+        assert_eq!(output.source_map, expected.source_map);
+
+        let mut bytes = vec![];
+        transpile_to_writer(
+            "page.sstat".to_owned(),
+            input.to_owned(),
+            &config,
+            &mut bytes,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected.content);
+
+        // Errors of the writer are passed on
+        struct Full;
+        impl std::io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let result = transpile_to_writer("page.sstat".to_owned(), input.to_owned(), &config, Full);
+        assert_matches!(result, Err(crate::Error::Io(e)) if e.kind() == std::io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn safe_mode() {
+        let config = Config::parse(
+            "sstat.cfg".to_owned(),
+            "[safe]\n[document fragment]\n[class_prefix none]".to_owned(),
+        )
+        .unwrap();
+        assert!(config.safe);
+
+        let input = r#"(doc
+            (p [style color: red; position: fixed] Styled)
+            (image [src javascript:alert(1)] [alt A] [caption B])
+            (html <p onclick="alert(1)" title='a "b"'>Hi<script>alert("<b>")</script> <a href=" JaVa&#115;cript&colon;x">x</a> <a href="/page?a=1&amp;b=2">y</a></p>)
+            (html <span style="color: blue; background: url(x.png)">c</span> <!-- gone --> 1 < 2 <iframe src=x></iframe>))"#;
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "<main>\n\
+             \t<p style=\"color: red\">Styled</p>\n\
+             \t<figure id=\"figure-1\">\n\
+             \t\t<img src=\"\" alt=\"A\">\n\
+             \t\t<figcaption><span>Figure 1:</span> B</figcaption>\n\
+             \t</figure>\n\
+             \t<p title=\"a &quot;b&quot;\">Hi <a>x</a> <a href=\"/page?a=1&amp;b=2\">y</a></p>\n\
+             \t<span style=\"color: blue\">c</span>  1 &lt; 2 \n\
+             </main>\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings.as_slice(),
+            [
+                ParseErrorType::UnsafeStyle { declaration: position },
+                ParseErrorType::UnsafeUrl { url: image },
+                ParseErrorType::UnsafeAttribute { name: onclick },
+                ParseErrorType::UnsafeTag { tag: script },
+                ParseErrorType::UnsafeUrl { url: link },
+                ParseErrorType::UnsafeStyle { declaration: background },
+                ParseErrorType::UnsafeTag { tag: iframe },
+            ] if position == "position: fixed"
+                && image == "javascript:alert(1)"
+                && onclick == "onclick"
+                && script == "script"
+                && link == " JaVa&#115;cript&colon;x"
+                && background == "background: url(x.png)"
+                && iframe == "iframe"
+        );
+        assert!(output
+            .warnings
+            .iter()
+            .all(|w| w.severity == Severity::Warning));
+
+        // The span of a warning in an `html` node is that of the tag
+        let onclick = &output.warnings[2];
+        assert_eq!(
+            &input[onclick.span.start..onclick.span.end],
+            "<p onclick=\"alert(1)\" title='a \"b\"'>"
+        );
+
+        // Without safe mode, everything is kept
+        let output = transpile(
+            "page.sstat".to_owned(),
+            input.to_owned(),
+            &fragment_config(),
+        )
+        .unwrap();
+        assert!(output.content.contains("<script>"));
+        assert!(output.warnings.is_empty());
+    }
+
+    /// Renders the outline of a page, with a line for every title and
+    /// paragraph
+    #[derive(Default)]
+    struct Outline {
+        context: Context,
+        lines: Vec<String>,
+    }
+
+    impl Renderer for Outline {
+        fn context(&mut self) -> &mut Context {
+            &mut self.context
+        }
+
+        fn text(&mut self, text: &str) {
+            if let Some(line) = self.lines.last_mut() {
+                line.push_str(text);
+            }
+        }
+
+        fn title(&mut self, _attributes: &[Attribute], inner: &[Node], id: Option<&str>) {
+            let level = self.context.heading_level();
+            self.lines
+                .push(format!("{} {}: ", "#".repeat(level), id.unwrap_or("-")));
+            self.render_children(inner);
+        }
+
+        fn paragraph(&mut self, _attributes: &[Attribute], inner: &[Node]) {
+            self.lines.push(String::new());
+            self.render_children(inner);
+        }
+
+        fn admonition(&mut self, kind: Admonition, _attributes: &[Attribute], inner: &[Node]) {
+            self.lines.push(format!("{}: ", kind.name()));
+            self.render_children(inner);
+        }
+
+        fn table_cell(&mut self, _attributes: &[Attribute], inner: &[Node], _align: Option<Align>) {
+            self.lines
+                .push(format!("{:?} cell: ", self.context.table_part()));
+            self.render_children(inner);
+        }
+
+        fn image(&mut self, _attributes: &[Attribute], number: usize) {
+            self.lines.push(format!("figure {number}"));
+        }
+
+        fn media(&mut self, kind: Media, _: &[Attribute], _: &[Node], _: Option<&str>) {
+            self.lines.push(kind.name().to_owned());
+        }
+
+        fn codeblock(&mut self, _: &[Attribute], _: &str, _: Option<usize>) {}
+
+        fn html(&mut self, _: &str) {}
+
+        fn math(&mut self, _: &[Attribute], _: &MathExpr, _: &str, _: bool) {}
+
+        fn reference(&mut self, _: &[Attribute], _: &[Node], _: Option<&str>) {}
+
+        fn listof(&mut self, _: &[Attribute], _: &[ListofEntry]) {}
+
+        fn citation(&mut self, _: &[Attribute], _: &str) {}
+
+        fn bibliography(&mut self, _: &[Attribute], _: &[Reference]) {}
+
+        fn footnote_reference(&mut self, _: &[Attribute], _: usize, _: Option<&[Node]>) {}
+
+        fn footnotes(&mut self, _: &[Attribute], _: &[Footnote]) {}
+    }
+
+    #[test]
+    fn renderer_hooks() {
+        let input = "(doc
+            (title Top)
+            (sec (title Intro) (p Hello)
+                (sec (title [id deep] Deeper) (note Careful)))
+            (image [src a.png] [alt A] [caption B])
+            (table (thead (trow (tcell Name))) (trow (tcell Value))))";
+        let parser = Parser::new(SimpleFile::new("page.sstat".to_owned(), input.to_owned()));
+        let mut page = parser.parse().unwrap();
+        parser.layout_tables(&mut page).unwrap();
+        parser.number_nodes(&mut page, &Config::default()).unwrap();
+        parser.assign_heading_ids(&mut page, &Config::default());
+
+        let mut outline = Outline::default();
+        outline.render_children(&page.doc.nodes);
+        let lines: Vec<_> = outline.lines.iter().map(|l| l.trim()).collect();
+        assert_eq!(
+            lines,
+            [
+                "# top: Top",
+                "## intro: Intro",
+                "Hello",
+                "### deep: Deeper",
+                "note: Careful",
+                "figure 1",
+                "Head cell: Name",
+                "Body cell: Value",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_format() {
+        let config = Config::parse("sstat.cfg".to_owned(), "[format html]".to_owned()).unwrap();
+        assert_eq!(config.format, Format::Html);
+
+        let result = Config::parse("sstat.cfg".to_owned(), "[format htm]".to_owned());
+        let Err(crate::Error::ParseError(error)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_matches!(&error.ty, ParseErrorType::InvalidAttributeValue { name, .. } if name == "format");
+        assert_eq!(error.context, vec!["did you mean 'html'?"]);
+    }
+
+    #[test]
+    fn safe_mode_paths() {
+        let dir = TempDir::new("sstat-safe-paths");
+        std::fs::create_dir_all(dir.join("pages")).unwrap();
+        std::fs::write(dir.join("secret.csv"), "secret\n").unwrap();
+        std::fs::write(dir.join("pages/data.csv"), "public\n").unwrap();
+
+        let config = Config {
+            safe: true,
+            document: Document::Fragment,
+            ..Config::default()
+        };
+        let transpile_page = |src: &str| {
+            transpile(
+                dir.join("pages/page.sstat").display().to_string(),
+                src.to_owned(),
+                &config,
+            )
+        };
+
+        for (src, path) in [
+            ("(doc (table [src /etc/hostname]))", "/etc/hostname"),
+            ("(doc (table [src ../secret.csv]))", "../secret.csv"),
+            ("[import ../secret.csv]\n(doc)", "../secret.csv"),
+            ("[layout ../secret]\n(doc)", "../secret"),
+        ] {
+            let Err(crate::Error::ParseError(error)) = transpile_page(src) else {
+                panic!("expected '{path}' to be rejected");
+            };
+            assert_matches!(&error.ty, ParseErrorType::UnsafePath { path: p } if p == path);
+        }
+
+        // Files next to the page can still be read
+        let output = transpile_page("(doc (table [src ./data.csv]))").unwrap();
+        assert!(output.content.contains("public"));
+    }
+
+    #[test]
+    fn render_markdown() {
+        let config = Config {
+            format: Format::Markdown,
+            ..Config::default()
+        };
+        let input = "(doc
+            (title Guide)
+            (p Some (b bold), (mono a`b) and (u underlined) text)
+            (sec (title Setup)
+                (p # is escaped)
+                (note Remember (i this).)
+                (codeblock [lang rust] fn main() {})
+                (table [align l r] (thead (trow (tcell Name) (tcell Value))) (trow (tcell a|b) (tcell 1)))
+                (p [style color: red] Red)))";
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "# Guide\n\
+             \n\
+             Some **bold**, ``a`b`` and <u>underlined</u> text\n\
+             \n\
+             ## Setup\n\
+             \n\
+             \\# is escaped\n\
+             \n\
+             > [!NOTE]\n\
+             > Remember *this*.\n\
+             \n\
+             ```rust\n\
+             fn main() {}\n\
+             ```\n\
+             \n\
+             | Name | Value |\n\
+             | :-- | --: |\n\
+             | a\\|b | 1 |\n\
+             \n\
+             <p style=\"color: red\">Red</p>\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [
+                ParseErrorType::HtmlFallback { name: u, attribute: None, .. },
+                ParseErrorType::HtmlFallback { name: p, attribute: Some(style), .. },
+            ] if u == "u" && p == "p" && style == "style"
+        );
+    }
+
+    #[test]
+    fn render_markdown_lists_and_footnotes() {
+        let config = Config {
+            format: Format::Markdown,
+            ..Config::default()
+        };
+        let input = "(doc
+            (list (li - one (ordlist (li nested) (li (p two) (p paragraphs)))) (li (span three)))
+            (p See (fnoteref [ref_id a]) and (fnote inline (b note)).)
+            (fnotedef [id a] first)
+            (dlist (dterm term) (ddesc description))
+            (div (p block)))";
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+        assert_eq!(
+            output.content,
+            "- \\- one\n\
+             \x20 1. nested\n\
+             \x20 1. two\n\
+             \n\
+             \x20    paragraphs\n\
+             - three\n\
+             \n\
+             See [^1] and [^2].\n\
+             \n\
+             <dl>\n\
+             \t<dt>term</dt>\n\
+             \t<dd>description</dd>\n\
+             </dl>\n\
+             \n\
+             <div>\n\
+             \t<p>block</p>\n\
+             </div>\n\
+             \n\
+             [^1]: first\n\
+             \n\
+             [^2]: inline **note**\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [
+                ParseErrorType::HtmlFallback { name: dlist, .. },
+                ParseErrorType::HtmlFallback { name: div, .. },
+            ] if dlist == "dlist" && div == "div"
+        );
+    }
+
+    #[test]
+    fn render_latex() {
+        let config = Config {
+            format: Format::Latex,
+            ..Config::default()
+        };
+        let input = r"[author Ada]
+            (doc
+                (title Guide)
+                (p Costs 5$ & 10% of #1, see (ref [ref_id fig]) and (math x_1^2).)
+                (sec (title Setup)
+                    (note Remember (b this).)
+                    (table [align l r] (thead (trow (tcell Name) (tcell Value)))
+                        (trow (tcell [rowspan 2] a_b) (tcell 1)) (trow (tcell 2)))
+                    (sec (title Deeper)
+                        (image [id fig] [src a.png] [alt A] [caption Big {deal}])
+                        (html <b>x</b>))))";
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+
+        let (preamble, body) = output.content.split_once("\\begin{document}\n").unwrap();
+        assert!(preamble.starts_with("\\documentclass{article}\n"));
+        assert!(preamble.ends_with("\\title{Guide}\n\\author{Ada}\n\\date{}\n\n"));
+        assert_eq!(
+            body,
+            "\n\\maketitle\n\
+             \n\
+             Costs 5\\$ \\& 10\\% of \\#1, see Figure~\\ref{fig} and $x_1^2$.\n\
+             \n\
+             \\section{Setup}\\label{setup}\n\
+             \n\
+             \\begin{quote}\n\
+             \\textbf{Note:} Remember \\textbf{this}.\n\
+             \\end{quote}\n\
+             \n\
+             \\begin{center}\n\
+             \\begin{tabular}{lr}\n\
+             \\hline\n\
+             Name & Value \\\\\n\
+             \\hline\n\
+             \\multirow{2}{*}{a\\_b} & 1 \\\\\n \
+             & 2 \\\\\n\
+             \\hline\n\
+             \\end{tabular}\n\
+             \\end{center}\n\
+             \n\
+             \\subsection{Deeper}\\label{deeper}\n\
+             \n\
+             \\begin{figure}[htbp]\n\
+             \\centering\n\
+             \\includegraphics[width=\\linewidth]{a.png}\n\
+             \\caption{Big \\{deal\\}}\\label{fig}\n\
+             \\end{figure}\n\
+             \n\
+             \\end{document}\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [ParseErrorType::Unrepresentable { name, .. }] if name == "html"
+        );
+    }
+
+    #[test]
+    fn render_latex_ids_and_urls() {
+        let config = Config {
+            format: Format::Latex,
+            ..Config::default()
+        };
+        let input = r"(doc
+                (p see (ref [ref_id a#b]) and (ref [ref_id a#b] this))
+                (image [id a#b] [src my_image~1.png] [alt a] [caption c])
+                (image [src 50%.png] [alt a] [caption c])
+                (video [src https://example.com/a b{c}%20#t]))";
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+
+        let (_, body) = output.content.split_once("\\begin{document}\n").unwrap();
+        assert_eq!(
+            body,
+            "\n\
+             see Figure~\\ref{a+23b} and \\hyperref[a+23b]{this}\n\
+             \n\
+             \\begin{figure}[htbp]\n\
+             \\centering\n\
+             \\includegraphics[width=\\linewidth]{\\detokenize{my_image~1.png}}\n\
+             \\caption{c}\\label{a+23b}\n\
+             \\end{figure}\n\
+             \n\
+             \\url{https://example.com/a\\%20b\\%7Bc\\%7D\\%20\\#t}\n\
+             \n\
+             \\end{document}\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [ParseErrorType::Unrepresentable { name, .. }] if name == "image"
+        );
+    }
+
+    #[test]
+    fn render_latex_lists_and_footnotes() {
+        let config = Config {
+            format: Format::Latex,
+            ..Config::default()
+        };
+        let input = r"(doc
+                (p See(fnote First) and(fnoteref [ref_id a]) again(fnoteref [ref_id a]).)
+                (fnotedef [id a] Shared)
+                (fnotes)
+                (list (li One) (li Two (ordlist (li Inner))))
+                (dlist (dterm Term]) (ddesc Text) (ddesc More))
+                (list)
+                (codeblock \end{verbatim}))";
+        let output = transpile("page.sstat".to_owned(), input.to_owned(), &config).unwrap();
+
+        let (_, body) = output.content.split_once("\\begin{document}\n").unwrap();
+        assert_eq!(
+            body,
+            "\n\
+             See\\footnote[1]{First} and\\footnote[2]{Shared} again\\footnotemark[2].\n\
+             \n\
+             \\begin{itemize}\n\
+             \\item One\n\
+             \\item Two\n\
+             \\begin{enumerate}\n\
+             \\item Inner\n\
+             \\end{enumerate}\n\
+             \\end{itemize}\n\
+             \n\
+             \\begin{description}\n\
+             \\item[{Term]}] Text\n\
+             \\item More\n\
+             \\end{description}\n\
+             \n\
+             \\end{document}\n"
+        );
+
+        let warnings: Vec<_> = output.warnings.iter().map(|w| &w.ty).collect();
+        assert_matches!(
+            warnings[..],
+            [ParseErrorType::Unrepresentable { name, .. }] if name == "codeblock"
+        );
+    }
 }
@@ -1,18 +1,120 @@
 use std::env::args;
 use std::fs::File;
-use std::io::Read;
+use std::io::{stdout, BufWriter, Read, Write};
 
-use sstat::transpile;
+use sstat::{stylesheet, transpile_to_writer, Config, Document, Format};
 
-fn main() {
-    let filename = args().nth(1).unwrap();
-    let mut file = File::open(filename.clone()).unwrap();
+fn read_file(filename: &str) -> String {
+    let mut file = File::open(filename).unwrap();
 
     let mut source = String::new();
     file.read_to_string(&mut source).unwrap();
 
-    match transpile(filename, source) {
-        Ok(_) => (),
-        Err(e) => eprintln!("{}", e),
+    source
+}
+
+fn main() {
+    let mut filename = None;
+    let mut config = Config::default();
+    let mut flags = vec![];
+    let mut print_stylesheet = false;
+    let mut fragment = false;
+    let mut debug = false;
+    let mut safe = false;
+    let mut source_map = None;
+    let mut format = None;
+    let mut output = None;
+
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                let config_filename = args.next().unwrap();
+                let source = read_file(&config_filename);
+
+                config = match Config::parse(config_filename, source) {
+                    Ok(config) => config,
+                    Err(e) => return eprintln!("{}", e),
+                };
+            }
+            "--set" => {
+                let flag = args.next().unwrap();
+                let (name, value) = flag.split_once('=').unwrap_or((&flag, ""));
+
+                flags.push((name.to_owned(), value.to_owned()));
+            }
+            "--stylesheet" => print_stylesheet = true,
+            "--fragment" => fragment = true,
+            "--debug" => debug = true,
+            "--safe" => safe = true,
+            "--source-map" => source_map = Some(args.next().unwrap()),
+            "--format" => {
+                let name = args.next().unwrap();
+
+                format = match Format::from_name(&name) {
+                    Some(format) => Some(format),
+                    None => {
+                        return eprintln!(
+                            "unknown format '{name}', expected one of: {}",
+                            Format::NAMES.join(", ")
+                        )
+                    }
+                };
+            }
+            "--output" | "-o" => output = Some(args.next().unwrap()),
+            _ => filename = Some(arg),
+        }
+    }
+
+    // Flags given on the command line override those in the configuration
+    config.flags.extend(flags);
+    if fragment {
+        config.document = Document::Fragment;
+    }
+    if let Some(format) = format {
+        config.format = format;
+    }
+    config.debug |= debug;
+    config.safe |= safe;
+    config.source_map |= source_map.is_some();
+
+    // The stylesheet is the same for every page, so it is emitted on its own
+    if print_stylesheet {
+        return print!("{}", stylesheet(&config));
+    }
+
+    let filename = filename.unwrap();
+    let source = read_file(&filename);
+
+    // The page is written out as it is rendered, instead of being built in
+    // memory first. A file is only replaced once the page rendered without
+    // errors, so that a failed build leaves the previous one intact
+    let temporary = output.as_ref().map(|path| format!("{path}.tmp"));
+    let writer: Box<dyn Write> = match &temporary {
+        Some(path) => Box::new(BufWriter::new(File::create(path).unwrap())),
+        None => Box::new(BufWriter::new(stdout().lock())),
+    };
+
+    match transpile_to_writer(filename, source, &config, writer) {
+        Ok(result) => {
+            if let (Some(temporary), Some(path)) = (temporary, output) {
+                std::fs::rename(temporary, path).unwrap();
+            }
+
+            for warning in result.warnings {
+                eprintln!("{}", warning);
+            }
+
+            if let (Some(path), Some(map)) = (source_map, result.source_map) {
+                std::fs::write(path, map).unwrap();
+            }
+        }
+        Err(e) => {
+            if let Some(temporary) = temporary {
+                let _ = std::fs::remove_file(temporary);
+            }
+
+            eprintln!("{}", e)
+        }
     }
 }